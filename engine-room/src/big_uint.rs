@@ -0,0 +1,148 @@
+//! Unsigned integers without an upper bound, used for counts that outgrow `u64`
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    ops::{Add, AddAssign},
+};
+
+/// Each limb holds 9 decimal digits, which keeps printing in base 10 simple
+const LIMB_BASE: u32 = 1_000_000_000;
+
+/// # Big Uint
+///
+/// An unsigned integer of any size. Only the operations needed for counting are supported.
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::big_uint::BigUint;
+///
+/// let max = BigUint::from(u64::MAX);
+/// assert_eq!((&max + &max).to_string(), "36893488147419103230");
+/// assert_eq!(max.to_u64(), Some(u64::MAX));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /// The limbs in base `LIMB_BASE`, least significant first, without leading zero limbs
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// The value as a `u64`, or `None` when it does not fit
+    pub fn to_u64(&self) -> Option<u64> {
+        self.limbs.iter().rev().try_fold(0u64, |value, &limb| {
+            value
+                .checked_mul(LIMB_BASE as u64)?
+                .checked_add(limb as u64)
+        })
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(mut value: u64) -> Self {
+        let mut limbs = vec![];
+        while value > 0 {
+            limbs.push((value % LIMB_BASE as u64) as u32);
+            value /= LIMB_BASE as u64;
+        }
+        BigUint { limbs }
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, rhs: &BigUint) {
+        if self.limbs.len() < rhs.limbs.len() {
+            self.limbs.resize(rhs.limbs.len(), 0);
+        }
+        let mut carry = 0;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb + rhs.limbs.get(i).copied().unwrap_or(0) + carry;
+            (*limb, carry) = (sum % LIMB_BASE, sum / LIMB_BASE);
+            if carry == 0 && i >= rhs.limbs.len() {
+                break;
+            }
+        }
+        if carry > 0 {
+            self.limbs.push(carry);
+        }
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let mut sum = self.clone();
+        sum += rhs;
+        sum
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((most_significant, rest)) = self.limbs.split_last() else {
+            return write!(f, "0");
+        };
+        write!(f, "{most_significant}")?;
+        for limb in rest.iter().rev() {
+            write!(f, "{limb:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod big_uint_tests {
+    use super::*;
+
+    #[test]
+    fn addition_carries() {
+        let mut value = BigUint::from(999_999_999_999_999_999);
+        value += &BigUint::from(1);
+        assert_eq!(value.to_string(), "1000000000000000000");
+        assert_eq!(value.to_u64(), Some(1_000_000_000_000_000_000));
+
+        // Doubling 2^64 - 1 a few times no longer fits in a u64
+        let mut value = BigUint::from(u64::MAX);
+        for _ in 0..3 {
+            value = &value + &value;
+        }
+        assert_eq!(value.to_string(), "147573952589676412920");
+        assert_eq!(value.to_u64(), None);
+    }
+
+    #[test]
+    fn zero_and_order() {
+        assert!(BigUint::zero().is_zero());
+        assert_eq!(BigUint::zero(), BigUint::from(0));
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from(7).to_string(), "7");
+        assert!(BigUint::from(1_000_000_000) > BigUint::from(999_999_999));
+        assert!(BigUint::from(5) < BigUint::from(6));
+        assert_eq!(&BigUint::zero() + &BigUint::from(3), BigUint::from(3));
+    }
+}
@@ -0,0 +1,580 @@
+//! Context free grammars over a machine's alphabet
+
+use crate::{regex::EPSILON, MachineError};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display},
+};
+
+mod cyk;
+mod ll1;
+mod lr;
+mod normal_form;
+mod regular;
+
+pub use cyk::ParseTree;
+pub use ll1::{Ll1Conflict, Ll1Table};
+pub use lr::{ConflictKind, Item, Lr0Automaton, LrAction, LrConflict, LrTable};
+pub use regular::{Linearity, RegularGrammar};
+
+/// Separates the head of a production from its bodies in grammar text
+pub const ARROW: &str = "->";
+
+/// # Symbol
+///
+/// A symbol in the body of a production. Terminals are indexes into the alphabet, the same `u16`
+/// characters used by every `StateMachine`, and nonterminals are indexes into
+/// [`Grammar::nonterminals`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Symbol {
+    Terminal(u16),
+    Nonterminal(u16),
+}
+
+/// # Production
+///
+/// A rule that lets `head` be replaced by `body`. An empty body is an epsilon production.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Production {
+    pub head: u16,
+    pub body: Vec<Symbol>,
+}
+
+impl Production {
+    /// Checks if the production replaces its head with the empty string
+    pub fn is_epsilon(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    /// Checks if the body is a single nonterminal
+    pub fn is_unit(&self) -> bool {
+        matches!(self.body[..], [Symbol::Nonterminal(_)])
+    }
+}
+
+/// # Grammar
+///
+/// A context free grammar. The nonterminals are numbered by their position in
+/// [`Grammar::nonterminals`], which holds their names, and the start symbol is always
+/// nonterminal 0. The terminals are the `chars` characters of the alphabet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    nonterminals: Vec<String>,
+    productions: Vec<Production>,
+    chars: u16,
+}
+
+/// # Grammar Error
+///
+/// Why grammar text could not be parsed. `line` is the index of the offending line and `position`
+/// the index of the offending character in that line, counted in characters rather than bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GrammarError {
+    pub line: usize,
+    pub position: usize,
+    pub kind: GrammarErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GrammarErrorKind {
+    /// The line has no `->` between the head and the bodies
+    MissingArrow,
+    /// The head before the `->` is empty or contains whitespace
+    InvalidHead,
+    /// The character is not a nonterminal, part of the alphabet or `ε`
+    UnknownSymbol(char),
+    /// One of the bodies separated by `|` is empty. The empty string is written `ε`.
+    EmptyBody,
+    /// The text does not contain any productions
+    NoProductions,
+    /// The text has more nonterminals than can be numbered by a `u16`
+    TooManyNonterminals,
+    /// The alphabet has no characters
+    NoChars,
+    /// The alphabet has more characters than can be numbered by a `u16`
+    TooManyChars,
+}
+
+impl Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, position) = (self.line, self.position);
+        match self.kind {
+            GrammarErrorKind::MissingArrow => {
+                write!(f, "line {line} is missing a '{ARROW}'")
+            }
+            GrammarErrorKind::InvalidHead => write!(
+                f,
+                "the head on line {line} must be a single name without whitespace"
+            ),
+            GrammarErrorKind::UnknownSymbol(c) => write!(
+                f,
+                "'{c}' at position {position} of line {line} is not a nonterminal or part of the alphabet"
+            ),
+            GrammarErrorKind::EmptyBody => write!(
+                f,
+                "empty body at position {position} of line {line}, write '{EPSILON}' for the empty string"
+            ),
+            GrammarErrorKind::NoProductions => write!(f, "the grammar has no productions"),
+            GrammarErrorKind::TooManyNonterminals => write!(
+                f,
+                "the grammar has more than {} nonterminals",
+                u16::MAX
+            ),
+            GrammarErrorKind::NoChars => write!(f, "the alphabet must have at least one character"),
+            GrammarErrorKind::TooManyChars => write!(
+                f,
+                "the alphabet has more than {} characters",
+                u16::MAX
+            ),
+        }
+    }
+}
+
+impl Error for GrammarError {}
+
+impl Grammar {
+    /// Builds a grammar with the given nonterminal names, the first of which is the start symbol.
+    /// Repeated productions are only kept once.
+    pub fn build(
+        nonterminals: Vec<String>,
+        productions: Vec<Production>,
+        chars: u16,
+    ) -> Result<Grammar, MachineError> {
+        if nonterminals.is_empty() {
+            return Err(MachineError::NoNonterminals);
+        }
+        if chars == 0 {
+            return Err(MachineError::NoChars);
+        }
+        let count =
+            u16::try_from(nonterminals.len()).map_err(|_| MachineError::TooManyNonterminals)?;
+        for production in &productions {
+            let body_nonterminals = production.body.iter().filter_map(|symbol| match symbol {
+                Symbol::Nonterminal(nonterminal) => Some(*nonterminal),
+                Symbol::Terminal(_) => None,
+            });
+            if let Some(nonterminal) = [production.head]
+                .into_iter()
+                .chain(body_nonterminals)
+                .find(|&nonterminal| nonterminal >= count)
+            {
+                return Err(MachineError::NonterminalOutOfRange {
+                    nonterminal,
+                    nonterminals: count,
+                });
+            }
+            if let Some(&Symbol::Terminal(char)) = production
+                .body
+                .iter()
+                .find(|symbol| matches!(symbol, &&Symbol::Terminal(char) if char >= chars))
+            {
+                return Err(MachineError::CharOutOfRange { char, chars });
+            }
+        }
+
+        Ok(Grammar::from_parts(nonterminals, productions, chars))
+    }
+
+    /// Parses grammar text where every character of `alphabet` stands for its index in the
+    /// alphabet.
+    ///
+    /// Each line holds a head, `->` and one or more bodies separated by `|`, like `S -> aSb | ε`.
+    /// The heads are the nonterminals, numbered in the order they first appear, so the head of
+    /// the first line is the start symbol. A head can have more than one line. In the bodies
+    /// whitespace is ignored, `ε` is the empty string and the names of nonterminals take
+    /// priority over alphabet characters, with longer names tried first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::cfg::Grammar;
+    ///
+    /// let grammar = Grammar::parse("S -> aSb | ε", &['a', 'b']).unwrap();
+    /// assert_eq!(grammar.productions().len(), 2);
+    /// assert!(grammar.generates(&[0, 0, 1, 1]).unwrap());
+    /// ```
+    pub fn parse(text: &str, alphabet: &[char]) -> Result<Grammar, GrammarError> {
+        let alphabet_error = |kind| GrammarError {
+            line: 0,
+            position: 0,
+            kind,
+        };
+        if alphabet.is_empty() {
+            return Err(alphabet_error(GrammarErrorKind::NoChars));
+        }
+        let chars = u16::try_from(alphabet.len())
+            .map_err(|_| alphabet_error(GrammarErrorKind::TooManyChars))?;
+        let lines: Vec<(usize, Vec<char>)> = text
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .enumerate()
+            .filter(|(_, line)| line.iter().any(|c| !c.is_whitespace()))
+            .collect();
+        let arrow: Vec<char> = ARROW.chars().collect();
+
+        // Every head has to be known before the bodies can be split into symbols
+        let mut nonterminals: Vec<String> = vec![];
+        let mut split_lines = vec![];
+        for (line, chars) in &lines {
+            let error = |position, kind| GrammarError {
+                line: *line,
+                position,
+                kind,
+            };
+            let arrow_position = chars
+                .windows(arrow.len())
+                .position(|window| window == arrow)
+                .ok_or_else(|| {
+                    let first = chars.iter().position(|c| !c.is_whitespace());
+                    error(first.unwrap_or(0), GrammarErrorKind::MissingArrow)
+                })?;
+            let head: String = chars[..arrow_position].iter().collect();
+            let head = head.trim();
+            if head.is_empty() || head.contains(char::is_whitespace) {
+                return Err(error(arrow_position, GrammarErrorKind::InvalidHead));
+            }
+            let index = match nonterminals.iter().position(|name| name == head) {
+                Some(index) => index,
+                None => {
+                    nonterminals.push(head.to_string());
+                    nonterminals.len() - 1
+                }
+            };
+            if index >= u16::MAX as usize {
+                return Err(error(0, GrammarErrorKind::TooManyNonterminals));
+            }
+            split_lines.push((*line, index as u16, arrow_position + arrow.len()));
+        }
+        if nonterminals.is_empty() {
+            return Err(GrammarError {
+                line: 0,
+                position: 0,
+                kind: GrammarErrorKind::NoProductions,
+            });
+        }
+
+        let mut names: Vec<(Vec<char>, u16)> = nonterminals
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.chars().collect(), index as u16))
+            .collect();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+        let mut productions = vec![];
+        for ((line, chars), (_, head, bodies_start)) in lines.iter().zip(split_lines) {
+            let mut body = vec![];
+            let mut empty = true;
+            let mut position = bodies_start;
+            loop {
+                let Some(&c) = chars.get(position) else {
+                    if empty {
+                        return Err(GrammarError {
+                            line: *line,
+                            position,
+                            kind: GrammarErrorKind::EmptyBody,
+                        });
+                    }
+                    productions.push(Production { head, body });
+                    break;
+                };
+
+                if c.is_whitespace() {
+                    position += 1;
+                    continue;
+                }
+                if c == '|' {
+                    if empty {
+                        return Err(GrammarError {
+                            line: *line,
+                            position,
+                            kind: GrammarErrorKind::EmptyBody,
+                        });
+                    }
+                    productions.push(Production {
+                        head,
+                        body: std::mem::take(&mut body),
+                    });
+                    empty = true;
+                    position += 1;
+                    continue;
+                }
+
+                empty = false;
+                if let Some((name, nonterminal)) = names
+                    .iter()
+                    .find(|(name, _)| chars[position..].starts_with(name))
+                {
+                    body.push(Symbol::Nonterminal(*nonterminal));
+                    position += name.len();
+                } else if c == EPSILON {
+                    position += 1;
+                } else if let Some(index) = alphabet.iter().position(|&a| a == c) {
+                    body.push(Symbol::Terminal(index as u16));
+                    position += 1;
+                } else {
+                    return Err(GrammarError {
+                        line: *line,
+                        position,
+                        kind: GrammarErrorKind::UnknownSymbol(c),
+                    });
+                }
+            }
+        }
+
+        Ok(Grammar::from_parts(nonterminals, productions, chars))
+    }
+
+    /// Writes the grammar in the syntax accepted by [`Grammar::parse`], using the character at
+    /// each index of `alphabet`. Every nonterminal with productions gets one line, in order.
+    pub fn to_text(&self, alphabet: &[char]) -> Result<String, MachineError> {
+        let mut lines = vec![];
+        for (head, name) in self.nonterminals.iter().enumerate() {
+            let bodies = self
+                .productions_of(head as u16)
+                .map(|production| {
+                    if production.is_epsilon() {
+                        return Ok(EPSILON.to_string());
+                    }
+                    self.symbols_text(&production.body, alphabet)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if !bodies.is_empty() {
+                lines.push(format!("{name} {ARROW} {}", bodies.join(" | ")));
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Writes `symbols` one after the other, using the character at each index of `alphabet` for
+    /// the terminals and the names of the nonterminals
+    fn symbols_text(&self, symbols: &[Symbol], alphabet: &[char]) -> Result<String, MachineError> {
+        symbols
+            .iter()
+            .map(|symbol| match *symbol {
+                Symbol::Terminal(char) => alphabet.get(char as usize).map(|c| c.to_string()).ok_or(
+                    MachineError::CharOutOfRange {
+                        char,
+                        chars: alphabet.len() as u16,
+                    },
+                ),
+                Symbol::Nonterminal(nonterminal) => {
+                    Ok(self.nonterminals[nonterminal as usize].clone())
+                }
+            })
+            .collect()
+    }
+
+    /// The names of the nonterminals, indexed by nonterminal
+    pub fn nonterminals(&self) -> &[String] {
+        &self.nonterminals
+    }
+
+    pub fn productions(&self) -> &[Production] {
+        &self.productions
+    }
+
+    /// The productions with `head` on the left side
+    pub fn productions_of(&self, head: u16) -> impl Iterator<Item = &Production> {
+        self.productions
+            .iter()
+            .filter(move |production| production.head == head)
+    }
+
+    /// The start symbol, which is always nonterminal 0
+    pub fn start(&self) -> u16 {
+        0
+    }
+
+    pub fn chars(&self) -> u16 {
+        self.chars
+    }
+
+    /// Builds a grammar from parts that are already known to be valid, dropping repeated
+    /// productions
+    fn from_parts(nonterminals: Vec<String>, productions: Vec<Production>, chars: u16) -> Grammar {
+        let mut seen = HashSet::new();
+        let productions = productions
+            .into_iter()
+            .filter(|production| seen.insert(production.clone()))
+            .collect();
+        Grammar {
+            nonterminals,
+            productions,
+            chars,
+        }
+    }
+}
+
+/// Adds a nonterminal named after `base` to `nonterminals`. See [`fresh_name`].
+fn add_nonterminal(nonterminals: &mut Vec<String>, base: &str) -> Result<u16, MachineError> {
+    let index = u16::try_from(nonterminals.len()).map_err(|_| MachineError::TooManyNonterminals)?;
+    nonterminals.push(fresh_name(nonterminals, base));
+    Ok(index)
+}
+
+/// `base` with `'` added until it is not the name of any of `nonterminals`
+fn fresh_name(nonterminals: &[String], base: &str) -> String {
+    let mut name = base.to_string();
+    while nonterminals.contains(&name) {
+        name.push('\'');
+    }
+    name
+}
+
+#[cfg(test)]
+mod cfg_tests {
+    use super::*;
+    use Symbol::{Nonterminal as N, Terminal as T};
+
+    #[test]
+    fn parse_productions() {
+        let grammar = Grammar::parse("S -> aSb | ε\n\nS -> A\nA -> b", &['a', 'b']).unwrap();
+
+        assert_eq!(grammar.nonterminals(), &["S", "A"]);
+        assert_eq!(grammar.chars(), 2);
+        assert_eq!(
+            grammar.productions(),
+            &[
+                Production {
+                    head: 0,
+                    body: vec![T(0), N(0), T(1)]
+                },
+                Production {
+                    head: 0,
+                    body: vec![]
+                },
+                Production {
+                    head: 0,
+                    body: vec![N(1)]
+                },
+                Production {
+                    head: 1,
+                    body: vec![T(1)]
+                },
+            ]
+        );
+        assert!(grammar.productions()[1].is_epsilon());
+        assert!(grammar.productions()[2].is_unit());
+    }
+
+    #[test]
+    fn parse_longest_name() {
+        let grammar = Grammar::parse("S -> S0 S | a\nS0 -> a S0", &['a']).unwrap();
+
+        assert_eq!(grammar.productions()[0].body, vec![N(1), N(0)]);
+        assert_eq!(grammar.productions()[2].body, vec![T(0), N(1)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let alphabet = ['a', 'b'];
+        let error = |text| Grammar::parse(text, &alphabet).unwrap_err();
+
+        assert_eq!(
+            error("S -> a\n  S a"),
+            GrammarError {
+                line: 1,
+                position: 2,
+                kind: GrammarErrorKind::MissingArrow
+            }
+        );
+        assert_eq!(error(" -> a").kind, GrammarErrorKind::InvalidHead);
+        assert_eq!(error("S T -> a").kind, GrammarErrorKind::InvalidHead);
+        assert_eq!(
+            error("S -> aXb"),
+            GrammarError {
+                line: 0,
+                position: 6,
+                kind: GrammarErrorKind::UnknownSymbol('X')
+            }
+        );
+        assert_eq!(
+            error("S -> a || b"),
+            GrammarError {
+                line: 0,
+                position: 8,
+                kind: GrammarErrorKind::EmptyBody
+            }
+        );
+        assert_eq!(error("S -> a |").kind, GrammarErrorKind::EmptyBody);
+        assert_eq!(error("\n  \n").kind, GrammarErrorKind::NoProductions);
+
+        // The alphabet has to fit the grammars that Grammar::build accepts
+        assert_eq!(
+            Grammar::parse("S -> ε", &[]).unwrap_err().kind,
+            GrammarErrorKind::NoChars
+        );
+        assert_eq!(
+            Grammar::parse("S -> a", &['a'; u16::MAX as usize + 1])
+                .unwrap_err()
+                .kind,
+            GrammarErrorKind::TooManyChars
+        );
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let alphabet = ['a', 'b', '(', ')'];
+        let texts = [
+            "S -> aSb | ε",
+            "S -> SS | (S) | ε",
+            "S -> AB\nA -> a | ε\nB -> b",
+        ];
+
+        for text in texts {
+            let grammar = Grammar::parse(text, &alphabet).unwrap();
+            assert_eq!(grammar.to_text(&alphabet).unwrap(), text);
+            assert_eq!(
+                Grammar::parse(&grammar.to_text(&alphabet).unwrap(), &alphabet).unwrap(),
+                grammar
+            );
+        }
+        assert_eq!(
+            Grammar::parse("S -> b", &alphabet)
+                .unwrap()
+                .to_text(&['a'])
+                .unwrap_err(),
+            MachineError::CharOutOfRange { char: 1, chars: 1 }
+        );
+    }
+
+    #[test]
+    fn build_errors() {
+        let production = |head, body: &[Symbol]| Production {
+            head,
+            body: body.to_vec(),
+        };
+
+        assert_eq!(
+            Grammar::build(vec![], vec![], 1).unwrap_err(),
+            MachineError::NoNonterminals
+        );
+        assert_eq!(
+            Grammar::build(vec!["S".into(); u16::MAX as usize + 1], vec![], 1).unwrap_err(),
+            MachineError::TooManyNonterminals
+        );
+        assert_eq!(
+            Grammar::build(vec!["S".into()], vec![production(0, &[N(1)])], 1).unwrap_err(),
+            MachineError::NonterminalOutOfRange {
+                nonterminal: 1,
+                nonterminals: 1
+            }
+        );
+        assert_eq!(
+            Grammar::build(vec!["S".into()], vec![production(0, &[T(2)])], 2).unwrap_err(),
+            MachineError::CharOutOfRange { char: 2, chars: 2 }
+        );
+        assert_eq!(
+            Grammar::build(
+                vec!["S".into()],
+                vec![production(0, &[T(0)]), production(0, &[T(0)])],
+                1
+            )
+            .unwrap()
+            .productions()
+            .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use super::{Grammar, Symbol};
+use crate::{machine_utils::validate_input, MachineError};
+
+/// `table[len - 1][start][nonterminal]` holds the index of the production and the split point
+/// used to derive the `len` characters from `start` from that nonterminal
+type CykTable = Vec<Vec<Vec<Option<(usize, usize)>>>>;
+
+/// # Parse Tree
+///
+/// A derivation of a string from a nonterminal of a [`Grammar`]. A nonterminal without children
+/// was replaced by the empty string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseTree {
+    Terminal(u16),
+    Nonterminal {
+        nonterminal: u16,
+        children: Vec<ParseTree>,
+    },
+}
+
+impl ParseTree {
+    /// The terminals at the leaves of the tree from left to right, which is the string it derives
+    pub fn word(&self) -> Vec<u16> {
+        let mut word = vec![];
+        let mut stack = vec![self];
+        while let Some(tree) = stack.pop() {
+            match tree {
+                &ParseTree::Terminal(char) => word.push(char),
+                ParseTree::Nonterminal { children, .. } => stack.extend(children.iter().rev()),
+            }
+        }
+        word
+    }
+}
+
+impl Grammar {
+    /// Decides if the grammar generates `input` with the CYK algorithm, returning a parse tree
+    /// from the start symbol when it does. The grammar must be in Chomsky normal form, see
+    /// [`Grammar::to_cnf`]. When several parse trees exist, the one with the shortest left part
+    /// at the highest split is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::cfg::{Grammar, ParseTree};
+    ///
+    /// let grammar = Grammar::parse("S -> AB\nA -> a\nB -> b", &['a', 'b']).unwrap();
+    /// let tree = grammar.cyk(&[0, 1]).unwrap().unwrap();
+    ///
+    /// assert_eq!(tree.word(), vec![0, 1]);
+    /// assert_eq!(grammar.cyk(&[1, 0]).unwrap(), None);
+    /// ```
+    pub fn cyk(&self, input: &[u16]) -> Result<Option<ParseTree>, MachineError> {
+        validate_input(input, self.chars)?;
+        if !self.is_cnf() {
+            return Err(MachineError::NotChomskyNormalForm);
+        }
+        if input.is_empty() {
+            return Ok(self
+                .productions_of(self.start())
+                .any(|production| production.is_epsilon())
+                .then_some(ParseTree::Nonterminal {
+                    nonterminal: self.start(),
+                    children: vec![],
+                }));
+        }
+
+        let n = input.len();
+        let nonterminals = self.nonterminals.len();
+        let mut table: CykTable = vec![vec![vec![None; nonterminals]; n]; n];
+        for (start, &char) in input.iter().enumerate() {
+            for (index, production) in self.productions.iter().enumerate() {
+                if production.body[..] == [Symbol::Terminal(char)] {
+                    table[0][start][production.head as usize].get_or_insert((index, 0));
+                }
+            }
+        }
+        for len in 2..=n {
+            for start in 0..=n - len {
+                for split in 1..len {
+                    for (index, production) in self.productions.iter().enumerate() {
+                        let [Symbol::Nonterminal(left), Symbol::Nonterminal(right)] =
+                            production.body[..]
+                        else {
+                            continue;
+                        };
+                        if table[split - 1][start][left as usize].is_some()
+                            && table[len - split - 1][start + split][right as usize].is_some()
+                        {
+                            table[len - 1][start][production.head as usize]
+                                .get_or_insert((index, split));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(table[n - 1][0][self.start() as usize]
+            .is_some()
+            .then(|| self.cyk_tree(&table, self.start(), 0, n)))
+    }
+
+    /// Checks if the grammar generates `input`, converting it into Chomsky normal form first if
+    /// needed
+    pub fn generates(&self, input: &[u16]) -> Result<bool, MachineError> {
+        if self.is_cnf() {
+            return Ok(self.cyk(input)?.is_some());
+        }
+        Ok(self.to_cnf()?.cyk(input)?.is_some())
+    }
+
+    /// Rebuilds the parse tree for the `len` characters from `start` derived from `nonterminal`
+    fn cyk_tree(&self, table: &CykTable, nonterminal: u16, start: usize, len: usize) -> ParseTree {
+        let (index, split) =
+            table[len - 1][start][nonterminal as usize].expect("Only derivable cells are visited");
+        let children = match self.productions[index].body[..] {
+            [Symbol::Nonterminal(left), Symbol::Nonterminal(right)] => vec![
+                self.cyk_tree(table, left, start, split),
+                self.cyk_tree(table, right, start + split, len - split),
+            ],
+            [Symbol::Terminal(char)] => vec![ParseTree::Terminal(char)],
+            _ => unreachable!("The grammar is in Chomsky normal form"),
+        };
+        ParseTree::Nonterminal {
+            nonterminal,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod cyk_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    const AB: [char; 2] = ['a', 'b'];
+
+    #[test]
+    fn parse_tree_shape() {
+        let grammar =
+            Grammar::parse("S -> AB | AC\nC -> XB\nX -> AB | AC\nA -> a\nB -> b", &AB).unwrap();
+        let tree = grammar.cyk(&[0, 0, 1, 1]).unwrap().unwrap();
+
+        let leaf = |nonterminal, char| ParseTree::Nonterminal {
+            nonterminal,
+            children: vec![ParseTree::Terminal(char)],
+        };
+        assert_eq!(
+            tree,
+            ParseTree::Nonterminal {
+                nonterminal: 0,
+                children: vec![
+                    leaf(3, 0),
+                    ParseTree::Nonterminal {
+                        nonterminal: 1,
+                        children: vec![
+                            ParseTree::Nonterminal {
+                                nonterminal: 2,
+                                children: vec![leaf(3, 0), leaf(4, 1)]
+                            },
+                            leaf(4, 1)
+                        ]
+                    }
+                ]
+            }
+        );
+        assert_eq!(tree.word(), vec![0, 0, 1, 1]);
+        assert_eq!(grammar.cyk(&[0, 1, 1]).unwrap(), None);
+    }
+
+    #[test]
+    fn empty_input() {
+        let cnf = Grammar::parse("S -> aSb | ε", &AB)
+            .unwrap()
+            .to_cnf()
+            .unwrap();
+
+        assert_eq!(
+            cnf.cyk(&[]).unwrap(),
+            Some(ParseTree::Nonterminal {
+                nonterminal: 0,
+                children: vec![]
+            })
+        );
+        let no_epsilon = Grammar::parse("S -> AA\nA -> a", &AB).unwrap();
+        assert_eq!(no_epsilon.cyk(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn trees_derive_input() {
+        // Palindromes
+        let grammar = Grammar::parse("S -> aSa | bSb | a | b | ε", &AB).unwrap();
+        let cnf = grammar.to_cnf().unwrap();
+        for input in all_inputs(2, 8) {
+            let reversed: Vec<u16> = input.iter().rev().copied().collect();
+            let tree = cnf.cyk(&input).unwrap();
+
+            assert_eq!(tree.is_some(), input == reversed, "{input:?}");
+            assert_eq!(grammar.generates(&input).unwrap(), input == reversed);
+            if let Some(tree) = tree {
+                assert_eq!(tree.word(), input);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let grammar = Grammar::parse("S -> aSb | ε", &AB).unwrap();
+
+        assert_eq!(
+            grammar.cyk(&[0, 1]).unwrap_err(),
+            MachineError::NotChomskyNormalForm
+        );
+        assert_eq!(
+            grammar.generates(&[2]).unwrap_err(),
+            MachineError::InvalidInputChar {
+                index: 0,
+                char: 2,
+                chars: 2
+            }
+        );
+    }
+}
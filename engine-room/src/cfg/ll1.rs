@@ -0,0 +1,270 @@
+use super::{Grammar, Symbol};
+use crate::machine_utils::table_lookup;
+
+use std::collections::BTreeSet;
+
+/// # Ll1 Conflict
+///
+/// A cell of an [`Ll1Table`] holding more than one production, so a predictive parser that sees
+/// `char` while expanding `nonterminal` can not decide which production to use.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ll1Conflict {
+    pub nonterminal: u16,
+    /// The lookahead character, where `chars` is the end of the input
+    pub char: u16,
+    /// The indexes of the productions in the cell
+    pub productions: Vec<usize>,
+}
+
+/// # Ll1 Table
+///
+/// The predictive parsing table of a grammar. There is a row for every nonterminal with a cell for
+/// every character, followed by one for the end of the input (index `chars`), so the cell for
+/// expanding `nonterminal` on `char` is at `nonterminal * (chars + 1) + char`. Each cell holds the
+/// indexes of the productions to expand with, and the grammar is LL(1) when no cell holds more
+/// than one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ll1Table {
+    cells: Vec<Vec<usize>>,
+    chars: u16,
+}
+
+impl Ll1Table {
+    /// The cells in the layout described by [`Ll1Table`]
+    pub fn cells(&self) -> &[Vec<usize>] {
+        &self.cells
+    }
+
+    /// The productions to expand `nonterminal` with when the next character is `char`, where
+    /// `chars` is the end of the input
+    pub fn cell(&self, nonterminal: u16, char: u16) -> &[usize] {
+        &self.cells[table_lookup(nonterminal as usize, char as usize, self.chars as usize + 1)]
+    }
+
+    /// Every cell with more than one production, in table order
+    pub fn conflicts(&self) -> Vec<Ll1Conflict> {
+        let stride = self.chars as usize + 1;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.len() > 1)
+            .map(|(index, cell)| Ll1Conflict {
+                nonterminal: (index / stride) as u16,
+                char: (index % stride) as u16,
+                productions: cell.clone(),
+            })
+            .collect()
+    }
+
+    /// Checks if no cell holds more than one production
+    pub fn is_ll1(&self) -> bool {
+        self.cells.iter().all(|cell| cell.len() <= 1)
+    }
+}
+
+impl Grammar {
+    /// The characters that can begin a string derived from each nonterminal, indexed by
+    /// nonterminal. Whether a nonterminal also derives the empty string is given by
+    /// [`Grammar::nullable_nonterminals`].
+    pub fn first_sets(&self) -> Vec<BTreeSet<u16>> {
+        let nullable = self.nullable_nonterminals();
+        let mut first = vec![BTreeSet::new(); self.nonterminals.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in &self.productions {
+                let (body_first, _) = first_of(&production.body, &first, &nullable);
+                let head = &mut first[production.head as usize];
+                let len = head.len();
+                head.extend(body_first);
+                changed |= head.len() != len;
+            }
+        }
+        first
+    }
+
+    /// The characters that can directly follow each nonterminal in a string derived from the start
+    /// symbol, indexed by nonterminal. The end of the input is the character `chars` and follows
+    /// the start symbol.
+    pub fn follow_sets(&self) -> Vec<BTreeSet<u16>> {
+        let nullable = self.nullable_nonterminals();
+        let first = self.first_sets();
+        let mut follow = vec![BTreeSet::new(); self.nonterminals.len()];
+        follow[self.start() as usize].insert(self.chars);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in &self.productions {
+                for (i, symbol) in production.body.iter().enumerate() {
+                    let &Symbol::Nonterminal(nonterminal) = symbol else {
+                        continue;
+                    };
+                    let (mut after, rest_nullable) =
+                        first_of(&production.body[i + 1..], &first, &nullable);
+                    if rest_nullable {
+                        after.extend(&follow[production.head as usize]);
+                    }
+                    let follow = &mut follow[nonterminal as usize];
+                    let len = follow.len();
+                    follow.extend(after);
+                    changed |= follow.len() != len;
+                }
+            }
+        }
+        follow
+    }
+
+    /// Builds the LL(1) predictive parsing table. Each production `A -> α` goes in the cells of
+    /// `A` for the characters in FIRST(α), and also for the characters in FOLLOW(A) when `α` can
+    /// derive the empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::cfg::Grammar;
+    ///
+    /// let grammar = Grammar::parse("S -> aSb | ε", &['a', 'b']).unwrap();
+    /// let table = grammar.ll1_table();
+    ///
+    /// assert!(table.is_ll1());
+    /// // Expand S with aSb on a, and with ε on b or the end of the input
+    /// assert_eq!(table.cell(0, 0), &[0]);
+    /// assert_eq!(table.cell(0, 1), &[1]);
+    /// assert_eq!(table.cell(0, 2), &[1]);
+    /// ```
+    pub fn ll1_table(&self) -> Ll1Table {
+        let nullable = self.nullable_nonterminals();
+        let first = self.first_sets();
+        let follow = self.follow_sets();
+        let stride = self.chars as usize + 1;
+        let mut cells = vec![vec![]; self.nonterminals.len() * stride];
+
+        for (index, production) in self.productions.iter().enumerate() {
+            let (mut chars, body_nullable) = first_of(&production.body, &first, &nullable);
+            if body_nullable {
+                chars.extend(&follow[production.head as usize]);
+            }
+            for char in chars {
+                cells[table_lookup(production.head as usize, char as usize, stride)].push(index);
+            }
+        }
+        Ll1Table {
+            cells,
+            chars: self.chars,
+        }
+    }
+}
+
+/// The characters that can begin a string derived from `symbols` and whether `symbols` can
+/// derive the empty string
+fn first_of(
+    symbols: &[Symbol],
+    first: &[BTreeSet<u16>],
+    nullable: &BTreeSet<u16>,
+) -> (BTreeSet<u16>, bool) {
+    let mut chars = BTreeSet::new();
+    for symbol in symbols {
+        match *symbol {
+            Symbol::Terminal(char) => {
+                chars.insert(char);
+                return (chars, false);
+            }
+            Symbol::Nonterminal(nonterminal) => {
+                chars.extend(&first[nonterminal as usize]);
+                if !nullable.contains(&nonterminal) {
+                    return (chars, false);
+                }
+            }
+        }
+    }
+    (chars, true)
+}
+
+#[cfg(test)]
+mod ll1_tests {
+    use super::*;
+
+    // + * ( ) i, with the end of the input at 5
+    const EXPRESSIONS: [char; 5] = ['+', '*', '(', ')', 'i'];
+    const END: u16 = 5;
+
+    fn expressions() -> Grammar {
+        Grammar::parse(
+            "E -> TX\nX -> +TX | ε\nT -> FY\nY -> *FY | ε\nF -> (E) | i",
+            &EXPRESSIONS,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn first_and_follow() {
+        let grammar = expressions();
+
+        assert_eq!(
+            grammar.first_sets(),
+            vec![
+                BTreeSet::from([2, 4]),
+                BTreeSet::from([0]),
+                BTreeSet::from([2, 4]),
+                BTreeSet::from([1]),
+                BTreeSet::from([2, 4]),
+            ]
+        );
+        assert_eq!(
+            grammar.follow_sets(),
+            vec![
+                BTreeSet::from([3, END]),
+                BTreeSet::from([3, END]),
+                BTreeSet::from([0, 3, END]),
+                BTreeSet::from([0, 3, END]),
+                BTreeSet::from([0, 1, 3, END]),
+            ]
+        );
+    }
+
+    #[test]
+    fn expression_table() {
+        let table = expressions().ll1_table();
+
+        assert!(table.is_ll1());
+        assert!(table.conflicts().is_empty());
+        assert_eq!(table.cells().len(), 5 * 6);
+        // E -> TX on ( and i
+        assert_eq!(table.cell(0, 2), &[0]);
+        assert_eq!(table.cell(0, 4), &[0]);
+        assert_eq!(table.cell(0, 0), &[] as &[usize]);
+        // X -> ε on ) and the end of the input
+        assert_eq!(table.cell(1, 3), &[2]);
+        assert_eq!(table.cell(1, END), &[2]);
+        // Y -> *FY on *
+        assert_eq!(table.cell(3, 1), &[4]);
+    }
+
+    #[test]
+    fn conflicts() {
+        // Both productions start with a
+        let table = Grammar::parse("S -> aS | a", &['a']).unwrap().ll1_table();
+        assert!(!table.is_ll1());
+        assert_eq!(
+            table.conflicts(),
+            vec![Ll1Conflict {
+                nonterminal: 0,
+                char: 0,
+                productions: vec![0, 1]
+            }]
+        );
+
+        // b starts one production of A and follows the other
+        let table = Grammar::parse("S -> Ab\nA -> b | ε", &['a', 'b'])
+            .unwrap()
+            .ll1_table();
+        assert_eq!(
+            table.conflicts(),
+            vec![Ll1Conflict {
+                nonterminal: 1,
+                char: 1,
+                productions: vec![1, 2]
+            }]
+        );
+    }
+}
@@ -0,0 +1,523 @@
+use super::{Grammar, Symbol};
+use crate::{dfa::Dfa, machine_utils::table_lookup, MachineError};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+/// Marks the position of the dot when writing an [`Item`]
+pub const DOT: char = '·';
+
+/// # Item
+///
+/// A production of the augmented grammar of an [`Lr0Automaton`] with a dot after the first `dot`
+/// symbols of its body, showing how much of the body has been seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Item {
+    pub production: usize,
+    pub dot: usize,
+}
+
+impl Item {
+    /// The symbol right after the dot, `None` when the whole body has been seen
+    pub fn next_symbol(&self, grammar: &Grammar) -> Option<Symbol> {
+        grammar.productions[self.production]
+            .body
+            .get(self.dot)
+            .copied()
+    }
+
+    /// Checks if the dot is at the end of the body, so the production can be reduced
+    pub fn is_complete(&self, grammar: &Grammar) -> bool {
+        self.dot == grammar.productions[self.production].body.len()
+    }
+
+    /// Writes the item like `S -> a·Sb`, using the character at each index of `alphabet`
+    pub fn to_text(&self, grammar: &Grammar, alphabet: &[char]) -> Result<String, MachineError> {
+        let production = &grammar.productions[self.production];
+        Ok(format!(
+            "{} {} {}{DOT}{}",
+            grammar.nonterminals[production.head as usize],
+            super::ARROW,
+            grammar.symbols_text(&production.body[..self.dot], alphabet)?,
+            grammar.symbols_text(&production.body[self.dot..], alphabet)?,
+        ))
+    }
+}
+
+/// # Lr Action
+///
+/// What a shift reduce parser does when it sees a character in a state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LrAction {
+    /// Consume the character and move to the state
+    Shift(usize),
+    /// Replace the body of the production on top of the stack by its head
+    Reduce(usize),
+    /// The whole input has been reduced to the start symbol
+    Accept,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictKind {
+    /// The parser could shift the character or reduce
+    ShiftReduce,
+    /// The parser could reduce by more than one production
+    ReduceReduce,
+}
+
+/// # Lr Conflict
+///
+/// A cell of an [`LrTable`] with more than one action, along with the items of the state that
+/// lead to those actions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LrConflict {
+    pub state: usize,
+    /// The lookahead character, where `chars` is the end of the input
+    pub char: u16,
+    pub kind: ConflictKind,
+    pub items: Vec<Item>,
+}
+
+/// # Lr Table
+///
+/// The action and goto tables of a shift reduce parser, built from an [`Lr0Automaton`]. The
+/// action table has a row for every state with a cell for every character, followed by one for the
+/// end of the input (index `chars`). The goto table has a row for every state with a cell for
+/// every nonterminal of the augmented grammar. Productions are numbered as in the augmented
+/// grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LrTable {
+    actions: Vec<Vec<LrAction>>,
+    gotos: Vec<Option<usize>>,
+    conflicts: Vec<LrConflict>,
+    chars: u16,
+    nonterminals: u16,
+}
+
+impl LrTable {
+    /// The actions for seeing `char` in `state`, where `chars` is the end of the input
+    pub fn actions(&self, state: usize, char: u16) -> &[LrAction] {
+        &self.actions[table_lookup(state, char as usize, self.chars as usize + 1)]
+    }
+
+    /// The state to move to after reducing to `nonterminal` when `state` is on top of the stack
+    pub fn goto(&self, state: usize, nonterminal: u16) -> Option<usize> {
+        self.gotos[table_lookup(state, nonterminal as usize, self.nonterminals as usize)]
+    }
+
+    /// Every cell with more than one action, in table order
+    pub fn conflicts(&self) -> &[LrConflict] {
+        &self.conflicts
+    }
+
+    /// Checks if every cell has at most one action
+    pub fn is_conflict_free(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// # Lr0 Automaton
+///
+/// The canonical collection of LR(0) item sets of a grammar. The grammar is augmented with a new
+/// start symbol, nonterminal 0, whose only production is production 0 to the old start symbol,
+/// so every other nonterminal and production is numbered one higher than in the original
+/// grammar. State 0 is the closure of the item for production 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lr0Automaton {
+    grammar: Grammar,
+    item_sets: Vec<BTreeSet<Item>>,
+    transitions: Vec<BTreeMap<Symbol, usize>>,
+}
+
+impl Grammar {
+    /// Builds the canonical collection of LR(0) item sets, numbering the sets in the order they
+    /// are found by a breadth first search from the start
+    pub fn lr0_automaton(&self) -> Result<Lr0Automaton, MachineError> {
+        let grammar = self.with_new_start()?;
+        let start = grammar.closure(BTreeSet::from([Item {
+            production: 0,
+            dot: 0,
+        }]));
+        let mut item_sets = vec![start.clone()];
+        let mut ids = BTreeMap::from([(start, 0)]);
+        let mut transitions = vec![];
+        let mut q = VecDeque::from([0]);
+
+        while let Some(state) = q.pop_front() {
+            let mut kernels: BTreeMap<Symbol, BTreeSet<Item>> = BTreeMap::new();
+            for item in &item_sets[state] {
+                if let Some(symbol) = item.next_symbol(&grammar) {
+                    kernels.entry(symbol).or_default().insert(Item {
+                        production: item.production,
+                        dot: item.dot + 1,
+                    });
+                }
+            }
+
+            let mut edges = BTreeMap::new();
+            for (symbol, kernel) in kernels {
+                let items = grammar.closure(kernel);
+                let next = match ids.get(&items) {
+                    Some(&next) => next,
+                    None => {
+                        item_sets.push(items.clone());
+                        ids.insert(items, item_sets.len() - 1);
+                        q.push_back(item_sets.len() - 1);
+                        item_sets.len() - 1
+                    }
+                };
+                edges.insert(symbol, next);
+            }
+            transitions.push(edges);
+        }
+
+        Ok(Lr0Automaton {
+            grammar,
+            item_sets,
+            transitions,
+        })
+    }
+
+    /// Adds the items with the dot at the start of every production of a nonterminal that
+    /// appears right after a dot
+    fn closure(&self, mut items: BTreeSet<Item>) -> BTreeSet<Item> {
+        let mut stack: Vec<Item> = items.iter().copied().collect();
+        while let Some(item) = stack.pop() {
+            let Some(Symbol::Nonterminal(nonterminal)) = item.next_symbol(self) else {
+                continue;
+            };
+            for (production, _) in self
+                .productions
+                .iter()
+                .enumerate()
+                .filter(|(_, production)| production.head == nonterminal)
+            {
+                let item = Item { production, dot: 0 };
+                if items.insert(item) {
+                    stack.push(item);
+                }
+            }
+        }
+        items
+    }
+}
+
+impl Lr0Automaton {
+    /// The augmented grammar the items refer to
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    /// The items of each state, indexed by state
+    pub fn item_sets(&self) -> &[BTreeSet<Item>] {
+        &self.item_sets
+    }
+
+    /// The state reached from `state` by seeing `symbol`, if any
+    pub fn transition(&self, state: usize, symbol: Symbol) -> Option<usize> {
+        self.transitions[state].get(&symbol).copied()
+    }
+
+    /// The character `symbol` is read as by [`Lr0Automaton::to_dfa`]. Terminals keep their
+    /// index and nonterminals follow them.
+    pub fn symbol_char(&self, symbol: Symbol) -> u16 {
+        match symbol {
+            Symbol::Terminal(char) => char,
+            Symbol::Nonterminal(nonterminal) => self.grammar.chars + nonterminal,
+        }
+    }
+
+    /// Builds a `Dfa` over the grammar symbols, numbered as in [`Lr0Automaton::symbol_char`],
+    /// with a state for every item set and a final dead state for the missing transitions. The
+    /// accept states are the item sets with a complete item, where the parser can reduce.
+    pub fn to_dfa(&self) -> Result<Dfa, MachineError> {
+        let symbols = self.grammar.chars as usize + self.grammar.nonterminals.len();
+        let chars = u16::try_from(symbols).map_err(|_| MachineError::TooManyNonterminals)?;
+        let states =
+            u16::try_from(self.item_sets.len() + 1).map_err(|_| MachineError::TooManyStates)?;
+        let dead = states - 1;
+
+        let mut transition_table = vec![dead; states as usize * chars as usize];
+        for (state, edges) in self.transitions.iter().enumerate() {
+            for (&symbol, &next) in edges {
+                transition_table
+                    [table_lookup(state, self.symbol_char(symbol) as usize, chars as usize)] =
+                    next as u16;
+            }
+        }
+        let accept_states: HashSet<u16> = (0..self.item_sets.len())
+            .filter(|&state| {
+                self.item_sets[state]
+                    .iter()
+                    .any(|item| item.is_complete(&self.grammar))
+            })
+            .map(|state| state as u16)
+            .collect();
+        Dfa::build(transition_table, accept_states, states, chars)
+    }
+
+    /// Builds the LR(0) parsing table, which reduces a complete item whatever the next character
+    /// is
+    pub fn lr0_table(&self) -> LrTable {
+        let lookahead: BTreeSet<u16> = (0..=self.grammar.chars).collect();
+        self.table(|_| lookahead.clone())
+    }
+
+    /// Builds the SLR(1) parsing table, which only reduces a complete item when the next character
+    /// is in the FOLLOW set of its head
+    pub fn slr1_table(&self) -> LrTable {
+        let follow = self.grammar.follow_sets();
+        self.table(|head| follow[head as usize].clone())
+    }
+
+    /// Builds a parsing table that reduces complete items with `head` on the characters in
+    /// `lookahead(head)`
+    fn table(&self, lookahead: impl Fn(u16) -> BTreeSet<u16>) -> LrTable {
+        let grammar = &self.grammar;
+        let stride = grammar.chars as usize + 1;
+        let nonterminals = grammar.nonterminals.len();
+        let mut actions = vec![vec![]; self.item_sets.len() * stride];
+        let mut gotos = vec![None; self.item_sets.len() * nonterminals];
+        // The items responsible for the actions in each cell
+        let mut causes: Vec<Vec<Item>> = vec![vec![]; actions.len()];
+
+        for (state, items) in self.item_sets.iter().enumerate() {
+            for (&symbol, &next) in &self.transitions[state] {
+                if let Symbol::Nonterminal(nonterminal) = symbol {
+                    gotos[table_lookup(state, nonterminal as usize, nonterminals)] = Some(next);
+                }
+            }
+
+            for &item in items {
+                let cells: Vec<(u16, LrAction)> = match item.next_symbol(grammar) {
+                    Some(Symbol::Terminal(char)) => {
+                        vec![(
+                            char,
+                            LrAction::Shift(self.transitions[state][&Symbol::Terminal(char)]),
+                        )]
+                    }
+                    Some(Symbol::Nonterminal(_)) => vec![],
+                    None if item.production == 0 => vec![(grammar.chars, LrAction::Accept)],
+                    None => lookahead(grammar.productions[item.production].head)
+                        .into_iter()
+                        .map(|char| (char, LrAction::Reduce(item.production)))
+                        .collect(),
+                };
+                for (char, action) in cells {
+                    let cell = table_lookup(state, char as usize, stride);
+                    if !actions[cell].contains(&action) {
+                        actions[cell].push(action);
+                    }
+                    causes[cell].push(item);
+                }
+            }
+        }
+
+        let conflicts = actions
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.len() > 1)
+            .map(|(index, cell)| LrConflict {
+                state: index / stride,
+                char: (index % stride) as u16,
+                kind: if cell
+                    .iter()
+                    .any(|action| matches!(action, LrAction::Shift(_)))
+                {
+                    ConflictKind::ShiftReduce
+                } else {
+                    ConflictKind::ReduceReduce
+                },
+                items: causes[index].clone(),
+            })
+            .collect();
+
+        LrTable {
+            actions,
+            gotos,
+            conflicts,
+            chars: grammar.chars,
+            nonterminals: nonterminals as u16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod lr_tests {
+    use super::*;
+    use crate::{machine_utils::all_inputs, StateMachine};
+
+    const EXPRESSIONS: [char; 3] = ['+', '*', 'i'];
+
+    fn expressions() -> Grammar {
+        Grammar::parse("E -> E+T | T\nT -> T*F | F\nF -> i", &EXPRESSIONS).unwrap()
+    }
+
+    /// Runs a shift reduce parser with `table`, taking the first action of every cell
+    fn parses(automaton: &Lr0Automaton, table: &LrTable, input: &[u16]) -> bool {
+        let grammar = automaton.grammar();
+        let mut stack = vec![0];
+        let mut position = 0;
+        loop {
+            let char = input.get(position).copied().unwrap_or(grammar.chars());
+            match table.actions(*stack.last().unwrap(), char).first() {
+                Some(&LrAction::Shift(next)) => {
+                    stack.push(next);
+                    position += 1;
+                }
+                Some(&LrAction::Reduce(production)) => {
+                    let production = &grammar.productions()[production];
+                    stack.truncate(stack.len() - production.body.len());
+                    match table.goto(*stack.last().unwrap(), production.head) {
+                        Some(next) => stack.push(next),
+                        None => return false,
+                    }
+                }
+                Some(LrAction::Accept) => return true,
+                None => return false,
+            }
+        }
+    }
+
+    #[test]
+    fn item_sets() {
+        let automaton = expressions().lr0_automaton().unwrap();
+        let grammar = automaton.grammar();
+
+        assert_eq!(grammar.nonterminals(), &["E'", "E", "T", "F"]);
+        assert_eq!(automaton.item_sets().len(), 9);
+        let start: Vec<String> = automaton.item_sets()[0]
+            .iter()
+            .map(|item| item.to_text(grammar, &EXPRESSIONS).unwrap())
+            .collect();
+        assert_eq!(
+            start,
+            vec![
+                "E' -> ·E",
+                "E -> ·E+T",
+                "E -> ·T",
+                "T -> ·T*F",
+                "T -> ·F",
+                "F -> ·i"
+            ]
+        );
+        let after_e = automaton.transition(0, Symbol::Nonterminal(1)).unwrap();
+        assert_eq!(
+            automaton.item_sets()[after_e],
+            BTreeSet::from([
+                Item {
+                    production: 0,
+                    dot: 1
+                },
+                Item {
+                    production: 1,
+                    dot: 1
+                }
+            ])
+        );
+        assert_eq!(automaton.transition(0, Symbol::Terminal(0)), None);
+    }
+
+    #[test]
+    fn item_dfa() {
+        let automaton = expressions().lr0_automaton().unwrap();
+        let dfa = automaton.to_dfa().unwrap();
+        let [plus, times, i] = [0, 1, 2];
+        let [e, t] = [1, 2].map(|n| automaton.symbol_char(Symbol::Nonterminal(n)));
+
+        assert_eq!(dfa.states(), 10);
+        assert_eq!(dfa.chars(), 3 + 4);
+        // Viable prefixes that end in a handle
+        assert!(dfa.accepts(&[i]).unwrap());
+        assert!(dfa.accepts(&[e, plus, t]).unwrap());
+        assert!(!dfa.accepts(&[e, plus]).unwrap());
+        assert!(!dfa.accepts(&[times]).unwrap());
+    }
+
+    #[test]
+    fn lr0_conflicts() {
+        let automaton = expressions().lr0_automaton().unwrap();
+        let grammar = automaton.grammar();
+        let table = automaton.lr0_table();
+
+        assert!(!table.is_conflict_free());
+        let conflict = &table.conflicts()[0];
+        assert_eq!(conflict.kind, ConflictKind::ShiftReduce);
+        assert_eq!(conflict.char, 1);
+        let items: Vec<String> = conflict
+            .items
+            .iter()
+            .map(|item| item.to_text(grammar, &EXPRESSIONS).unwrap())
+            .collect();
+        assert_eq!(items, vec!["E -> T·", "T -> T·*F"]);
+    }
+
+    #[test]
+    fn slr1_table() {
+        let grammar = expressions();
+        let automaton = grammar.lr0_automaton().unwrap();
+        let table = automaton.slr1_table();
+
+        assert!(table.is_conflict_free());
+        assert_eq!(table.actions(0, 2), &[LrAction::Shift(1)]);
+        assert_eq!(table.goto(0, 1), Some(2));
+        for input in all_inputs(3, 6) {
+            assert_eq!(
+                parses(&automaton, &table, &input),
+                grammar.generates(&input).unwrap(),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn reduce_reduce() {
+        let alphabet = ['a'];
+        let automaton = Grammar::parse("S -> A | B\nA -> a\nB -> a", &alphabet)
+            .unwrap()
+            .lr0_automaton()
+            .unwrap();
+        let table = automaton.slr1_table();
+
+        let conflicts = table.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::ReduceReduce);
+        assert_eq!(conflicts[0].char, 1);
+        let items: Vec<String> = conflicts[0]
+            .items
+            .iter()
+            .map(|item| item.to_text(automaton.grammar(), &alphabet).unwrap())
+            .collect();
+        assert_eq!(items, vec!["A -> a·", "B -> a·"]);
+        assert_eq!(
+            table.actions(conflicts[0].state, 1),
+            &[LrAction::Reduce(3), LrAction::Reduce(4)]
+        );
+    }
+
+    #[test]
+    fn epsilon_items() {
+        let alphabet = ['a', 'b'];
+        let automaton = Grammar::parse("S -> aSb | ε", &alphabet)
+            .unwrap()
+            .lr0_automaton()
+            .unwrap();
+        let grammar = automaton.grammar();
+
+        assert_eq!(
+            automaton.item_sets()[0]
+                .iter()
+                .map(|item| item.to_text(grammar, &alphabet).unwrap())
+                .collect::<Vec<_>>(),
+            vec!["S' -> ·S", "S -> ·aSb", "S -> ·"]
+        );
+        let table = automaton.slr1_table();
+        assert!(table.is_conflict_free());
+        for input in all_inputs(2, 6) {
+            assert_eq!(
+                parses(&automaton, &table, &input),
+                grammar.generates(&input).unwrap(),
+                "{input:?}"
+            );
+        }
+    }
+}
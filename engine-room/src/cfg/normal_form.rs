@@ -0,0 +1,470 @@
+use super::{add_nonterminal, fresh_name, Grammar, Production, Symbol};
+use crate::MachineError;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+impl Grammar {
+    /// The nonterminals that derive at least one string of terminals
+    pub fn generating_nonterminals(&self) -> BTreeSet<u16> {
+        let mut generating = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in &self.productions {
+                if !generating.contains(&production.head)
+                    && production.body.iter().all(|symbol| match symbol {
+                        Symbol::Terminal(_) => true,
+                        Symbol::Nonterminal(nonterminal) => generating.contains(nonterminal),
+                    })
+                {
+                    generating.insert(production.head);
+                    changed = true;
+                }
+            }
+        }
+        generating
+    }
+
+    /// The nonterminals that appear in some string derived from the start symbol
+    pub fn reachable_nonterminals(&self) -> BTreeSet<u16> {
+        let mut reachable = BTreeSet::from([self.start()]);
+        let mut stack = vec![self.start()];
+        while let Some(head) = stack.pop() {
+            for symbol in self.productions_of(head).flat_map(|p| &p.body) {
+                if let &Symbol::Nonterminal(nonterminal) = symbol {
+                    if reachable.insert(nonterminal) {
+                        stack.push(nonterminal);
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// The nonterminals that derive the empty string
+    pub fn nullable_nonterminals(&self) -> BTreeSet<u16> {
+        let mut nullable = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in &self.productions {
+                if !nullable.contains(&production.head)
+                    && production.body.iter().all(|symbol| match symbol {
+                        Symbol::Terminal(_) => false,
+                        Symbol::Nonterminal(nonterminal) => nullable.contains(nonterminal),
+                    })
+                {
+                    nullable.insert(production.head);
+                    changed = true;
+                }
+            }
+        }
+        nullable
+    }
+
+    /// Removes the nonterminals that do not generate any string, and then the ones that can not
+    /// be reached from the start symbol, along with every production that uses them. The start
+    /// symbol is always kept, without any productions if the language is empty. The remaining
+    /// nonterminals keep their order.
+    pub fn remove_useless(&self) -> Grammar {
+        let generating = self.generating_nonterminals();
+        let productions: Vec<Production> = self
+            .productions
+            .iter()
+            .filter(|production| {
+                generating.contains(&production.head)
+                    && production.body.iter().all(|symbol| match symbol {
+                        Symbol::Terminal(_) => true,
+                        Symbol::Nonterminal(nonterminal) => generating.contains(nonterminal),
+                    })
+            })
+            .cloned()
+            .collect();
+        let generating_only =
+            Grammar::from_parts(self.nonterminals.clone(), productions, self.chars);
+
+        let reachable = generating_only.reachable_nonterminals();
+        generating_only.retain_nonterminals(&reachable)
+    }
+
+    /// Removes every epsilon production without changing the language, except for the empty
+    /// string itself. Each production is replaced by every way of leaving out the nullable
+    /// nonterminals in its body. When the start symbol is nullable the empty string stays in the
+    /// language through a `start -> ε` production, after adding a new start symbol if the old one
+    /// appears in a body.
+    pub fn remove_epsilon(&self) -> Result<Grammar, MachineError> {
+        let nullable = self.nullable_nonterminals();
+        let grammar = if nullable.contains(&self.start()) && self.start_in_body() {
+            self.with_new_start()?
+        } else {
+            self.clone()
+        };
+        let nullable = grammar.nullable_nonterminals();
+
+        let mut productions = vec![];
+        for production in &grammar.productions {
+            let mut bodies = vec![vec![]];
+            for &symbol in &production.body {
+                let optional = matches!(symbol, Symbol::Nonterminal(n) if nullable.contains(&n));
+                let with: Vec<Vec<Symbol>> = bodies
+                    .iter()
+                    .map(|body| [&body[..], &[symbol]].concat())
+                    .collect();
+                if optional {
+                    bodies = with.into_iter().chain(bodies).collect();
+                } else {
+                    bodies = with;
+                }
+            }
+            productions.extend(
+                bodies
+                    .into_iter()
+                    .filter(|body| {
+                        !body.is_empty() && body[..] != [Symbol::Nonterminal(production.head)]
+                    })
+                    .map(|body| Production {
+                        head: production.head,
+                        body,
+                    }),
+            );
+        }
+        if nullable.contains(&grammar.start()) {
+            productions.push(Production {
+                head: grammar.start(),
+                body: vec![],
+            });
+        }
+        Ok(Grammar::from_parts(
+            grammar.nonterminals,
+            productions,
+            grammar.chars,
+        ))
+    }
+
+    /// Removes every unit production `A -> B` by giving `A` the other productions of every
+    /// nonterminal it can reach through unit productions
+    pub fn remove_unit(&self) -> Grammar {
+        let mut unit_edges: BTreeMap<u16, BTreeSet<u16>> = BTreeMap::new();
+        for production in self.productions.iter().filter(|p| p.is_unit()) {
+            if let [Symbol::Nonterminal(to)] = production.body[..] {
+                unit_edges.entry(production.head).or_default().insert(to);
+            }
+        }
+
+        let mut productions = vec![];
+        for head in 0..self.nonterminals.len() as u16 {
+            // Every nonterminal reachable from head through unit productions, head first
+            let mut reached = vec![head];
+            let mut seen = BTreeSet::from([head]);
+            let mut i = 0;
+            while let Some(&from) = reached.get(i) {
+                for &to in unit_edges.get(&from).into_iter().flatten() {
+                    if seen.insert(to) {
+                        reached.push(to);
+                    }
+                }
+                i += 1;
+            }
+
+            for from in reached {
+                productions.extend(
+                    self.productions_of(from)
+                        .filter(|production| !production.is_unit())
+                        .map(|production| Production {
+                            head,
+                            body: production.body.clone(),
+                        }),
+                );
+            }
+        }
+        Grammar::from_parts(self.nonterminals.clone(), productions, self.chars)
+    }
+
+    /// Checks if every production is `A -> BC` with neither `B` nor `C` the start symbol,
+    /// `A -> a`, or `start -> ε`
+    pub fn is_cnf(&self) -> bool {
+        self.productions
+            .iter()
+            .all(|production| match production.body[..] {
+                [Symbol::Terminal(_)] => true,
+                [Symbol::Nonterminal(first), Symbol::Nonterminal(second)] => {
+                    first != self.start() && second != self.start()
+                }
+                [] => production.head == self.start(),
+                _ => false,
+            })
+    }
+
+    /// Converts the grammar into Chomsky normal form, generating the same language. A new start
+    /// symbol is added if the old one appears in a body, then the epsilon, unit and useless
+    /// productions are removed. Finally terminals in longer bodies are replaced by new
+    /// nonterminals named `T` followed by the character, and bodies longer than two are split
+    /// into chains of new nonterminals named after the head.
+    pub fn to_cnf(&self) -> Result<Grammar, MachineError> {
+        let grammar = if self.start_in_body() {
+            self.with_new_start()?
+        } else {
+            self.clone()
+        };
+        let Grammar {
+            mut nonterminals,
+            productions,
+            chars,
+        } = grammar.remove_epsilon()?.remove_unit().remove_useless();
+
+        // Nonterminals that only derive a single terminal
+        let mut terminals: BTreeMap<u16, u16> = BTreeMap::new();
+        let mut cnf = vec![];
+        for production in productions {
+            if production.body.len() < 2 {
+                cnf.push(production);
+                continue;
+            }
+
+            let mut body = vec![];
+            for symbol in production.body {
+                body.push(match symbol {
+                    Symbol::Terminal(char) => Symbol::Nonterminal(match terminals.get(&char) {
+                        Some(&nonterminal) => nonterminal,
+                        None => {
+                            let nonterminal =
+                                add_nonterminal(&mut nonterminals, &format!("T{char}"))?;
+                            terminals.insert(char, nonterminal);
+                            cnf.push(Production {
+                                head: nonterminal,
+                                body: vec![symbol],
+                            });
+                            nonterminal
+                        }
+                    }),
+                    nonterminal => nonterminal,
+                });
+            }
+
+            let mut head = production.head;
+            for link in 1.. {
+                if body.len() <= 2 {
+                    break;
+                }
+                let name = format!("{}{link}", nonterminals[production.head as usize]);
+                let rest = add_nonterminal(&mut nonterminals, &name)?;
+                cnf.push(Production {
+                    head,
+                    body: vec![body.remove(0), Symbol::Nonterminal(rest)],
+                });
+                head = rest;
+            }
+            cnf.push(Production { head, body });
+        }
+        Ok(Grammar::from_parts(nonterminals, cnf, chars))
+    }
+
+    /// Checks if the start symbol appears in the body of any production
+    fn start_in_body(&self) -> bool {
+        self.productions
+            .iter()
+            .flat_map(|production| &production.body)
+            .any(|&symbol| symbol == Symbol::Nonterminal(self.start()))
+    }
+
+    /// Adds a new start symbol with a single production to the old one, which is named after the
+    /// old start symbol and becomes nonterminal 0
+    pub(super) fn with_new_start(&self) -> Result<Grammar, MachineError> {
+        if self.nonterminals.len() >= u16::MAX as usize {
+            return Err(MachineError::TooManyNonterminals);
+        }
+        let name = fresh_name(&self.nonterminals, &self.nonterminals[0]);
+
+        let shift = |symbol: &Symbol| match *symbol {
+            Symbol::Nonterminal(nonterminal) => Symbol::Nonterminal(nonterminal + 1),
+            terminal => terminal,
+        };
+        let productions = [Production {
+            head: 0,
+            body: vec![Symbol::Nonterminal(1)],
+        }]
+        .into_iter()
+        .chain(self.productions.iter().map(|production| Production {
+            head: production.head + 1,
+            body: production.body.iter().map(shift).collect(),
+        }))
+        .collect();
+        Ok(Grammar::from_parts(
+            [name]
+                .into_iter()
+                .chain(self.nonterminals.iter().cloned())
+                .collect(),
+            productions,
+            self.chars,
+        ))
+    }
+
+    /// Keeps the start symbol and the nonterminals in `keep`, renumbering them in order, along
+    /// with the productions that only use them
+    fn retain_nonterminals(&self, keep: &BTreeSet<u16>) -> Grammar {
+        let mut renumber = BTreeMap::new();
+        let mut nonterminals = vec![];
+        for (nonterminal, name) in self.nonterminals.iter().enumerate() {
+            let nonterminal = nonterminal as u16;
+            if nonterminal == self.start() || keep.contains(&nonterminal) {
+                renumber.insert(nonterminal, nonterminals.len() as u16);
+                nonterminals.push(name.clone());
+            }
+        }
+
+        let productions = self
+            .productions
+            .iter()
+            .filter_map(|production| {
+                Some(Production {
+                    head: *renumber.get(&production.head)?,
+                    body: production
+                        .body
+                        .iter()
+                        .map(|symbol| match *symbol {
+                            Symbol::Nonterminal(nonterminal) => {
+                                Some(Symbol::Nonterminal(*renumber.get(&nonterminal)?))
+                            }
+                            terminal => Some(terminal),
+                        })
+                        .collect::<Option<_>>()?,
+                })
+            })
+            .collect();
+        Grammar::from_parts(nonterminals, productions, self.chars)
+    }
+}
+
+#[cfg(test)]
+mod normal_form_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    const AB: [char; 2] = ['a', 'b'];
+
+    fn a_n_b_n(input: &[u16]) -> bool {
+        let zeros = input.iter().take_while(|&&c| c == 0).count();
+        zeros * 2 == input.len() && input[zeros..].iter().all(|&c| c == 1)
+    }
+
+    fn balanced(input: &[u16]) -> bool {
+        let mut depth = 0i32;
+        for &c in input {
+            depth += if c == 0 { 1 } else { -1 };
+            if depth < 0 {
+                return false;
+            }
+        }
+        depth == 0
+    }
+
+    fn assert_language(grammar: &Grammar, language: impl Fn(&[u16]) -> bool) {
+        let cnf = grammar.to_cnf().unwrap();
+        assert!(cnf.is_cnf(), "{}", cnf.to_text(&AB).unwrap());
+        for input in all_inputs(2, 8) {
+            assert_eq!(
+                cnf.cyk(&input).unwrap().is_some(),
+                language(&input),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn nonterminal_sets() {
+        let grammar =
+            Grammar::parse("S -> AB | C\nA -> a | ε\nB -> A\nC -> Cb\nD -> a", &AB).unwrap();
+
+        assert_eq!(
+            grammar.generating_nonterminals(),
+            BTreeSet::from([0, 1, 2, 4])
+        );
+        assert_eq!(
+            grammar.reachable_nonterminals(),
+            BTreeSet::from([0, 1, 2, 3])
+        );
+        assert_eq!(grammar.nullable_nonterminals(), BTreeSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn useless() {
+        let grammar = Grammar::parse("S -> AB | a\nA -> b\nB -> Bb\nC -> a", &AB).unwrap();
+        let useful = grammar.remove_useless();
+
+        assert_eq!(useful.to_text(&AB).unwrap(), "S -> a");
+        assert_eq!(useful.nonterminals(), &["S"]);
+
+        let empty = Grammar::parse("S -> aS\nA -> a", &AB).unwrap();
+        assert_eq!(empty.remove_useless().productions(), &[]);
+        assert_eq!(empty.remove_useless().nonterminals(), &["S"]);
+    }
+
+    #[test]
+    fn epsilon() {
+        let grammar = Grammar::parse("S -> AaA\nA -> b | ε", &AB).unwrap();
+        let removed = grammar.remove_epsilon().unwrap();
+
+        assert_eq!(
+            removed.to_text(&AB).unwrap(),
+            "S -> AaA | aA | Aa | a\nA -> b"
+        );
+
+        // The start symbol appears in a body, so a new one takes over the empty string
+        let grammar = Grammar::parse("S -> aSb | ε", &AB).unwrap();
+        let removed = grammar.remove_epsilon().unwrap();
+        assert_eq!(removed.to_text(&AB).unwrap(), "S' -> S | ε\nS -> aSb | ab");
+        assert_language(&removed, a_n_b_n);
+    }
+
+    #[test]
+    fn unit() {
+        let grammar = Grammar::parse("S -> A | a\nA -> B | bb\nB -> S | ab", &AB).unwrap();
+        let removed = grammar.remove_unit();
+
+        assert!(removed.productions().iter().all(|p| !p.is_unit()));
+        assert_eq!(
+            removed.to_text(&AB).unwrap(),
+            "S -> a | bb | ab\nA -> bb | ab | a\nB -> ab | a | bb"
+        );
+    }
+
+    #[test]
+    fn cnf_shape() {
+        let grammar = Grammar::parse("S -> ABa\nA -> a\nB -> b", &AB).unwrap();
+        let cnf = grammar.to_cnf().unwrap();
+
+        assert!(!grammar.is_cnf());
+        assert!(cnf.is_cnf());
+        assert_eq!(
+            cnf.to_text(&AB).unwrap(),
+            "S -> AS1\nA -> a\nB -> b\nT0 -> a\nS1 -> BT0"
+        );
+
+        // The start symbol appears in a body, so a new one is added
+        let cnf = Grammar::parse("S -> aSb | ε", &AB)
+            .unwrap()
+            .to_cnf()
+            .unwrap();
+        assert!(cnf.is_cnf());
+        assert_eq!(cnf.nonterminals()[..2], ["S'", "S"]);
+        assert!(cnf
+            .productions_of(0)
+            .any(|production| production.is_epsilon()));
+    }
+
+    #[test]
+    fn cnf_keeps_language() {
+        assert_language(&Grammar::parse("S -> aSb | ε", &AB).unwrap(), a_n_b_n);
+        assert_language(&Grammar::parse("S -> SS | aSb | ε", &AB).unwrap(), balanced);
+        assert_language(
+            &Grammar::parse("S -> aSbS | bSaS | ε", &AB).unwrap(),
+            |input| input.iter().filter(|&&c| c == 0).count() * 2 == input.len(),
+        );
+        assert_language(
+            &Grammar::parse("S -> A | SaB\nA -> B | a\nB -> b | C\nC -> Cb", &AB).unwrap(),
+            // Any character followed by any number of ab
+            |input| input.len() % 2 == 1 && input[1..].chunks(2).all(|pair| pair == [0, 1]),
+        );
+        assert_language(&Grammar::parse("S -> aS", &AB).unwrap(), |_| false);
+    }
+}
@@ -0,0 +1,357 @@
+use super::{Grammar, Production, Symbol};
+use crate::{
+    dfa::Dfa, e_nfa::EpsilonNfa, machine_utils::table_lookup, nfa::Nfa, MachineError, StateMachine,
+};
+
+use std::collections::HashSet;
+
+/// # Linearity
+///
+/// Where the nonterminals of a grammar appear in the bodies of its productions. A grammar is right
+/// linear when every body is a string of characters optionally followed by one nonterminal, and
+/// left linear when the nonterminal can only come first. A grammar where no body has a
+/// nonterminal, or only bodies that are a single nonterminal, is both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Linearity {
+    Right,
+    Left,
+    Both,
+    Neither,
+}
+
+impl Grammar {
+    /// Checks if every production is `A -> w` or `A -> wB`, where `w` is a string of characters
+    pub fn is_right_linear(&self) -> bool {
+        self.productions.iter().all(is_right_linear)
+    }
+
+    /// Checks if every production is `A -> w` or `A -> Bw`, where `w` is a string of characters
+    pub fn is_left_linear(&self) -> bool {
+        self.productions.iter().all(is_left_linear)
+    }
+
+    /// Whether the grammar is right linear, left linear, both or neither
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::cfg::{Grammar, Linearity};
+    ///
+    /// let alphabet = ['a', 'b'];
+    /// let linearity = |text| Grammar::parse(text, &alphabet).unwrap().linearity();
+    ///
+    /// assert_eq!(linearity("S -> abS | ε"), Linearity::Right);
+    /// assert_eq!(linearity("S -> Sab | ε"), Linearity::Left);
+    /// assert_eq!(linearity("S -> ab"), Linearity::Both);
+    /// assert_eq!(linearity("S -> aSb | ε"), Linearity::Neither);
+    /// ```
+    pub fn linearity(&self) -> Linearity {
+        match (self.is_right_linear(), self.is_left_linear()) {
+            (true, true) => Linearity::Both,
+            (true, false) => Linearity::Right,
+            (false, true) => Linearity::Left,
+            (false, false) => Linearity::Neither,
+        }
+    }
+}
+
+/// # Regular Grammar
+///
+/// A right linear [`Grammar`], which generates a regular language. These can be converted to and
+/// from finite automata, where each nonterminal stands for a state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegularGrammar {
+    grammar: Grammar,
+}
+
+impl TryFrom<Grammar> for RegularGrammar {
+    type Error = MachineError;
+
+    fn try_from(grammar: Grammar) -> Result<Self, Self::Error> {
+        if let Some(production) = grammar
+            .productions
+            .iter()
+            .position(|production| !is_right_linear(production))
+        {
+            return Err(MachineError::NotRightLinear { production });
+        }
+        Ok(RegularGrammar { grammar })
+    }
+}
+
+impl From<RegularGrammar> for Grammar {
+    fn from(value: RegularGrammar) -> Self {
+        value.grammar
+    }
+}
+
+impl RegularGrammar {
+    /// Converts a `Nfa` into a grammar with a nonterminal `Q{state}` for every state. Each
+    /// transition from `p` to `q` on `a` becomes `Qp -> aQq`, and each accept state `p` gets
+    /// `Qp -> ε`.
+    pub fn from_nfa(nfa: &Nfa) -> RegularGrammar {
+        let chars = nfa.chars() as usize;
+        Self::from_transitions(
+            nfa,
+            nfa.transition_table()
+                .iter()
+                .enumerate()
+                .flat_map(|(index, targets)| {
+                    let mut targets: Vec<u16> = targets.iter().copied().collect();
+                    targets.sort_unstable();
+                    targets
+                        .into_iter()
+                        .map(move |target| (index / chars, index % chars, target))
+                }),
+            nfa.accept_states(),
+        )
+    }
+
+    /// Converts a `Dfa` into a grammar in the same way as [`RegularGrammar::from_nfa`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{cfg::RegularGrammar, dfa::Dfa};
+    /// use std::collections::HashSet;
+    ///
+    /// // Strings with an odd number of a's
+    /// let dfa = Dfa::build(vec![1, 0, 0, 1], HashSet::from([1]), 2, 2).unwrap();
+    /// let grammar = RegularGrammar::from_dfa(&dfa);
+    ///
+    /// assert_eq!(
+    ///     grammar.grammar().to_text(&['a', 'b']).unwrap(),
+    ///     "Q0 -> aQ1 | bQ0\nQ1 -> aQ0 | bQ1 | ε"
+    /// );
+    /// ```
+    pub fn from_dfa(dfa: &Dfa) -> RegularGrammar {
+        let chars = dfa.chars() as usize;
+        Self::from_transitions(
+            dfa,
+            dfa.transition_table()
+                .iter()
+                .enumerate()
+                .map(|(index, &target)| (index / chars, index % chars, target)),
+            dfa.accept_states(),
+        )
+    }
+
+    /// Builds the grammar for a machine from its transitions as `(state, char, target)`
+    fn from_transitions(
+        machine: &impl StateMachine,
+        transitions: impl IntoIterator<Item = (usize, usize, u16)>,
+        accept_states: &HashSet<u16>,
+    ) -> RegularGrammar {
+        let nonterminals = (0..machine.states())
+            .map(|state| format!("Q{state}"))
+            .collect();
+        let mut productions: Vec<Production> = transitions
+            .into_iter()
+            .map(|(state, char, target)| Production {
+                head: state as u16,
+                body: vec![Symbol::Terminal(char as u16), Symbol::Nonterminal(target)],
+            })
+            .chain(accept_states.iter().map(|&state| Production {
+                head: state,
+                body: vec![],
+            }))
+            .collect();
+        // Keep the productions of each nonterminal together
+        productions.sort_by_key(|production| production.head);
+
+        RegularGrammar {
+            grammar: Grammar::from_parts(nonterminals, productions, machine.chars()),
+        }
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    /// Converts the grammar into a `EpsilonNfa`. Every nonterminal becomes the state with the same
+    /// number, and one more state is the only accept state. Each production `A -> a1...akB` is a
+    /// path from `A` to `B` reading `a1...ak` through new states, which is a single epsilon
+    /// transition when `k` is 0. Productions without a nonterminal end at the accept state instead.
+    /// The grammar itself always fits, but the `EpsilonNfa` may need more states than can be
+    /// numbered by a `u16`, which returns [`MachineError::TooManyStates`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{
+    ///     cfg::{Grammar, RegularGrammar},
+    ///     StateMachine,
+    /// };
+    ///
+    /// let grammar = Grammar::parse("S -> abS | A\nA -> bA | ε", &['a', 'b']).unwrap();
+    /// let e_nfa = RegularGrammar::try_from(grammar).unwrap().to_epsilon_nfa().unwrap();
+    ///
+    /// assert!(e_nfa.accepts(&[0, 1, 0, 1, 1]).unwrap());
+    /// assert!(!e_nfa.accepts(&[0, 1, 0]).unwrap());
+    /// ```
+    pub fn to_epsilon_nfa(&self) -> Result<EpsilonNfa, MachineError> {
+        let nonterminals = self.grammar.nonterminals.len();
+        let accept_state = nonterminals;
+        let chain_states: usize = self
+            .grammar
+            .productions
+            .iter()
+            .map(|production| match production.body.last() {
+                Some(Symbol::Nonterminal(_)) => production.body.len().saturating_sub(2),
+                _ => production.body.len().saturating_sub(1),
+            })
+            .sum();
+        let states = u16::try_from(nonterminals + 1 + chain_states)
+            .map_err(|_| MachineError::TooManyStates)?;
+        let chars = self.grammar.chars;
+        let stride = chars as usize + 1;
+
+        let mut transition_table = vec![HashSet::new(); states as usize * stride];
+        let mut next_state = accept_state + 1;
+        for production in &self.grammar.productions {
+            let (word, target) = match production.body.split_last() {
+                Some((&Symbol::Nonterminal(target), word)) => (word, target as usize),
+                _ => (&production.body[..], accept_state),
+            };
+            let mut state = production.head as usize;
+            for (i, symbol) in word.iter().enumerate() {
+                let &Symbol::Terminal(char) = symbol else {
+                    unreachable!("Only the last symbol of a right linear body is a nonterminal");
+                };
+                let to = if i + 1 == word.len() {
+                    target
+                } else {
+                    next_state += 1;
+                    next_state - 1
+                };
+                transition_table[table_lookup(state, char as usize, stride)].insert(to as u16);
+                state = to;
+            }
+            if word.is_empty() {
+                transition_table[table_lookup(state, chars as usize, stride)].insert(target as u16);
+            }
+        }
+        debug_assert_eq!(next_state, states as usize);
+
+        EpsilonNfa::build(
+            transition_table,
+            HashSet::from([accept_state as u16]),
+            states,
+            chars,
+        )
+    }
+
+    /// Converts the grammar into a `Nfa`, which has the same states as
+    /// [`RegularGrammar::to_epsilon_nfa`] with the epsilon transitions removed
+    pub fn to_nfa(&self) -> Result<Nfa, MachineError> {
+        Ok(self.to_epsilon_nfa()?.to_nfa())
+    }
+}
+
+fn is_right_linear(production: &Production) -> bool {
+    let word = match production.body.split_last() {
+        Some((Symbol::Nonterminal(_), word)) => word,
+        _ => &production.body[..],
+    };
+    word.iter()
+        .all(|symbol| matches!(symbol, Symbol::Terminal(_)))
+}
+
+fn is_left_linear(production: &Production) -> bool {
+    let word = match production.body.split_first() {
+        Some((Symbol::Nonterminal(_), word)) => word,
+        _ => &production.body[..],
+    };
+    word.iter()
+        .all(|symbol| matches!(symbol, Symbol::Terminal(_)))
+}
+
+#[cfg(test)]
+mod regular_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    const AB: [char; 2] = ['a', 'b'];
+
+    #[test]
+    fn linearity() {
+        let linearity = |text| Grammar::parse(text, &AB).unwrap().linearity();
+
+        assert_eq!(linearity("S -> aA | ε\nA -> bS | A"), Linearity::Right);
+        assert_eq!(linearity("S -> Aa | ε\nA -> Sb | A"), Linearity::Left);
+        assert_eq!(linearity("S -> A | ab\nA -> ε"), Linearity::Both);
+        // Each production is linear, but on different sides
+        assert_eq!(linearity("S -> aA\nA -> Sb"), Linearity::Neither);
+        assert_eq!(linearity("S -> AA\nA -> a"), Linearity::Neither);
+    }
+
+    #[test]
+    fn not_right_linear() {
+        let grammar = Grammar::parse("S -> aS | Sb | ε", &AB).unwrap();
+
+        assert_eq!(
+            RegularGrammar::try_from(grammar).unwrap_err(),
+            MachineError::NotRightLinear { production: 1 }
+        );
+    }
+
+    #[test]
+    fn grammar_to_automata() {
+        for text in [
+            "S -> abS | A\nA -> bA | ε",
+            "S -> aaS | bB | ε\nB -> bab | aS | B",
+            "S -> A\nA -> B\nB -> S | ba",
+            // A is useless and B is unreachable
+            "S -> aS | b | aA\nA -> bA\nB -> a",
+        ] {
+            let grammar = Grammar::parse(text, &AB).unwrap();
+            let regular = RegularGrammar::try_from(grammar.clone()).unwrap();
+            let e_nfa = regular.to_epsilon_nfa().unwrap();
+            let nfa = regular.to_nfa().unwrap();
+
+            assert_eq!(e_nfa.states(), nfa.states());
+            for input in all_inputs(2, 7) {
+                let generates = grammar.generates(&input).unwrap();
+                assert_eq!(
+                    e_nfa.accepts(&input).unwrap(),
+                    generates,
+                    "{text} {input:?}"
+                );
+                assert_eq!(nfa.accepts(&input).unwrap(), generates, "{text} {input:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn automata_to_grammar() {
+        // Strings ending in ab
+        let nfa = Nfa::build(
+            vec![
+                HashSet::from([0, 1]),
+                HashSet::from([0]),
+                HashSet::new(),
+                HashSet::from([2]),
+                HashSet::new(),
+                HashSet::new(),
+            ],
+            HashSet::from([2]),
+            3,
+            2,
+        )
+        .unwrap();
+        let grammar = RegularGrammar::from_nfa(&nfa);
+        assert_eq!(
+            grammar.grammar().to_text(&AB).unwrap(),
+            "Q0 -> aQ0 | aQ1 | bQ0\nQ1 -> bQ2\nQ2 -> ε"
+        );
+        let (dfa, _) = Dfa::from_nfa(&nfa).unwrap();
+        let from_dfa = RegularGrammar::from_dfa(&dfa);
+
+        for input in all_inputs(2, 7) {
+            let accepts = nfa.accepts(&input).unwrap();
+            assert_eq!(grammar.grammar().generates(&input).unwrap(), accepts);
+            assert_eq!(from_dfa.grammar().generates(&input).unwrap(), accepts);
+            assert_eq!(grammar.to_nfa().unwrap().accepts(&input).unwrap(), accepts);
+        }
+    }
+}
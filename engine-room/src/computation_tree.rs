@@ -0,0 +1,383 @@
+//! Every branch a nondeterministic finite automaton can take on an input
+
+use crate::{
+    e_nfa::EpsilonNfa, machine_utils::table_lookup, machine_utils::validate_input, nfa::Nfa,
+    MachineError, StateMachine,
+};
+use std::collections::{HashSet, VecDeque};
+
+/// # Branch Status
+///
+/// What happened to the computation at a node of a [`ComputationTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BranchStatus {
+    /// The computation continues in the children of the node. This includes a node that has
+    /// consumed the whole input outside of an accept state but can still take epsilon moves.
+    Running,
+    /// The whole input has been consumed in an accept state. The computation stops here, so the
+    /// node never has children.
+    Accepted,
+    /// The whole input has been consumed in a state that does not accept and no epsilon moves
+    /// are left to take
+    Rejected,
+    /// The input has not been consumed but there is no transition for the next character
+    Died,
+    /// The node budget ran out before all of the children of this node could be added
+    Truncated,
+}
+
+/// # Computation Node
+///
+/// The machine being in `state` after reading the first `consumed` characters of the input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ComputationNode {
+    pub state: u16,
+    pub consumed: usize,
+    /// The index of the node this one was reached from, `None` for the root
+    pub parent: Option<usize>,
+    /// The character read to reach this node from its parent, `None` for an epsilon move or the
+    /// root
+    pub read: Option<u16>,
+    pub children: Vec<usize>,
+    pub status: BranchStatus,
+}
+
+/// # Computation Tree
+///
+/// Every branch of a nondeterministic computation. The root is node 0 and the nodes are stored in
+/// breadth first order, so when the node budget runs out the shallowest part of the tree is kept.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ComputationTree {
+    pub nodes: Vec<ComputationNode>,
+    /// Whether any branch was cut off by the node budget
+    pub truncated: bool,
+}
+
+impl ComputationTree {
+    /// Checks if any branch of the tree accepts the input
+    pub fn accepts(&self) -> bool {
+        self.nodes
+            .iter()
+            .any(|node| node.status == BranchStatus::Accepted)
+    }
+
+    /// Builds the tree breadth first from the start state. `moves` returns the `(state, read)`
+    /// pairs that can be reached from a node, where `read` is `None` for an epsilon move.
+    fn build(
+        input: &[u16],
+        max_nodes: usize,
+        is_accept_state: impl Fn(u16) -> bool,
+        moves: impl Fn(&[ComputationNode], usize) -> Vec<(u16, Option<u16>)>,
+    ) -> ComputationTree {
+        let mut tree = ComputationTree {
+            nodes: vec![ComputationNode {
+                state: 0,
+                consumed: 0,
+                parent: None,
+                read: None,
+                children: vec![],
+                status: BranchStatus::Running,
+            }],
+            truncated: false,
+        };
+        let mut q = VecDeque::from([0]);
+
+        while let Some(index) = q.pop_front() {
+            let node = &tree.nodes[index];
+            let (state, consumed) = (node.state, node.consumed);
+            if consumed == input.len() && is_accept_state(state) {
+                tree.nodes[index].status = BranchStatus::Accepted;
+                continue;
+            }
+
+            let next = moves(&tree.nodes, index);
+            let mut status = if !next.is_empty() {
+                BranchStatus::Running
+            } else if consumed == input.len() {
+                BranchStatus::Rejected
+            } else {
+                BranchStatus::Died
+            };
+
+            for (next_state, read) in next {
+                if tree.nodes.len() >= max_nodes {
+                    status = BranchStatus::Truncated;
+                    tree.truncated = true;
+                    break;
+                }
+                let child = tree.nodes.len();
+                tree.nodes.push(ComputationNode {
+                    state: next_state,
+                    consumed: consumed + read.is_some() as usize,
+                    parent: Some(index),
+                    read,
+                    children: vec![],
+                    status: BranchStatus::Running,
+                });
+                tree.nodes[index].children.push(child);
+                q.push_back(child);
+            }
+            tree.nodes[index].status = status;
+        }
+
+        tree
+    }
+}
+
+impl Nfa {
+    /// Builds the tree of every path this machine can take on `input`, with at most `max_nodes`
+    /// nodes. Unlike [`StateMachine::trace_states`], which picks a single path, branches that die
+    /// or reject are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{computation_tree::BranchStatus, nfa::Nfa};
+    /// use std::collections::HashSet;
+    ///
+    /// // Accepts strings ending in 1
+    /// let nfa = Nfa::build(
+    ///     vec![HashSet::from([0]), HashSet::from([0, 1]), HashSet::new(), HashSet::new()],
+    ///     HashSet::from([1]),
+    ///     2,
+    ///     2,
+    /// )
+    /// .unwrap();
+    /// let tree = nfa.computation_tree(&[1, 1], 100).unwrap();
+    ///
+    /// // The branch that guessed the first 1 was the last one dies on the second 1
+    /// assert_eq!(tree.nodes.len(), 5);
+    /// assert_eq!(tree.nodes[2].status, BranchStatus::Died);
+    /// assert!(tree.accepts());
+    /// ```
+    pub fn computation_tree(
+        &self,
+        input: &[u16],
+        max_nodes: usize,
+    ) -> Result<ComputationTree, MachineError> {
+        validate_input(input, self.chars())?;
+        let chars = self.chars() as usize;
+        Ok(ComputationTree::build(
+            input,
+            max_nodes,
+            |state| self.accept_states().contains(&state),
+            |nodes, index| {
+                let node = &nodes[index];
+                let Some(&char) = input.get(node.consumed) else {
+                    return vec![];
+                };
+                sorted(
+                    &self.transition_table()
+                        [table_lookup(node.state as usize, char as usize, chars)],
+                )
+                .map(|state| (state, Some(char)))
+                .collect()
+            },
+        ))
+    }
+}
+
+impl EpsilonNfa {
+    /// Builds the tree of every path this machine can take on `input`, with at most `max_nodes`
+    /// nodes. Epsilon moves are their own edges in the tree. A branch never follows an epsilon
+    /// move back to a state it already visited without reading a character, so epsilon cycles
+    /// do not make the tree infinite.
+    pub fn computation_tree(
+        &self,
+        input: &[u16],
+        max_nodes: usize,
+    ) -> Result<ComputationTree, MachineError> {
+        validate_input(input, self.chars())?;
+        let chars = self.chars() as usize;
+        Ok(ComputationTree::build(
+            input,
+            max_nodes,
+            |state| self.accept_states().contains(&state),
+            |nodes, index| {
+                let node = &nodes[index];
+                let row = table_lookup(node.state as usize, 0, chars + 1);
+
+                // States visited by this branch since it last read a character
+                let mut visited = HashSet::from([node.state]);
+                let mut ancestor = node;
+                while let (Some(parent), None) = (ancestor.parent, ancestor.read) {
+                    ancestor = &nodes[parent];
+                    visited.insert(ancestor.state);
+                }
+
+                let mut moves: Vec<_> = match input.get(node.consumed) {
+                    Some(&char) => sorted(&self.transition_table()[row + char as usize])
+                        .map(|state| (state, Some(char)))
+                        .collect(),
+                    None => vec![],
+                };
+                moves.extend(
+                    sorted(&self.transition_table()[row + chars])
+                        .filter(|state| !visited.contains(state))
+                        .map(|state| (state, None)),
+                );
+                moves
+            },
+        ))
+    }
+}
+
+fn sorted(states: &HashSet<u16>) -> impl Iterator<Item = u16> {
+    let mut states: Vec<u16> = states.iter().copied().collect();
+    states.sort_unstable();
+    states.into_iter()
+}
+
+#[cfg(test)]
+mod computation_tree_tests {
+    use super::*;
+    use crate::machine_utils::{all_inputs, ends_in_one};
+
+    // Accepts 0* 1*, with an epsilon cycle between the two states
+    fn zeros_then_ones() -> EpsilonNfa {
+        EpsilonNfa::build(
+            vec![
+                HashSet::from([0]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::from([0]),
+            ],
+            HashSet::from([1]),
+            2,
+            2,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn nfa_tree_shape() {
+        let tree = ends_in_one().computation_tree(&[1, 0], 100).unwrap();
+
+        let summary: Vec<_> = tree
+            .nodes
+            .iter()
+            .map(|node| (node.state, node.consumed, node.parent, node.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 0, None, BranchStatus::Running),
+                (0, 1, Some(0), BranchStatus::Running),
+                (1, 1, Some(0), BranchStatus::Died),
+                (0, 2, Some(1), BranchStatus::Rejected),
+            ]
+        );
+        assert_eq!(tree.nodes[0].children, vec![1, 2]);
+        assert_eq!(tree.nodes[3].read, Some(0));
+        assert!(!tree.accepts());
+        assert!(!tree.truncated);
+    }
+
+    #[test]
+    fn empty_input_is_root_only() {
+        let tree = ends_in_one().computation_tree(&[], 100).unwrap();
+
+        assert_eq!(tree.nodes.len(), 1);
+        assert_eq!(tree.nodes[0].status, BranchStatus::Rejected);
+    }
+
+    #[test]
+    fn budget_truncates() {
+        let tree = ends_in_one().computation_tree(&[1, 1, 1, 1], 4).unwrap();
+
+        assert_eq!(tree.nodes.len(), 4);
+        assert!(tree.truncated);
+        assert!(tree
+            .nodes
+            .iter()
+            .any(|node| node.status == BranchStatus::Truncated));
+        // The shallowest nodes are kept
+        assert!(tree.nodes.iter().all(|node| node.consumed <= 2));
+    }
+
+    #[test]
+    fn epsilon_moves_are_edges() {
+        let tree = zeros_then_ones().computation_tree(&[1], 100).unwrap();
+
+        let summary: Vec<_> = tree
+            .nodes
+            .iter()
+            .map(|node| (node.state, node.consumed, node.read, node.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 0, None, BranchStatus::Running),
+                (1, 0, None, BranchStatus::Running),
+                (1, 1, Some(1), BranchStatus::Accepted),
+            ]
+        );
+        assert!(tree.nodes[2].children.is_empty());
+        assert!(tree.accepts());
+    }
+
+    #[test]
+    fn trailing_epsilon_moves_keep_running() {
+        // After reading the 0 the branch is in state 0, which only accepts through its epsilon
+        // move to state 1
+        let tree = zeros_then_ones().computation_tree(&[0], 100).unwrap();
+
+        let summary: Vec<_> = tree
+            .nodes
+            .iter()
+            .map(|node| (node.state, node.consumed, node.read, node.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 0, None, BranchStatus::Running),
+                (0, 1, Some(0), BranchStatus::Running),
+                (1, 0, None, BranchStatus::Died),
+                (1, 1, None, BranchStatus::Accepted),
+            ]
+        );
+        assert_eq!(tree.nodes[3].parent, Some(1));
+        assert!(tree.accepts());
+
+        // Accepted and rejected nodes are always leaves at the end of the input
+        for input in all_inputs(2, 4) {
+            let tree = zeros_then_ones()
+                .computation_tree(&input, usize::MAX)
+                .unwrap();
+            for node in &tree.nodes {
+                if matches!(node.status, BranchStatus::Accepted | BranchStatus::Rejected) {
+                    assert!(node.children.is_empty(), "{input:?}");
+                    assert_eq!(node.consumed, input.len(), "{input:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn accepts_matches_machine() {
+        let nfa = ends_in_one();
+        let e_nfa = zeros_then_ones();
+        for input in all_inputs(2, 6) {
+            let tree = nfa.computation_tree(&input, usize::MAX).unwrap();
+            assert_eq!(tree.accepts(), nfa.accepts(&input).unwrap(), "{input:?}");
+
+            let tree = e_nfa.computation_tree(&input, usize::MAX).unwrap();
+            assert_eq!(tree.accepts(), e_nfa.accepts(&input).unwrap(), "{input:?}");
+            assert!(!tree.truncated);
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            ends_in_one().computation_tree(&[3], 100).unwrap_err(),
+            MachineError::InvalidInputChar {
+                index: 0,
+                char: 3,
+                chars: 2
+            }
+        );
+    }
+}
@@ -1,12 +1,27 @@
 use crate::{
-    machine_utils::{add_tape_mov_stay_fir, table_lookup},
-    transitions::{self, SingleChar},
-    StateMachine, StateMachineBuilder, TapeMovement,
+    diagnostics::{accept_state_diagnostics, Diagnostic, DiagnosticKind},
+    e_nfa::EpsilonNfa,
+    machine_utils::{
+        add_tape_mov_stay_fir, remove_char_column, swap_in_set, swap_rows, table_lookup,
+    },
+    nfa::Nfa,
+    transitions::SingleChar,
+    FiniteAutomaton, MachineError, StateMachine, StateMachineBuilder, TapeMovement,
 };
 
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    iter,
+};
+
+mod minimize;
+mod product;
+mod trim;
+
+pub use product::SetOperation;
+pub use trim::StateLint;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Dfa {
     transition_table: Vec<u16>,
     accept_states: HashSet<u16>,
@@ -38,28 +53,127 @@ impl DfaBuilder {
         }
     }
 
-    fn swap_state(&mut self, first: u16, second: u16) {
-        debug_assert!(first < self.states);
-        debug_assert!(second < self.states);
-        if first == second {
-            return;
+    /// Sends every missing transition to a trap state, a state that rejects and never leaves
+    /// itself, so the builder can be turned into a `Dfa`. An existing trap state is reused when
+    /// there is one, otherwise a new state is added, which fails with
+    /// [`MachineError::TooManyStates`] when the builder already has `u16::MAX` states. Returns the
+    /// trap state, or `None` when no transition was missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{
+    ///     dfa::{Dfa, DfaBuilder},
+    ///     StateMachineBuilder,
+    /// };
+    /// use std::collections::HashSet;
+    ///
+    /// let mut builder = DfaBuilder::from(Dfa::build(vec![0], HashSet::from([0]), 1, 1).unwrap());
+    /// builder.add_char();
+    ///
+    /// assert_eq!(builder.complete_with_sink(), Ok(Some(1)));
+    /// assert_eq!(builder.complete_with_sink(), Ok(None));
+    /// assert_eq!(Dfa::try_from(builder).unwrap().transition_table(), &[0, 1, 1, 1]);
+    /// ```
+    pub fn complete_with_sink(&mut self) -> Result<Option<u16>, MachineError> {
+        if self.building_layers.iter().all(Option::is_some) {
+            return Ok(None);
         }
 
-        // Make first < second
-        if first > second {
-            return self.swap_state(second, first);
+        let chars = self.chars as usize;
+        let existing = (0..self.states).find(|state| {
+            !self.accept_states.contains(state)
+                && self.building_layers[table_lookup(*state as usize, 0, chars)..][..chars]
+                    .iter()
+                    .all(|&transition| transition == Some(*state))
+        });
+        let sink = match existing {
+            Some(sink) => sink,
+            None => {
+                if self.states == u16::MAX {
+                    return Err(MachineError::TooManyStates);
+                }
+                let sink = self.add_state();
+                let start = table_lookup(sink as usize, 0, chars);
+                self.building_layers[start..start + chars].fill(Some(sink));
+                sink
+            }
+        };
+        for transition in self.building_layers.iter_mut() {
+            transition.get_or_insert(sink);
         }
-        debug_assert!(first < second);
+        Ok(Some(sink))
+    }
 
-        // Indexes to the start of the tables for first and second
-        let first_start = table_lookup(first as usize, 0, self.chars as usize);
-        let second_start = table_lookup(second as usize, 0, self.chars as usize);
+    /// Checks the builder for missing transitions, which stop it from being turned into a `Dfa`,
+    /// and for accept states that are missing or can not be reached. The missing transitions come
+    /// first, in the order of the transition table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{
+    ///     diagnostics::{Diagnostic, DiagnosticKind, Severity},
+    ///     dfa::{Dfa, DfaBuilder},
+    ///     StateMachineBuilder,
+    /// };
+    /// use std::collections::HashSet;
+    ///
+    /// let mut builder = DfaBuilder::from(Dfa::build(vec![0], HashSet::new(), 1, 1).unwrap());
+    /// builder.add_state();
+    ///
+    /// let diagnostics = builder.validate();
+    /// assert_eq!(
+    ///     diagnostics[0],
+    ///     Diagnostic {
+    ///         severity: Severity::Error,
+    ///         kind: DiagnosticKind::MissingTransition,
+    ///         state: Some(1),
+    ///         char: Some(0),
+    ///     }
+    /// );
+    /// assert_eq!(diagnostics[1].kind, DiagnosticKind::NoAcceptStates);
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let chars = self.chars as usize;
+        let mut diagnostics: Vec<Diagnostic> = self
+            .building_layers
+            .iter()
+            .enumerate()
+            .filter(|(_, transition)| transition.is_none())
+            .map(|(index, _)| {
+                Diagnostic::new(
+                    DiagnosticKind::MissingTransition,
+                    Some((index / chars) as u16),
+                    Some((index % chars) as u16),
+                )
+            })
+            .collect();
+        diagnostics.extend(accept_state_diagnostics(
+            self.states,
+            self.edges(),
+            &self.accept_states,
+        ));
+        diagnostics
+    }
 
-        // Swap the slices in the transition table
-        let (first_extra, second_extra) = self.building_layers.split_at_mut(second_start);
-        let first_table = &mut first_extra[first_start..first_start + self.chars as usize];
-        let second_table = &mut second_extra[..self.chars as usize];
-        first_table.swap_with_slice(second_table);
+    /// Every transition that has been set as `(from, to)`
+    fn edges(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.building_layers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &to)| Some(((index / self.chars as usize) as u16, to?)))
+    }
+
+    fn swap_state(&mut self, first: u16, second: u16) {
+        debug_assert!(first < self.states);
+        debug_assert!(second < self.states);
+        swap_rows(
+            &mut self.building_layers,
+            first,
+            second,
+            self.chars as usize,
+        );
 
         for state in self.building_layers.iter_mut().flatten() {
             *state = match *state {
@@ -69,37 +183,43 @@ impl DfaBuilder {
             }
         }
 
-        let first_is_accept = self.accept_states.remove(&first);
-        let second_is_accept = self.accept_states.remove(&second);
+        swap_in_set(&mut self.accept_states, first, second);
+    }
 
-        if first_is_accept {
-            self.accept_states.insert(second);
-        }
-        if second_is_accept {
-            self.accept_states.insert(first);
+    fn check_state(&self, state: u16) -> Result<(), MachineError> {
+        if state >= self.states {
+            return Err(MachineError::StateOutOfRange {
+                state,
+                states: self.states,
+            });
         }
+        Ok(())
     }
 }
 
 impl StateMachineBuilder for DfaBuilder {
     type Machine = Dfa;
     type Trasition = SingleChar;
-
-    //TDOD: figure out error type
-    type Error = ();
+    type Error = MachineError;
 
     fn add_state(&mut self) -> u16 {
         self.building_layers
-            .append(&mut vec![None; self.chars as usize].into());
+            .extend(iter::repeat_n(None, self.chars as usize));
         self.states += 1;
         debug_assert!(self.building_layers.len() == (self.chars as usize) * (self.states as usize));
 
         self.states - 1
     }
 
-    fn remove_state(&mut self, state: u16) -> Result<Option<u16>, ()> {
-        if state >= self.states || self.states == 1 {
-            return Err(());
+    fn remove_state(&mut self, state: u16) -> Result<Option<u16>, Self::Error> {
+        if state >= self.states {
+            return Err(MachineError::StateOutOfRange {
+                state,
+                states: self.states,
+            });
+        }
+        if self.states == 1 {
+            return Err(MachineError::LastState);
         }
 
         self.swap_state(state, self.states - 1);
@@ -127,8 +247,13 @@ impl StateMachineBuilder for DfaBuilder {
 
     fn set_transition(&mut self, transition: SingleChar) -> Result<(), Self::Error> {
         let SingleChar { start, end, char } = transition;
-        if start >= self.states || end >= self.states || char >= self.chars {
-            return Err(());
+        self.check_state(start)?;
+        self.check_state(end)?;
+        if char >= self.chars {
+            return Err(MachineError::CharOutOfRange {
+                char,
+                chars: self.chars,
+            });
         }
 
         self.building_layers[table_lookup(start as usize, char as usize, self.chars as usize)] =
@@ -138,69 +263,44 @@ impl StateMachineBuilder for DfaBuilder {
     }
 
     fn set_start_state(&mut self, new_start_state: u16) -> Result<(), Self::Error> {
-        if new_start_state >= self.states {
-            return Err(());
-        }
+        self.check_state(new_start_state)?;
         self.swap_state(0, new_start_state);
         Ok(())
     }
 
     fn add_char(&mut self) {
         let chunks = self.building_layers.chunks_exact(self.chars as usize);
-        debug_assert!(chunks.remainder().len() == 0);
+        debug_assert!(chunks.remainder().is_empty());
         debug_assert!(chunks.len() == self.states as usize);
 
         self.building_layers = chunks
-            .map(|chunk| chunk.into_iter().chain([None].iter()))
-            .flatten()
+            .flat_map(|chunk| chunk.iter().chain([None].iter()))
             .copied()
             .collect();
         self.chars += 1;
-        debug_assert!(self.building_layers.len() == (self.chars * self.states) as usize);
+        debug_assert!(self.building_layers.len() == (self.chars as usize) * (self.states as usize));
     }
 
     fn remove_char(&mut self, char: u16) -> Result<Option<u16>, Self::Error> {
-        if self.chars == 1 {
-            return Err(());
-        }
-        if char >= self.chars {
-            return Err(());
-        }
-        let chunks = self.building_layers.chunks_exact(self.chars as usize);
-        debug_assert!(chunks.remainder().len() == 0);
-        debug_assert!(chunks.len() == self.states as usize);
-
-        self.building_layers = chunks
-            .map(|chunk| {
-                let (left, right) = chunk.split_at(char as usize);
-                left.into_iter().chain(right.into_iter().skip(1))
-            })
-            .flatten()
-            .copied()
-            .collect();
+        let moved = remove_char_column(
+            &mut self.building_layers,
+            char,
+            self.chars,
+            self.chars as usize,
+        )?;
         self.chars -= 1;
-        debug_assert!(self.building_layers.len() == (self.chars * self.states) as usize);
-        if char == self.chars {
-            Ok(None)
-        } else {
-            Ok(Some(self.chars))
-        }
+        debug_assert!(self.building_layers.len() == (self.chars as usize) * (self.states as usize));
+        Ok(moved)
     }
 
     fn add_accept_state(&mut self, state: u16) -> Result<bool, Self::Error> {
-        if state >= self.states {
-            return Err(());
-        }
-
-        return Ok(self.accept_states.insert(state));
+        self.check_state(state)?;
+        Ok(self.accept_states.insert(state))
     }
 
     fn remove_accept_state(&mut self, state: u16) -> Result<bool, Self::Error> {
-        if state >= self.states {
-            return Err(());
-        }
-
-        return Ok(self.accept_states.remove(&state));
+        self.check_state(state)?;
+        Ok(self.accept_states.remove(&state))
     }
 }
 
@@ -215,21 +315,28 @@ impl From<Dfa> for DfaBuilder {
     }
 }
 
-//TODO: create
 impl TryFrom<DfaBuilder> for Dfa {
-    type Error = ();
+    type Error = MachineError;
 
     fn try_from(value: DfaBuilder) -> Result<Self, Self::Error> {
-        Ok(Dfa::build(
-            value
-                .building_layers
-                .into_iter()
-                .collect::<Option<Vec<u16>>>()
-                .ok_or(())?,
+        let transition_table = value
+            .building_layers
+            .iter()
+            .enumerate()
+            .map(|(index, transition)| {
+                transition.ok_or(MachineError::MissingTransition {
+                    state: (index / value.chars as usize) as u16,
+                    char: (index % value.chars as usize) as u16,
+                })
+            })
+            .collect::<Result<Vec<u16>, MachineError>>()?;
+
+        Dfa::build(
+            transition_table,
             value.accept_states,
             value.states,
             value.chars,
-        )?)
+        )
     }
 }
 
@@ -239,16 +346,32 @@ impl Dfa {
         accept_states: HashSet<u16>,
         states: u16,
         chars: u16,
-    ) -> Result<Dfa, ()> {
-        if transition_table.len() != (states * chars) as usize {
-            return Err(());
+    ) -> Result<Dfa, MachineError> {
+        if states == 0 {
+            return Err(MachineError::NoStates);
+        }
+        if chars == 0 {
+            return Err(MachineError::NoChars);
+        }
+        if transition_table.len() != states as usize * chars as usize {
+            return Err(MachineError::WrongTableLength {
+                expected: states as usize * chars as usize,
+                actual: transition_table.len(),
+            });
         }
-        if transition_table
+        if let Some((index, &state)) = transition_table
             .iter()
-            .chain(accept_states.iter())
-            .any(|&item| item >= states)
+            .enumerate()
+            .find(|(_, &state)| state >= states)
         {
-            return Err(());
+            return Err(MachineError::InvalidTransitionTarget {
+                index,
+                state,
+                states,
+            });
+        }
+        if let Some(&state) = accept_states.iter().find(|&&state| state >= states) {
+            return Err(MachineError::InvalidAcceptState { state, states });
         }
 
         Ok(Dfa {
@@ -259,6 +382,76 @@ impl Dfa {
         })
     }
 
+    /// Builds a `Dfa` from a builder that may be missing transitions, where a missing transition
+    /// rejects the rest of the input. The missing transitions go to a trap state as in
+    /// [`DfaBuilder::complete_with_sink`], which is also returned, or `None` when the builder was
+    /// already complete.
+    pub fn from_partial(mut builder: DfaBuilder) -> Result<(Dfa, Option<u16>), MachineError> {
+        let sink = builder.complete_with_sink()?;
+        Ok((Dfa::try_from(builder)?, sink))
+    }
+
+    /// Converts a `Nfa` into an equivalent `Dfa` using the subset construction. Only the subsets
+    /// reachable from the start state become states of the `Dfa`. Along with the `Dfa` this
+    /// returns the set of `Nfa` states that each `Dfa` state represents, indexed by `Dfa` state.
+    pub fn from_nfa(nfa: &Nfa) -> Result<(Dfa, Vec<BTreeSet<u16>>), MachineError> {
+        Self::subset_construction(nfa)
+    }
+
+    /// Converts a `EpsilonNfa` into an equivalent `Dfa` using the subset construction. Every
+    /// subset is closed under epsilon transitions, so the start state of the `Dfa` represents the
+    /// epsilon closure of the start state of the `EpsilonNfa`.
+    pub fn from_epsilon_nfa(e_nfa: &EpsilonNfa) -> Result<(Dfa, Vec<BTreeSet<u16>>), MachineError> {
+        Self::subset_construction(e_nfa)
+    }
+
+    /// Breadth first search over the subsets reachable from the start states of `machine`.
+    /// Subsets are numbered in the order they are discovered, so the start states always become
+    /// state 0.
+    fn subset_construction(
+        machine: &impl FiniteAutomaton,
+    ) -> Result<(Dfa, Vec<BTreeSet<u16>>), MachineError> {
+        let start = machine.start_states();
+        let chars = machine.chars();
+        let mut subsets = vec![start.clone()];
+        let mut subset_ids = HashMap::from([(start, 0)]);
+        let mut transition_table = Vec::new();
+        let mut q = VecDeque::from([0]);
+
+        while let Some(cur_state) = q.pop_front() {
+            for c in 0..chars {
+                let next = machine.next_states(&subsets[cur_state as usize], c);
+                let next_state = match subset_ids.get(&next) {
+                    Some(&state) => state,
+                    None => {
+                        let state = u16::try_from(subsets.len())
+                            .map_err(|_| MachineError::TooManyStates)?;
+                        subset_ids.insert(next.clone(), state);
+                        subsets.push(next);
+                        q.push_back(state);
+                        state
+                    }
+                };
+                transition_table.push(next_state);
+            }
+        }
+
+        let accept_states = (0..subsets.len() as u16)
+            .filter(|&state| machine.is_accepting(&subsets[state as usize]))
+            .collect();
+        let dfa = Dfa::build(transition_table, accept_states, subsets.len() as u16, chars)
+            .expect("Subset construction should always produce a valid Dfa");
+        Ok((dfa, subsets))
+    }
+
+    pub fn accept_states(&self) -> &HashSet<u16> {
+        &self.accept_states
+    }
+
+    pub fn transition_table(&self) -> &[u16] {
+        &self.transition_table
+    }
+
     fn states<'a>(&'a self, input: &'a [u16]) -> impl Iterator<Item = u16> + 'a {
         Some(0).into_iter().chain(input.iter().scan(0, |state, &c| {
             *state = self.next_state(*state, c);
@@ -272,6 +465,40 @@ impl Dfa {
     }
 }
 
+impl From<&Nfa> for Dfa {
+    /// Converts using [`Dfa::from_nfa`]. Panics if the `Dfa` would need more than `u16::MAX`
+    /// states, use [`Dfa::from_nfa`] to handle that case.
+    fn from(value: &Nfa) -> Self {
+        Dfa::from_nfa(value)
+            .expect("The Dfa should have at most u16::MAX states")
+            .0
+    }
+}
+
+impl From<&EpsilonNfa> for Dfa {
+    /// Converts using [`Dfa::from_epsilon_nfa`]. Panics if the `Dfa` would need more than
+    /// `u16::MAX` states, use [`Dfa::from_epsilon_nfa`] to handle that case.
+    fn from(value: &EpsilonNfa) -> Self {
+        Dfa::from_epsilon_nfa(value)
+            .expect("The Dfa should have at most u16::MAX states")
+            .0
+    }
+}
+
+impl FiniteAutomaton for Dfa {
+    fn start_states(&self) -> BTreeSet<u16> {
+        BTreeSet::from([0])
+    }
+
+    fn next_states(&self, states: &BTreeSet<u16>, char: u16) -> BTreeSet<u16> {
+        states.iter().map(|&s| self.next_state(s, char)).collect()
+    }
+
+    fn is_accepting(&self, states: &BTreeSet<u16>) -> bool {
+        states.iter().any(|s| self.accept_states.contains(s))
+    }
+}
+
 impl StateMachine for Dfa {
     fn accepts_validated(&self, input: &[u16]) -> bool {
         self.accept_states.contains(
@@ -299,4 +526,8 @@ impl StateMachine for Dfa {
 mod dfa_tests {
     mod dfa_builder_tests;
     mod dfa_machine_tests;
+    mod minimize_tests;
+    mod product_tests;
+    mod subset_construction_tests;
+    mod trim_tests;
 }
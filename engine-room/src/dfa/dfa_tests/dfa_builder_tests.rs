@@ -1,10 +1,10 @@
 use crate::{
     dfa::{Dfa, DfaBuilder},
-    machine_utils::add_tape_mov_stay_fir,
-    transitions::{self, SingleChar},
-    StateMachine, StateMachineBuilder, TapeMovement,
+    diagnostics::{Diagnostic, DiagnosticKind, Severity},
+    transitions::SingleChar,
+    MachineError, StateMachine, StateMachineBuilder,
 };
-use std::{cmp::min, collections::HashSet, iter::RepeatN};
+use std::{cmp::min, collections::HashSet};
 
 #[test]
 fn create_builder_from_scratch() {
@@ -29,6 +29,49 @@ fn add_state() {
     assert!(new_dfa.is_err());
 }
 
+#[test]
+fn missing_transition_error() {
+    let dfa = Dfa::build(vec![1, 0, 0, 1], HashSet::from([0]), 2, 2).unwrap();
+    let mut builder: DfaBuilder = dfa.into();
+    builder.add_state();
+
+    assert_eq!(
+        Dfa::try_from(builder).unwrap_err(),
+        MachineError::MissingTransition { state: 2, char: 0 }
+    );
+}
+
+#[test]
+fn builder_errors_report_offender() {
+    let dfa = Dfa::build(vec![1, 0], HashSet::from([0]), 2, 1).unwrap();
+    let mut builder: DfaBuilder = dfa.into();
+
+    assert_eq!(
+        builder.remove_state(2).unwrap_err(),
+        MachineError::StateOutOfRange {
+            state: 2,
+            states: 2
+        }
+    );
+    assert_eq!(
+        builder
+            .set_transition(SingleChar {
+                start: 0,
+                end: 1,
+                char: 1
+            })
+            .unwrap_err(),
+        MachineError::CharOutOfRange { char: 1, chars: 1 }
+    );
+    assert_eq!(builder.remove_char(0).unwrap_err(), MachineError::LastChar);
+
+    builder.remove_state(1).unwrap();
+    assert_eq!(
+        builder.remove_state(0).unwrap_err(),
+        MachineError::LastState
+    );
+}
+
 #[test]
 fn remove_state_last() {
     let dfa = Dfa::build(vec![0, 1], HashSet::from([0, 1]), 2, 1).unwrap();
@@ -57,7 +100,7 @@ fn remove_state_first() {
 
 #[test]
 fn remove_state_large() {
-    let table = (0..8).into_iter().rev().collect::<Vec<u16>>().repeat(8);
+    let table = (0..8).rev().collect::<Vec<u16>>().repeat(8);
     let dfa = Dfa::build(table, HashSet::from([0, 2, 4, 6]), 8, 8).unwrap();
     let mut builder: DfaBuilder = dfa.into();
 
@@ -112,7 +155,7 @@ fn remove_state_invalid_no_effect() {
         states: 2,
         chars: 3,
     };
-    let mut builder: DfaBuilder = dfa.try_into().unwrap();
+    let mut builder: DfaBuilder = dfa.into();
     let copy = builder.clone();
 
     for i in 2..100 {
@@ -139,7 +182,7 @@ fn add_then_remove() {
         states: 2,
         chars: 3,
     };
-    let mut builder: DfaBuilder = dfa.try_into().unwrap();
+    let mut builder: DfaBuilder = dfa.into();
     let copy = builder.clone();
 
     for i in 1..100 {
@@ -165,7 +208,7 @@ fn set_transition() {
         chars: 3,
     };
 
-    let mut builder: DfaBuilder = dfa.try_into().unwrap();
+    let mut builder: DfaBuilder = dfa.into();
     assert!(builder
         .set_transition(SingleChar {
             start: 0,
@@ -423,3 +466,154 @@ fn add_accept_state() {
     assert_eq!(builder.states, copy.states);
     assert_eq!(builder.chars, copy.chars);
 }
+
+#[test]
+fn remove_char_renumbers_last() {
+    let dfa = Dfa::build(vec![0, 1, 1, 1, 0, 0], HashSet::from([1]), 2, 3).unwrap();
+    let mut builder: DfaBuilder = dfa.into();
+
+    assert_eq!(builder.remove_char(0).unwrap(), Some(2));
+    assert_eq!(builder.chars, 2);
+    assert_eq!(
+        builder.building_layers,
+        vec![Some(1), Some(1), Some(0), Some(0)]
+    );
+
+    assert_eq!(builder.remove_char(1).unwrap(), None);
+    assert_eq!(builder.building_layers, vec![Some(1), Some(0)]);
+}
+
+#[test]
+fn complete_with_new_sink() {
+    // State 1 is reached on 1 and accepts, but has no transitions of its own yet
+    let mut builder = DfaBuilder::from(Dfa::build(vec![0, 0], HashSet::new(), 1, 2).unwrap());
+    builder.add_state();
+    builder.add_accept_state(1).unwrap();
+    builder
+        .set_transition(SingleChar {
+            start: 0,
+            end: 1,
+            char: 1,
+        })
+        .unwrap();
+
+    assert_eq!(builder.complete_with_sink(), Ok(Some(2)));
+    let dfa = Dfa::try_from(builder).unwrap();
+    assert_eq!(dfa.transition_table(), &[0, 1, 2, 2, 2, 2]);
+    assert_eq!(dfa.accept_states(), &HashSet::from([1]));
+}
+
+#[test]
+fn complete_reuses_sink() {
+    // State 1 already rejects and loops on itself, state 2 is missing its transition on 1
+    let mut builder =
+        DfaBuilder::from(Dfa::build(vec![0, 1, 1, 1], HashSet::from([0]), 2, 2).unwrap());
+    let state = builder.add_state();
+    for (start, end) in [(0, state), (state, 0)] {
+        builder
+            .set_transition(SingleChar {
+                start,
+                end,
+                char: 0,
+            })
+            .unwrap();
+    }
+
+    assert_eq!(builder.complete_with_sink(), Ok(Some(1)));
+    let dfa = Dfa::try_from(builder).unwrap();
+    assert_eq!(dfa.transition_table(), &[2, 1, 1, 1, 0, 1]);
+}
+
+#[test]
+fn from_partial_rejects_missing() {
+    // Accepts only 01, with every other transition missing
+    let mut builder = DfaBuilder::from(Dfa::build(vec![0], HashSet::from([0]), 1, 1).unwrap());
+    builder.add_char();
+    builder.remove_accept_state(0).unwrap();
+    let middle = builder.add_state();
+    let end = builder.add_state();
+    builder.add_accept_state(end).unwrap();
+    for (start, end, char) in [(0, middle, 0), (middle, end, 1)] {
+        builder
+            .set_transition(SingleChar { start, end, char })
+            .unwrap();
+    }
+
+    let (dfa, sink) = Dfa::from_partial(builder).unwrap();
+    assert_eq!(sink, Some(3));
+    assert!(dfa.accepts(&[0, 1]).unwrap());
+    assert!(!dfa.accepts(&[0]).unwrap());
+    assert!(!dfa.accepts(&[1, 0, 1]).unwrap());
+    assert!(!dfa.accepts(&[0, 1, 1]).unwrap());
+
+    let complete = DfaBuilder::from(Dfa::build(vec![0], HashSet::new(), 1, 1).unwrap());
+    assert_eq!(Dfa::from_partial(complete).unwrap().1, None);
+}
+
+#[test]
+fn complete_without_room_for_sink() {
+    // Every state loops on 0 and is missing its transition on 1, so none of them is a trap state
+    let mut builder =
+        DfaBuilder::from(Dfa::build((0..u16::MAX).collect(), HashSet::new(), u16::MAX, 1).unwrap());
+    builder.add_char();
+
+    assert_eq!(
+        Dfa::from_partial(builder).unwrap_err(),
+        MachineError::TooManyStates
+    );
+}
+
+#[test]
+fn validate() {
+    let complete = DfaBuilder::from(Dfa::build(vec![1, 0], HashSet::from([0]), 2, 1).unwrap());
+    assert_eq!(complete.validate(), vec![]);
+
+    let mut builder = complete.clone();
+    builder.remove_accept_state(0).unwrap();
+    assert_eq!(
+        builder.validate(),
+        vec![Diagnostic {
+            severity: Severity::Warning,
+            kind: DiagnosticKind::NoAcceptStates,
+            state: None,
+            char: None,
+        }]
+    );
+
+    // State 2 is an accept state with no transitions, and nothing leads to it
+    let mut builder = complete;
+    let unreachable = builder.add_state();
+    builder.add_accept_state(unreachable).unwrap();
+    let diagnostics = builder.validate();
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                severity: Severity::Error,
+                kind: DiagnosticKind::MissingTransition,
+                state: Some(2),
+                char: Some(0),
+            },
+            Diagnostic {
+                severity: Severity::Warning,
+                kind: DiagnosticKind::UnreachableAcceptState,
+                state: Some(2),
+                char: None,
+            },
+        ]
+    );
+    assert!(Dfa::try_from(builder.clone()).is_err());
+
+    builder
+        .set_transition(SingleChar {
+            start: 2,
+            end: 0,
+            char: 0,
+        })
+        .unwrap();
+    assert!(builder
+        .validate()
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Warning));
+    assert!(Dfa::try_from(builder).is_ok());
+}
@@ -1,4 +1,6 @@
-use crate::{dfa::Dfa, machine_utils::add_tape_mov_stay_fir, StateMachine, TapeMovement};
+use crate::{
+    dfa::Dfa, machine_utils::add_tape_mov_stay_fir, MachineError, StateMachine, TapeMovement,
+};
 use std::collections::HashSet;
 
 #[test]
@@ -73,6 +75,53 @@ fn invalid_input_many_chars() {
     assert!(dfa.trace_states(&long_invalid).is_err());
 }
 
+#[test]
+fn build_errors_report_offender() {
+    assert_eq!(
+        Dfa::build(vec![], HashSet::new(), 0, 0).unwrap_err(),
+        MachineError::NoStates
+    );
+    assert_eq!(
+        Dfa::build(vec![], HashSet::new(), 2, 0).unwrap_err(),
+        MachineError::NoChars
+    );
+    assert_eq!(
+        Dfa::build(vec![0, 1, 1], HashSet::from([1]), 2, 2).unwrap_err(),
+        MachineError::WrongTableLength {
+            expected: 4,
+            actual: 3
+        }
+    );
+    assert_eq!(
+        Dfa::build(vec![0, 1, 2, 1], HashSet::from([1]), 2, 2).unwrap_err(),
+        MachineError::InvalidTransitionTarget {
+            index: 2,
+            state: 2,
+            states: 2
+        }
+    );
+    assert_eq!(
+        Dfa::build(vec![0, 1, 1, 0], HashSet::from([7]), 2, 2).unwrap_err(),
+        MachineError::InvalidAcceptState {
+            state: 7,
+            states: 2
+        }
+    );
+}
+
+#[test]
+fn invalid_input_reports_index() {
+    let dfa = Dfa::build(vec![1, 0, 1, 0], HashSet::from([0]), 2, 2).unwrap();
+    let err = MachineError::InvalidInputChar {
+        index: 3,
+        char: 5,
+        chars: 2,
+    };
+
+    assert_eq!(dfa.accepts(&[0, 1, 1, 5, 9]).unwrap_err(), err);
+    assert_eq!(dfa.trace_states(&[0, 1, 1, 5, 9]).unwrap_err(), err);
+}
+
 #[test]
 fn valid_input_empty() {
     let dfa = Dfa::build(vec![1, 0, 1, 0], HashSet::from([0]), 2, 2).unwrap();
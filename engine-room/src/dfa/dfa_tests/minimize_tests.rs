@@ -0,0 +1,150 @@
+use crate::{dfa::Dfa, machine_utils::all_inputs, nfa::Nfa, MachineError, StateMachine};
+use std::collections::{BTreeSet, HashSet};
+
+#[test]
+fn minimal_dfa_unchanged() {
+    let dfa = Dfa::build(vec![1, 0], HashSet::from([1]), 2, 1).unwrap();
+    let (min_dfa, partition) = dfa.minimize();
+
+    assert_eq!(min_dfa, dfa);
+    assert_eq!(partition, vec![BTreeSet::from([0]), BTreeSet::from([1])]);
+}
+
+#[test]
+fn merges_equivalent_states() {
+    // Accepts 0*1*, but states 1 and 3 both track "seen a 1" and states 2 and 4 are both dead
+    let dfa = Dfa::build(
+        vec![0, 1, 2, 3, 4, 2, 4, 1, 2, 4],
+        HashSet::from([0, 1, 3]),
+        5,
+        2,
+    )
+    .unwrap();
+    let (min_dfa, partition) = dfa.minimize();
+
+    assert_eq!(min_dfa.states, 3);
+    assert_eq!(min_dfa.transition_table, vec![0, 1, 2, 1, 2, 2]);
+    assert_eq!(min_dfa.accept_states, HashSet::from([0, 1]));
+    assert_eq!(
+        partition,
+        vec![
+            BTreeSet::from([0]),
+            BTreeSet::from([1, 3]),
+            BTreeSet::from([2, 4])
+        ]
+    );
+    for input in all_inputs(2, 8) {
+        assert_eq!(min_dfa.accepts(&input), dfa.accepts(&input), "{input:?}");
+    }
+}
+
+#[test]
+fn removes_unreachable_states() {
+    // State 2 is unreachable and is the only state that rejects
+    let dfa = Dfa::build(vec![1, 0, 2], HashSet::from([0, 1]), 3, 1).unwrap();
+    let (min_dfa, partition) = dfa.minimize();
+
+    assert_eq!(min_dfa.states, 1);
+    assert_eq!(min_dfa.transition_table, vec![0]);
+    assert_eq!(min_dfa.accept_states, HashSet::from([0]));
+    assert_eq!(partition, vec![BTreeSet::from([0, 1])]);
+}
+
+#[test]
+fn no_accept_states_collapse() {
+    let dfa = Dfa::build(vec![1, 2, 2, 0, 0, 1], HashSet::new(), 3, 2).unwrap();
+    let (min_dfa, partition) = dfa.minimize();
+
+    assert_eq!(min_dfa.states, 1);
+    assert_eq!(min_dfa.transition_table, vec![0, 0]);
+    assert!(min_dfa.accept_states.is_empty());
+    assert_eq!(partition, vec![BTreeSet::from([0, 1, 2])]);
+}
+
+#[test]
+fn single_state_is_smallest() {
+    // minimize needs a start state, so a Dfa without states is refused when it is built
+    assert_eq!(
+        Dfa::build(vec![], HashSet::new(), 0, 0).unwrap_err(),
+        MachineError::NoStates
+    );
+
+    let dfa = Dfa::build(vec![0, 0], HashSet::from([0]), 1, 2).unwrap();
+    let (min_dfa, partition) = dfa.minimize();
+    assert_eq!(min_dfa, dfa);
+    assert_eq!(partition, vec![BTreeSet::from([0])]);
+}
+
+#[test]
+fn canonical_numbering() {
+    // Both accept strings with a number of 1s divisible by 3, with states listed in a different
+    // order and the second one containing duplicate states
+    let first = Dfa::build(vec![0, 1, 1, 2, 2, 0], HashSet::from([0]), 3, 2).unwrap();
+    let second = Dfa::build(
+        vec![0, 3, 1, 0, 2, 1, 2, 4, 1, 5, 5, 3],
+        HashSet::from([0, 5]),
+        6,
+        2,
+    )
+    .unwrap();
+
+    for input in all_inputs(2, 8) {
+        assert_eq!(first.accepts(&input), second.accepts(&input), "{input:?}");
+    }
+    assert_eq!(first.minimize().0, second.minimize().0);
+    assert_eq!(first.minimize().0, first);
+}
+
+#[test]
+fn minimize_subset_construction() {
+    // The subset construction of "ends in 1" has no redundant states
+    let nfa = Nfa::build(
+        vec![
+            HashSet::from([0]),
+            HashSet::from([0, 1]),
+            HashSet::new(),
+            HashSet::new(),
+        ],
+        HashSet::from([1]),
+        2,
+        2,
+    )
+    .unwrap();
+    let (dfa, _) = Dfa::from_nfa(&nfa).unwrap();
+    let (min_dfa, partition) = dfa.minimize();
+
+    assert_eq!(min_dfa, dfa);
+    assert_eq!(partition.len(), 2);
+}
+
+#[test]
+fn distinguishing_inputs_explain_partition() {
+    // Same machine as merges_equivalent_states
+    let dfa = Dfa::build(
+        vec![0, 1, 2, 3, 4, 2, 4, 1, 2, 4],
+        HashSet::from([0, 1, 3]),
+        5,
+        2,
+    )
+    .unwrap();
+    let (_, partition) = dfa.minimize();
+
+    for class in &partition {
+        for &first in class {
+            for &second in class {
+                assert_eq!(dfa.distinguishing_input(first, second), Ok(None));
+            }
+        }
+    }
+    // 0 keeps accepting after a 0, while 1 and 3 reject
+    assert_eq!(dfa.distinguishing_input(0, 1), Ok(Some(vec![0])));
+    assert_eq!(dfa.distinguishing_input(3, 0), Ok(Some(vec![0])));
+    assert_eq!(dfa.distinguishing_input(1, 2), Ok(Some(vec![])));
+    assert_eq!(
+        dfa.distinguishing_input(0, 5),
+        Err(MachineError::StateOutOfRange {
+            state: 5,
+            states: 5
+        })
+    );
+}
@@ -0,0 +1,100 @@
+use crate::{
+    dfa::{Dfa, SetOperation},
+    machine_utils::all_inputs,
+    MachineError, StateMachine,
+};
+use std::collections::HashSet;
+
+// Accepts strings with an even number of 0s
+fn even_zeros() -> Dfa {
+    Dfa::build(vec![1, 0, 0, 1], HashSet::from([0]), 2, 2).unwrap()
+}
+
+// Accepts strings ending in 1
+fn ends_in_one() -> Dfa {
+    Dfa::build(vec![0, 1, 0, 1], HashSet::from([1]), 2, 2).unwrap()
+}
+
+#[test]
+fn every_operation_matches_definition() {
+    let first = even_zeros();
+    let second = ends_in_one();
+    type Expected = fn(bool, bool) -> bool;
+    let operations: [(SetOperation, Expected); 4] = [
+        (SetOperation::Union, |a, b| a || b),
+        (SetOperation::Intersection, |a, b| a && b),
+        (SetOperation::Difference, |a, b| a && !b),
+        (SetOperation::SymmetricDifference, |a, b| a != b),
+    ];
+
+    for (operation, expected) in operations {
+        let (product, _) = first.product(&second, operation).unwrap();
+        for input in all_inputs(2, 7) {
+            assert_eq!(
+                product.accepts(&input).unwrap(),
+                expected(
+                    first.accepts(&input).unwrap(),
+                    second.accepts(&input).unwrap()
+                ),
+                "{operation:?} {input:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn product_pairs() {
+    let (product, pairs) = even_zeros().intersection(&ends_in_one()).unwrap();
+
+    assert_eq!(product.states, 4);
+    assert_eq!(pairs, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+    assert_eq!(product.transition_table, vec![1, 2, 0, 3, 1, 2, 0, 3]);
+    assert_eq!(product.accept_states, HashSet::from([2]));
+}
+
+#[test]
+fn only_reachable_pairs() {
+    // Both machines move in lockstep on a single character, so only pairs (n, n) are reachable
+    let first = Dfa::build(vec![1, 2, 0], HashSet::from([2]), 3, 1).unwrap();
+    let (product, pairs) = first.union(&first).unwrap();
+
+    assert_eq!(product.states, 3);
+    assert_eq!(pairs, vec![(0, 0), (1, 1), (2, 2)]);
+    assert_eq!(product.accept_states, HashSet::from([2]));
+}
+
+#[test]
+fn difference_with_self_is_empty() {
+    let dfa = ends_in_one();
+    let (difference, _) = dfa.difference(&dfa).unwrap();
+    let (symmetric_difference, _) = dfa.symmetric_difference(&dfa).unwrap();
+
+    assert!(difference.accept_states.is_empty());
+    assert!(symmetric_difference.accept_states.is_empty());
+}
+
+#[test]
+fn complement() {
+    let dfa = even_zeros();
+    let complement = dfa.complement();
+
+    assert_eq!(complement.transition_table, dfa.transition_table);
+    assert_eq!(complement.accept_states, HashSet::from([1]));
+    for input in all_inputs(2, 7) {
+        assert_ne!(complement.accepts(&input), dfa.accepts(&input), "{input:?}");
+    }
+    assert_eq!(complement.complement(), dfa);
+}
+
+#[test]
+fn alphabet_mismatch() {
+    let one_char = Dfa::build(vec![0], HashSet::from([0]), 1, 1).unwrap();
+
+    assert_eq!(
+        one_char.union(&even_zeros()).unwrap_err(),
+        MachineError::AlphabetMismatch {
+            first: 1,
+            second: 2
+        }
+    );
+}
@@ -0,0 +1,152 @@
+use crate::{
+    dfa::Dfa,
+    e_nfa::EpsilonNfa,
+    machine_utils::{all_inputs, ends_in_one},
+    nfa::Nfa,
+    StateMachine,
+};
+use std::collections::{BTreeSet, HashSet};
+
+#[test]
+fn ends_in_one_subsets() {
+    let (dfa, subsets) = Dfa::from_nfa(&ends_in_one()).unwrap();
+
+    assert_eq!(dfa.states, 2);
+    assert_eq!(dfa.chars, 2);
+    assert_eq!(subsets, vec![BTreeSet::from([0]), BTreeSet::from([0, 1])]);
+    assert_eq!(dfa.transition_table, vec![0, 1, 0, 1]);
+    assert_eq!(dfa.accept_states, HashSet::from([1]));
+}
+
+#[test]
+fn ends_in_one_same_language() {
+    let nfa = ends_in_one();
+    let dfa = Dfa::from(&nfa);
+
+    for input in all_inputs(2, 8) {
+        assert_eq!(dfa.accepts(&input), nfa.accepts(&input), "{input:?}");
+    }
+}
+
+#[test]
+fn empty_subset_becomes_dead_state() {
+    // Accepts only the string 0
+    let nfa = Nfa::build(
+        vec![
+            HashSet::from([1]),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        ],
+        HashSet::from([1]),
+        2,
+        2,
+    )
+    .unwrap();
+    let (dfa, subsets) = Dfa::from_nfa(&nfa).unwrap();
+
+    assert_eq!(
+        subsets,
+        vec![BTreeSet::from([0]), BTreeSet::from([1]), BTreeSet::new()]
+    );
+    assert_eq!(dfa.transition_table, vec![1, 2, 2, 2, 2, 2]);
+    assert!(dfa.accepts(&[0]).unwrap());
+    assert!(!dfa.accepts(&[0, 0]).unwrap());
+    assert!(!dfa.accepts(&[]).unwrap());
+}
+
+#[test]
+fn only_reachable_subsets_are_built() {
+    // States 2 and 3 can never be reached from the start state
+    let nfa = Nfa::build(
+        vec![
+            HashSet::from([0, 1]),
+            HashSet::from([1]),
+            HashSet::from([0, 1, 2, 3]),
+            HashSet::from([0, 1, 2, 3]),
+        ],
+        HashSet::from([1, 3]),
+        4,
+        1,
+    )
+    .unwrap();
+    let (dfa, subsets) = Dfa::from_nfa(&nfa).unwrap();
+
+    assert_eq!(dfa.states, 2);
+    assert_eq!(subsets, vec![BTreeSet::from([0]), BTreeSet::from([0, 1])]);
+    assert!(subsets.iter().flatten().all(|&s| s < 2));
+}
+
+#[test]
+fn fourth_from_last_blow_up() {
+    // Accepts strings where the fourth character from the end is a 1
+    let mut table = vec![HashSet::from([0]), HashSet::from([0, 1])];
+    for state in 1..4 {
+        table.extend([HashSet::from([state + 1]), HashSet::from([state + 1])]);
+    }
+    table.extend([HashSet::new(), HashSet::new()]);
+    let nfa = Nfa::build(table, HashSet::from([4]), 5, 2).unwrap();
+    let (dfa, subsets) = Dfa::from_nfa(&nfa).unwrap();
+
+    assert_eq!(dfa.states, 16);
+    assert!(subsets.iter().all(|subset| subset.contains(&0)));
+    for input in all_inputs(2, 7) {
+        assert_eq!(dfa.accepts(&input), nfa.accepts(&input), "{input:?}");
+    }
+}
+
+#[test]
+fn epsilon_nfa_start_is_closed() {
+    // Accepts (012)* (021)*
+    let transition_table = vec![
+        // State 0
+        HashSet::from([1]),
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::from([4]),
+        // State 1
+        HashSet::new(),
+        HashSet::from([2]),
+        HashSet::new(),
+        HashSet::new(),
+        // State 2
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::from([3]),
+        HashSet::new(),
+        // State 3
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::from([0, 4]),
+        // State 4
+        HashSet::from([5]),
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::new(),
+        // State 5
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::from([6]),
+        HashSet::new(),
+        // State 6
+        HashSet::new(),
+        HashSet::from([7]),
+        HashSet::new(),
+        HashSet::new(),
+        // State 7
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::from([4]),
+    ];
+    let e_nfa = EpsilonNfa::build(transition_table, HashSet::from([4, 7]), 8, 3).unwrap();
+    let (dfa, subsets) = Dfa::from_epsilon_nfa(&e_nfa).unwrap();
+
+    assert_eq!(subsets[0], BTreeSet::from([0, 4]));
+    assert_eq!(Dfa::from(&e_nfa), dfa);
+    assert!(dfa.accept_states.contains(&0));
+    for input in all_inputs(3, 7) {
+        assert_eq!(dfa.accepts(&input), e_nfa.accepts(&input), "{input:?}");
+    }
+}
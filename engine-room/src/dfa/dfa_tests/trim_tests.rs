@@ -0,0 +1,98 @@
+use crate::{
+    dfa::{Dfa, DfaBuilder, StateLint},
+    equivalent,
+    transitions::SingleChar,
+    Equivalence, MachineError, StateMachineBuilder,
+};
+use std::collections::{BTreeSet, HashSet};
+
+// Accepts strings ending in 1. State 2 is only reachable from the unreachable state 3, state 4
+// is a reachable dead state and state 5 is an unreachable dead state.
+fn cluttered() -> Dfa {
+    Dfa::build(
+        vec![0, 1, 0, 1, 4, 4, 2, 2, 4, 4, 5, 4],
+        HashSet::from([1, 2]),
+        6,
+        2,
+    )
+    .unwrap()
+}
+
+#[test]
+fn reachable_and_coreachable() {
+    let dfa = cluttered();
+
+    assert_eq!(dfa.reachable_states(), BTreeSet::from([0, 1]));
+    assert_eq!(dfa.coreachable_states(), BTreeSet::from([0, 1, 2, 3]));
+}
+
+#[test]
+fn trim_removes_and_renumbers() {
+    let dfa = cluttered();
+    let (trimmed, renumbered) = dfa.trim();
+
+    assert_eq!(trimmed.states, 2);
+    assert_eq!(trimmed.transition_table, vec![0, 1, 0, 1]);
+    assert_eq!(trimmed.accept_states, HashSet::from([1]));
+    assert_eq!(renumbered, vec![Some(0), Some(1), None, None, None, None]);
+    assert_eq!(equivalent(&dfa, &trimmed), Ok(Equivalence::Equivalent));
+    assert_eq!(trimmed.trim().0, trimmed);
+}
+
+#[test]
+fn trim_keeps_one_sink() {
+    // Accepts only 1. States 2 and 3 are both dead and state 3 is moved into the place of the
+    // unreachable state 1.
+    let dfa = Dfa::build(vec![2, 4, 1, 1, 3, 3, 3, 3, 2, 3], HashSet::from([4]), 5, 2).unwrap();
+    let (trimmed, renumbered) = dfa.trim();
+
+    assert_eq!(renumbered, vec![Some(0), None, Some(2), None, Some(1)]);
+    assert_eq!(trimmed.transition_table, vec![2, 1, 2, 2, 2, 2]);
+    assert_eq!(trimmed.accept_states, HashSet::from([1]));
+    assert_eq!(equivalent(&dfa, &trimmed), Ok(Equivalence::Equivalent));
+
+    // Nothing is accepted so only the start state is left as the sink
+    let empty = Dfa::build(vec![1, 1, 0, 0], HashSet::new(), 2, 2).unwrap();
+    let (trimmed, renumbered) = empty.trim();
+    assert_eq!(trimmed.transition_table, vec![0, 0]);
+    assert_eq!(renumbered, vec![Some(0), None]);
+}
+
+#[test]
+fn trim_single_state() {
+    // Trimming starts from state 0, which a Dfa without states would not have
+    assert_eq!(
+        Dfa::build(vec![], HashSet::new(), 0, 0).unwrap_err(),
+        MachineError::NoStates
+    );
+
+    let dfa = Dfa::build(vec![0, 0], HashSet::new(), 1, 2).unwrap();
+    let (trimmed, renumbered) = dfa.trim();
+    assert_eq!(trimmed, dfa);
+    assert_eq!(renumbered, vec![Some(0)]);
+}
+
+#[test]
+fn builder_lint() {
+    let mut builder = DfaBuilder::from(cluttered());
+    assert_eq!(
+        builder.lint_states(),
+        StateLint {
+            unreachable: BTreeSet::from([2, 3, 4, 5]),
+            dead: BTreeSet::from([4, 5]),
+        }
+    );
+
+    // Missing transitions are ignored rather than reported
+    let state = builder.add_state();
+    builder
+        .set_transition(SingleChar {
+            start: 1,
+            end: state,
+            char: 0,
+        })
+        .unwrap();
+    let lint = builder.lint_states();
+    assert!(!lint.unreachable.contains(&state));
+    assert!(lint.dead.contains(&state));
+}
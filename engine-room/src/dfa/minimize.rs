@@ -0,0 +1,226 @@
+use super::Dfa;
+use crate::{machine_utils::table_lookup, MachineError};
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+impl Dfa {
+    /// Minimizes this `Dfa` using Hopcroft's algorithm after removing every state that cannot be
+    /// reached from the start state.
+    ///
+    /// The states of the returned `Dfa` are numbered in breadth first order from the start state,
+    /// following characters in increasing order, so two `Dfa`s accepting the same language
+    /// minimize to equal machines. Along with the minimal `Dfa` this returns the partition of the
+    /// reachable original states into equivalence classes, indexed by the new state each class
+    /// became. States in the same class accept exactly the same inputs, which is why they were
+    /// merged. The partition does not say why two states ended up in different classes, use
+    /// [`Dfa::distinguishing_input`] to find an input that tells them apart.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::dfa::Dfa;
+    /// use std::collections::{BTreeSet, HashSet};
+    ///
+    /// // Accepts inputs ending in 1, with states 1 and 2 both tracking "read a 1 last"
+    /// let dfa = Dfa::build(vec![0, 1, 0, 2, 0, 1], HashSet::from([1, 2]), 3, 2).unwrap();
+    /// let (minimal, partition) = dfa.minimize();
+    ///
+    /// assert_eq!(minimal.transition_table(), &[0, 1, 0, 1]);
+    /// assert_eq!(partition, vec![BTreeSet::from([0]), BTreeSet::from([1, 2])]);
+    /// assert_eq!(dfa.distinguishing_input(1, 2), Ok(None));
+    /// assert_eq!(dfa.distinguishing_input(0, 1), Ok(Some(vec![])));
+    /// ```
+    pub fn minimize(&self) -> (Dfa, Vec<BTreeSet<u16>>) {
+        let reachable = self.reachable_from_start();
+        let (block_of, blocks) = self.hopcroft_partition(&reachable);
+
+        // Renumber the blocks in breadth first order from the block holding the start state
+        let mut new_state_of_block = HashMap::from([(block_of[0], 0)]);
+        let mut block_order = vec![block_of[0]];
+        let mut transition_table = Vec::with_capacity(blocks.len() * self.chars as usize);
+        let mut q = VecDeque::from([block_of[0]]);
+        while let Some(block) = q.pop_front() {
+            let representative = blocks[block][0];
+            for c in 0..self.chars {
+                let next_block = block_of[self.next_state(representative, c) as usize];
+                let next_state = *new_state_of_block.entry(next_block).or_insert_with(|| {
+                    block_order.push(next_block);
+                    q.push_back(next_block);
+                    block_order.len() as u16 - 1
+                });
+                transition_table.push(next_state);
+            }
+        }
+
+        let accept_states = block_order
+            .iter()
+            .enumerate()
+            .filter(|(_, &block)| self.accept_states.contains(&blocks[block][0]))
+            .map(|(state, _)| state as u16)
+            .collect();
+        let partition = block_order
+            .iter()
+            .map(|&block| blocks[block].iter().copied().collect())
+            .collect::<Vec<BTreeSet<u16>>>();
+
+        let dfa = Dfa::build(
+            transition_table,
+            accept_states,
+            partition.len() as u16,
+            self.chars,
+        )
+        .expect("The minimized Dfa should always be valid");
+        (dfa, partition)
+    }
+
+    /// Finds the shortest, and among those the lexicographically smallest, input that is accepted
+    /// when starting from exactly one of the two states. Returns `None` when both states accept
+    /// the same inputs, which means [`Dfa::minimize`] merges them if they are reachable.
+    pub fn distinguishing_input(
+        &self,
+        first: u16,
+        second: u16,
+    ) -> Result<Option<Vec<u16>>, MachineError> {
+        for state in [first, second] {
+            if state >= self.states {
+                return Err(MachineError::StateOutOfRange {
+                    state,
+                    states: self.states,
+                });
+            }
+        }
+
+        // Each explored pair remembers the pair it was reached from and the character read, so
+        // the input can be rebuilt once a pair with different acceptance is found
+        let mut seen = HashSet::from([(first, second)]);
+        let mut explored: Vec<(Option<usize>, u16)> = vec![(None, 0)];
+        let mut q = VecDeque::from([((first, second), 0)]);
+        while let Some(((first_state, second_state), index)) = q.pop_front() {
+            if self.accept_states.contains(&first_state)
+                != self.accept_states.contains(&second_state)
+            {
+                let mut input = vec![];
+                let mut cur = index;
+                while let (Some(parent), c) = explored[cur] {
+                    input.push(c);
+                    cur = parent;
+                }
+                input.reverse();
+                return Ok(Some(input));
+            }
+
+            for c in 0..self.chars {
+                let next = (
+                    self.next_state(first_state, c),
+                    self.next_state(second_state, c),
+                );
+                if seen.insert(next) {
+                    explored.push((Some(index), c));
+                    q.push_back((next, explored.len() - 1));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns which states can be reached from the start state
+    fn reachable_from_start(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.states as usize];
+        reachable[0] = true;
+        let mut q = VecDeque::from([0]);
+        while let Some(state) = q.pop_front() {
+            for c in 0..self.chars {
+                let next_state = self.next_state(state, c);
+                if !reachable[next_state as usize] {
+                    reachable[next_state as usize] = true;
+                    q.push_back(next_state);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Hopcroft's partition refinement over the reachable states. Returns the block each state
+    /// belongs to (unreachable states are given `usize::MAX`) and the states in each block.
+    fn hopcroft_partition(&self, reachable: &[bool]) -> (Vec<usize>, Vec<Vec<u16>>) {
+        let chars = self.chars as usize;
+
+        // inverse[table_lookup(q, c)] holds every reachable state p with a transition p -c-> q
+        let mut inverse = vec![vec![]; self.transition_table.len()];
+        for state in (0..self.states).filter(|&s| reachable[s as usize]) {
+            for c in 0..self.chars {
+                let next_state = self.next_state(state, c);
+                inverse[table_lookup(next_state as usize, c as usize, chars)].push(state);
+            }
+        }
+
+        let (accepting, rejecting): (Vec<u16>, Vec<u16>) = (0..self.states)
+            .filter(|&s| reachable[s as usize])
+            .partition(|s| self.accept_states.contains(s));
+        let mut blocks: Vec<Vec<u16>> = [accepting, rejecting]
+            .into_iter()
+            .filter(|block| !block.is_empty())
+            .collect();
+        let mut block_of = vec![usize::MAX; self.states as usize];
+        for (block, states) in blocks.iter().enumerate() {
+            for &state in states {
+                block_of[state as usize] = block;
+            }
+        }
+
+        // Only the smaller of the two starting blocks needs to be used as a splitter
+        let smallest = (0..blocks.len())
+            .min_by_key(|&block| blocks[block].len())
+            .expect("The start state is always reachable so there is at least one block");
+        let mut work: VecDeque<(usize, u16)> = (0..self.chars).map(|c| (smallest, c)).collect();
+        let mut in_work: HashSet<(usize, u16)> = work.iter().copied().collect();
+
+        while let Some((splitter, c)) = work.pop_front() {
+            in_work.remove(&(splitter, c));
+
+            // Group the states that move into the splitter on c by the block they are in
+            let mut touched: HashMap<usize, Vec<u16>> = HashMap::new();
+            for &target in &blocks[splitter] {
+                for &source in &inverse[table_lookup(target as usize, c as usize, chars)] {
+                    touched
+                        .entry(block_of[source as usize])
+                        .or_default()
+                        .push(source);
+                }
+            }
+
+            for (block, moved) in touched {
+                if moved.len() == blocks[block].len() {
+                    continue;
+                }
+
+                let new_block = blocks.len();
+                for &state in &moved {
+                    block_of[state as usize] = new_block;
+                }
+                blocks[block].retain(|&state| block_of[state as usize] == block);
+                blocks.push(moved);
+
+                for d in 0..self.chars {
+                    if in_work.contains(&(block, d)) {
+                        work.push_back((new_block, d));
+                        in_work.insert((new_block, d));
+                    } else {
+                        let smaller = if blocks[new_block].len() < blocks[block].len() {
+                            new_block
+                        } else {
+                            block
+                        };
+                        work.push_back((smaller, d));
+                        in_work.insert((smaller, d));
+                    }
+                }
+            }
+        }
+
+        for states in &mut blocks {
+            states.sort_unstable();
+        }
+        (block_of, blocks)
+    }
+}
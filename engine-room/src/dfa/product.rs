@@ -0,0 +1,130 @@
+use super::Dfa;
+use crate::MachineError;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// # Set Operation
+///
+/// How the accept states of a product `Dfa` are chosen from the accept states of the two machines
+/// it was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SetOperation {
+    /// Accepts inputs accepted by either machine
+    Union,
+    /// Accepts inputs accepted by both machines
+    Intersection,
+    /// Accepts inputs accepted by the first machine but not the second
+    Difference,
+    /// Accepts inputs accepted by exactly one of the machines
+    SymmetricDifference,
+}
+
+impl SetOperation {
+    fn accepts(self, first: bool, second: bool) -> bool {
+        match self {
+            SetOperation::Union => first || second,
+            SetOperation::Intersection => first && second,
+            SetOperation::Difference => first && !second,
+            SetOperation::SymmetricDifference => first != second,
+        }
+    }
+}
+
+impl Dfa {
+    /// Builds the product of this `Dfa` and `other`, running both machines side by side. Only the
+    /// pairs of states reachable from `(0, 0)` become states, numbered in breadth first order.
+    /// Along with the product this returns the pair `(p, q)` of states each new state came from,
+    /// where `p` is a state of `self` and `q` a state of `other`.
+    pub fn product(
+        &self,
+        other: &Dfa,
+        operation: SetOperation,
+    ) -> Result<(Dfa, Vec<(u16, u16)>), MachineError> {
+        if self.chars != other.chars {
+            return Err(MachineError::AlphabetMismatch {
+                first: self.chars,
+                second: other.chars,
+            });
+        }
+
+        let mut pairs = vec![(0, 0)];
+        let mut pair_ids = HashMap::from([((0, 0), 0)]);
+        let mut transition_table = Vec::new();
+        let mut q = VecDeque::from([0]);
+        while let Some(cur_state) = q.pop_front() {
+            let (p, r) = pairs[cur_state as usize];
+            for c in 0..self.chars {
+                let next = (self.next_state(p, c), other.next_state(r, c));
+                let next_state = match pair_ids.get(&next) {
+                    Some(&state) => state,
+                    None => {
+                        let state =
+                            u16::try_from(pairs.len()).map_err(|_| MachineError::TooManyStates)?;
+                        pair_ids.insert(next, state);
+                        pairs.push(next);
+                        q.push_back(state);
+                        state
+                    }
+                };
+                transition_table.push(next_state);
+            }
+        }
+
+        let accept_states = pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, (p, r))| {
+                operation.accepts(
+                    self.accept_states.contains(p),
+                    other.accept_states.contains(r),
+                )
+            })
+            .map(|(state, _)| state as u16)
+            .collect();
+        let dfa = Dfa::build(
+            transition_table,
+            accept_states,
+            pairs.len() as u16,
+            self.chars,
+        )
+        .expect("The product of two valid Dfas should always be valid");
+        Ok((dfa, pairs))
+    }
+
+    /// Accepts inputs accepted by either machine. See [`Dfa::product`].
+    pub fn union(&self, other: &Dfa) -> Result<(Dfa, Vec<(u16, u16)>), MachineError> {
+        self.product(other, SetOperation::Union)
+    }
+
+    /// Accepts inputs accepted by both machines. See [`Dfa::product`].
+    pub fn intersection(&self, other: &Dfa) -> Result<(Dfa, Vec<(u16, u16)>), MachineError> {
+        self.product(other, SetOperation::Intersection)
+    }
+
+    /// Accepts inputs accepted by this machine but not `other`. See [`Dfa::product`].
+    pub fn difference(&self, other: &Dfa) -> Result<(Dfa, Vec<(u16, u16)>), MachineError> {
+        self.product(other, SetOperation::Difference)
+    }
+
+    /// Accepts inputs accepted by exactly one of the machines. See [`Dfa::product`].
+    pub fn symmetric_difference(
+        &self,
+        other: &Dfa,
+    ) -> Result<(Dfa, Vec<(u16, u16)>), MachineError> {
+        self.product(other, SetOperation::SymmetricDifference)
+    }
+
+    /// Accepts exactly the inputs this machine rejects. The states and transitions are kept as
+    /// they are and only the accept states are flipped.
+    pub fn complement(&self) -> Dfa {
+        let accept_states: HashSet<u16> = (0..self.states)
+            .filter(|s| !self.accept_states.contains(s))
+            .collect();
+        Dfa {
+            transition_table: self.transition_table.clone(),
+            accept_states,
+            states: self.states,
+            chars: self.chars,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use super::{Dfa, DfaBuilder};
+use crate::{
+    machine_utils::{reachable_states, remove_states},
+    transitions::SingleChar,
+    StateMachineBuilder,
+};
+
+use std::collections::BTreeSet;
+
+/// # State Lint
+///
+/// The states of a machine that can be removed without changing what it accepts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateLint {
+    /// States that can not be reached from the start state
+    pub unreachable: BTreeSet<u16>,
+    /// States that can not reach an accept state
+    pub dead: BTreeSet<u16>,
+}
+
+impl Dfa {
+    /// The states that can be reached from the start state
+    pub fn reachable_states(&self) -> BTreeSet<u16> {
+        reachable_states(self.states, self.edges(), [0])
+    }
+
+    /// The states that can reach an accept state
+    pub fn coreachable_states(&self) -> BTreeSet<u16> {
+        reachable_states(
+            self.states,
+            self.edges().map(|(from, to)| (to, from)),
+            self.accept_states.iter().copied(),
+        )
+    }
+
+    /// Removes every state that is unreachable or dead. A `Dfa` needs a transition for every
+    /// character, so when a reachable state is dead the lowest such state is kept as a sink that
+    /// every dead state is merged into.
+    ///
+    /// The states are removed from the highest down in the same way as
+    /// [`DfaBuilder::remove_state`](crate::StateMachineBuilder::remove_state), so a removed state
+    /// takes the number of the last remaining state. Along with the trimmed `Dfa` this returns
+    /// the new number of every original state, or `None` when it was removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::dfa::Dfa;
+    /// use std::collections::HashSet;
+    ///
+    /// // State 1 is unreachable
+    /// let dfa = Dfa::build(vec![0, 2, 1, 1, 2, 0], HashSet::from([2]), 3, 2).unwrap();
+    /// let (trimmed, renumbered) = dfa.trim();
+    ///
+    /// assert_eq!(trimmed.transition_table(), &[0, 1, 1, 0]);
+    /// assert_eq!(renumbered, vec![Some(0), None, Some(1)]);
+    /// ```
+    pub fn trim(&self) -> (Dfa, Vec<Option<u16>>) {
+        let reachable = self.reachable_states();
+        let coreachable = self.coreachable_states();
+        let sink = reachable
+            .iter()
+            .copied()
+            .find(|state| !coreachable.contains(state));
+
+        let mut builder = DfaBuilder::from(self.clone());
+        if let Some(sink) = sink {
+            for &start in &reachable {
+                for char in 0..self.chars {
+                    if !coreachable.contains(&self.next_state(start, char)) {
+                        builder
+                            .set_transition(SingleChar {
+                                start,
+                                end: sink,
+                                char,
+                            })
+                            .expect("The states and character exist");
+                    }
+                }
+            }
+        }
+
+        let remove = (0..self.states)
+            .filter(|state| {
+                !reachable.contains(state) || !coreachable.contains(state) && Some(*state) != sink
+            })
+            .collect();
+        let renumbered = remove_states(&mut builder, self.states, &remove)
+            .expect("The start state is always kept");
+        let dfa =
+            Dfa::try_from(builder).expect("Every kept transition goes to a kept state or the sink");
+        (dfa, renumbered)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.transition_table
+            .iter()
+            .enumerate()
+            .map(|(index, &to)| ((index / self.chars as usize) as u16, to))
+    }
+}
+
+impl DfaBuilder {
+    /// Finds the unreachable and dead states using only the transitions that have been set, so
+    /// they can be reported before the builder is turned into a `Dfa`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{
+    ///     dfa::{Dfa, DfaBuilder, StateLint},
+    ///     StateMachineBuilder,
+    /// };
+    /// use std::collections::{BTreeSet, HashSet};
+    ///
+    /// let mut builder = DfaBuilder::from(Dfa::build(vec![0], HashSet::from([0]), 1, 1).unwrap());
+    /// builder.add_state();
+    ///
+    /// assert_eq!(
+    ///     builder.lint_states(),
+    ///     StateLint {
+    ///         unreachable: BTreeSet::from([1]),
+    ///         dead: BTreeSet::from([1]),
+    ///     }
+    /// );
+    /// ```
+    pub fn lint_states(&self) -> StateLint {
+        let reachable = reachable_states(self.states, self.edges(), [0]);
+        let coreachable = reachable_states(
+            self.states,
+            self.edges().map(|(from, to)| (to, from)),
+            self.accept_states.iter().copied(),
+        );
+
+        StateLint {
+            unreachable: (0..self.states)
+                .filter(|state| !reachable.contains(state))
+                .collect(),
+            dead: (0..self.states)
+                .filter(|state| !coreachable.contains(state))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Problems found in a machine while it is still being built
+
+use crate::machine_utils::reachable_states;
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+};
+
+/// # Severity
+///
+/// How serious a [`Diagnostic`] is. Errors stop the builder from being turned into its machine,
+/// while warnings point at parts of a machine that are valid but most likely a mistake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// # Diagnostic
+///
+/// A problem with a machine being built, along with the state and character it refers to so the
+/// editor can point at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub state: Option<u16>,
+    pub char: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// A deterministic machine has no transition out of `state` on `char`
+    MissingTransition,
+    /// A deterministic machine has more than one transition out of `state` on `char`
+    DuplicateTransition,
+    /// No state is an accept state, so nothing is accepted
+    NoAcceptStates,
+    /// The accept state `state` can not be reached from the start state
+    UnreachableAcceptState,
+}
+
+impl DiagnosticKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            DiagnosticKind::MissingTransition | DiagnosticKind::DuplicateTransition => {
+                Severity::Error
+            }
+            DiagnosticKind::NoAcceptStates | DiagnosticKind::UnreachableAcceptState => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl Diagnostic {
+    pub(crate) fn new(kind: DiagnosticKind, state: Option<u16>, char: Option<u16>) -> Diagnostic {
+        Diagnostic {
+            severity: kind.severity(),
+            kind,
+            state,
+            char,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.unwrap_or_default();
+        let char = self.char.unwrap_or_default();
+        match self.kind {
+            DiagnosticKind::MissingTransition => {
+                write!(f, "state {state} has no transition on character {char}")
+            }
+            DiagnosticKind::DuplicateTransition => write!(
+                f,
+                "state {state} has more than one transition on character {char}"
+            ),
+            DiagnosticKind::NoAcceptStates => write!(f, "there are no accept states"),
+            DiagnosticKind::UnreachableAcceptState => write!(
+                f,
+                "accept state {state} can not be reached from the start state"
+            ),
+        }
+    }
+}
+
+/// The warnings about the accept states of a machine with the given transitions as `(from, to)`.
+/// Unreachable accept states are reported in increasing order.
+pub(crate) fn accept_state_diagnostics(
+    states: u16,
+    edges: impl IntoIterator<Item = (u16, u16)>,
+    accept_states: &HashSet<u16>,
+) -> Vec<Diagnostic> {
+    if accept_states.is_empty() {
+        return vec![Diagnostic::new(DiagnosticKind::NoAcceptStates, None, None)];
+    }
+
+    let reachable = reachable_states(states, edges, [0]);
+    let mut unreachable: Vec<u16> = accept_states
+        .iter()
+        .copied()
+        .filter(|state| !reachable.contains(state))
+        .collect();
+    unreachable.sort_unstable();
+    unreachable
+        .into_iter()
+        .map(|state| Diagnostic::new(DiagnosticKind::UnreachableAcceptState, Some(state), None))
+        .collect()
+}
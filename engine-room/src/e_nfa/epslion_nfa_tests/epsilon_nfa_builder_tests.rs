@@ -0,0 +1,340 @@
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticKind},
+    e_nfa::{EpsilonNfa, EpsilonNfaBuilder},
+    transitions::SingleCharOrEpsilon,
+    MachineError, StateMachine, StateMachineBuilder,
+};
+use std::{cmp::min, collections::HashSet};
+
+// Accepts 0* 1*, using an epsilon transition from the 0 loop to the 1 loop
+fn zeros_then_ones() -> EpsilonNfa {
+    EpsilonNfa::build(
+        vec![
+            // State 0
+            HashSet::from([0]),
+            HashSet::new(),
+            HashSet::from([1]),
+            // State 1
+            HashSet::new(),
+            HashSet::from([1]),
+            HashSet::new(),
+        ],
+        HashSet::from([1]),
+        2,
+        2,
+    )
+    .unwrap()
+}
+
+#[test]
+fn create_builder_from_scratch() {
+    let _builder = EpsilonNfaBuilder::new(zeros_then_ones());
+}
+
+#[test]
+fn create_builder_from_into() {
+    let _builder: EpsilonNfaBuilder = zeros_then_ones().into();
+}
+
+#[test]
+fn round_trip() {
+    let e_nfa = zeros_then_ones();
+    let builder: EpsilonNfaBuilder = e_nfa.clone().into();
+
+    assert_eq!(EpsilonNfa::try_from(builder).unwrap(), e_nfa);
+}
+
+#[test]
+fn round_trip_keeps_epsilon_transitions() {
+    let builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    // The builder holds the transitions that were drawn, not the closed ones of the inner Nfa
+    assert_eq!(builder.transition_table[0], HashSet::from([0]));
+    assert_eq!(builder.transition_table[1], HashSet::new());
+    assert_eq!(builder.transition_table[2], HashSet::from([1]));
+}
+
+#[test]
+fn closures_recomputed_on_build() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    // Remove the epsilon transition so only 0* is accepted
+    assert!(builder
+        .remove_transition(SingleCharOrEpsilon {
+            start: 0,
+            end: 1,
+            char: None,
+        })
+        .unwrap());
+    builder.add_accept_state(0).unwrap();
+    let e_nfa: EpsilonNfa = builder.clone().try_into().unwrap();
+    assert!(e_nfa.accepts(&[0, 0]).unwrap());
+    assert!(!e_nfa.accepts(&[0, 1]).unwrap());
+
+    // Adding it back allows the 1s again
+    assert!(builder
+        .add_transition(SingleCharOrEpsilon {
+            start: 0,
+            end: 1,
+            char: None,
+        })
+        .unwrap());
+    let e_nfa: EpsilonNfa = builder.try_into().unwrap();
+    assert!(e_nfa.accepts(&[0, 1, 1]).unwrap());
+    assert!(!e_nfa.accepts(&[1, 0]).unwrap());
+}
+
+#[test]
+fn add_state() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    assert_eq!(builder.add_state(), 2);
+    assert_eq!(builder.states, 2 + 1);
+    assert_eq!(builder.transition_table.len(), 9);
+
+    let new_e_nfa: EpsilonNfa = builder.try_into().unwrap();
+    assert!(new_e_nfa.accepts(&[0, 1]).unwrap());
+}
+
+#[test]
+fn remove_state_last() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    assert_eq!(builder.remove_state(1).unwrap(), None);
+    assert_eq!(builder.states, 1);
+    assert_eq!(
+        builder.transition_table,
+        vec![HashSet::from([0]), HashSet::new(), HashSet::new()]
+    );
+    assert_eq!(builder.accept_states, HashSet::new());
+}
+
+#[test]
+fn remove_state_first() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    assert_eq!(builder.remove_state(0).unwrap(), Some(1));
+    assert_eq!(builder.states, 1);
+    assert_eq!(
+        builder.transition_table,
+        vec![HashSet::new(), HashSet::from([0]), HashSet::new()]
+    );
+    assert_eq!(builder.accept_states, HashSet::from([0]));
+}
+
+#[test]
+fn remove_state_invalid_no_effect() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+    let copy = builder.clone();
+
+    for i in 2..100 {
+        assert_eq!(
+            builder.remove_state(i).unwrap_err(),
+            MachineError::StateOutOfRange {
+                state: i,
+                states: 2
+            }
+        );
+        assert_eq!(builder, copy);
+    }
+
+    assert_eq!(builder.remove_state(0).unwrap(), Some(1));
+    let copy = builder.clone();
+
+    assert_eq!(
+        builder.remove_state(0).unwrap_err(),
+        MachineError::LastState
+    );
+    assert_eq!(builder, copy);
+}
+
+#[test]
+fn add_then_remove() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+    let copy = builder.clone();
+
+    for i in 1..100 {
+        for _ in 0..i {
+            builder.add_state();
+        }
+
+        for _ in 0..i {
+            builder
+                .remove_state(min(builder.states - 1, 2 + i % 7))
+                .unwrap();
+        }
+        assert_eq!(builder, copy);
+    }
+}
+
+#[test]
+fn set_transition() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    builder
+        .set_transition(SingleCharOrEpsilon {
+            start: 1,
+            end: 0,
+            char: None,
+        })
+        .unwrap();
+    assert_eq!(builder.transition_table[5], HashSet::from([0]));
+
+    builder
+        .set_transition(SingleCharOrEpsilon {
+            start: 0,
+            end: 1,
+            char: Some(0),
+        })
+        .unwrap();
+    assert_eq!(builder.transition_table[0], HashSet::from([1]));
+
+    assert_eq!(
+        builder
+            .set_transition(SingleCharOrEpsilon {
+                start: 0,
+                end: 2,
+                char: None,
+            })
+            .unwrap_err(),
+        MachineError::StateOutOfRange {
+            state: 2,
+            states: 2
+        }
+    );
+    assert_eq!(
+        builder
+            .set_transition(SingleCharOrEpsilon {
+                start: 0,
+                end: 0,
+                char: Some(2),
+            })
+            .unwrap_err(),
+        MachineError::CharOutOfRange { char: 2, chars: 2 }
+    );
+}
+
+#[test]
+fn set_start_state() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+    let copy = builder.clone();
+    builder.set_start_state(0).unwrap();
+    assert_eq!(builder, copy);
+
+    builder.set_start_state(1).unwrap();
+    assert_eq!(
+        builder.transition_table,
+        vec![
+            HashSet::new(),
+            HashSet::from([0]),
+            HashSet::new(),
+            HashSet::from([1]),
+            HashSet::new(),
+            HashSet::from([0]),
+        ]
+    );
+    assert_eq!(builder.accept_states, HashSet::from([0]));
+
+    for i in 2..100 {
+        assert!(builder.set_start_state(i).is_err());
+    }
+}
+
+#[test]
+fn add_char() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+    let copy = builder.clone();
+    builder.add_char();
+    builder.remove_char(2).unwrap();
+    assert_eq!(builder, copy);
+
+    builder.add_char();
+    // The new character goes before the epsilon column
+    assert_eq!(
+        builder.transition_table,
+        vec![
+            HashSet::from([0]),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::from([1]),
+            HashSet::new(),
+            HashSet::from([1]),
+            HashSet::new(),
+            HashSet::new(),
+        ]
+    );
+    assert_eq!(builder.chars, 3);
+}
+
+#[test]
+fn remove_char() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    assert_eq!(
+        builder.remove_char(2).unwrap_err(),
+        MachineError::CharOutOfRange { char: 2, chars: 2 }
+    );
+    assert_eq!(builder.remove_char(0).unwrap(), Some(1));
+    assert_eq!(
+        builder.transition_table,
+        vec![
+            HashSet::new(),
+            HashSet::from([1]),
+            HashSet::from([1]),
+            HashSet::new(),
+        ]
+    );
+    assert_eq!(builder.remove_char(0).unwrap_err(), MachineError::LastChar);
+
+    let e_nfa: EpsilonNfa = builder.try_into().unwrap();
+    assert!(e_nfa.accepts(&[0, 0]).unwrap());
+}
+
+#[test]
+fn add_and_remove_accept_state() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+
+    assert!(!builder.add_accept_state(1).unwrap());
+    assert!(builder.add_accept_state(0).unwrap());
+    assert_eq!(builder.accept_states, HashSet::from([0, 1]));
+    assert!(builder.remove_accept_state(1).unwrap());
+    assert!(!builder.remove_accept_state(1).unwrap());
+    assert!(builder.add_accept_state(2).is_err());
+    assert!(builder.remove_accept_state(2).is_err());
+}
+
+#[test]
+fn validate() {
+    let mut builder: EpsilonNfaBuilder = zeros_then_ones().into();
+    // State 1 is only reached through the epsilon transition
+    builder
+        .remove_transition(SingleCharOrEpsilon {
+            start: 1,
+            end: 1,
+            char: Some(1),
+        })
+        .unwrap();
+    assert_eq!(builder.validate(), vec![]);
+
+    builder
+        .remove_transition(SingleCharOrEpsilon {
+            start: 0,
+            end: 1,
+            char: None,
+        })
+        .unwrap();
+    assert_eq!(
+        builder.validate(),
+        vec![Diagnostic::new(
+            DiagnosticKind::UnreachableAcceptState,
+            Some(1),
+            None
+        )]
+    );
+
+    builder.remove_accept_state(1).unwrap();
+    assert_eq!(
+        builder.validate(),
+        vec![Diagnostic::new(DiagnosticKind::NoAcceptStates, None, None)]
+    );
+}
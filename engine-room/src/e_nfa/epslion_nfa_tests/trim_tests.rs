@@ -0,0 +1,51 @@
+use crate::{e_nfa::EpsilonNfa, equivalent, Equivalence, StateMachine};
+use std::collections::{BTreeSet, HashSet};
+
+// Accepts 0*1 where state 1 is only reached by an epsilon transition, state 2 is dead and state 3
+// is unreachable
+fn cluttered() -> EpsilonNfa {
+    EpsilonNfa::build(
+        vec![
+            // State 0
+            HashSet::from([0]),
+            HashSet::new(),
+            HashSet::from([1, 2]),
+            // State 1
+            HashSet::new(),
+            HashSet::from([3]),
+            HashSet::new(),
+            // State 2
+            HashSet::from([2]),
+            HashSet::new(),
+            HashSet::new(),
+            // State 3
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        ],
+        HashSet::from([3]),
+        4,
+        2,
+    )
+    .unwrap()
+}
+
+#[test]
+fn epsilon_transitions_are_followed() {
+    let e_nfa = cluttered();
+
+    assert_eq!(e_nfa.reachable_states(), BTreeSet::from([0, 1, 2, 3]));
+    assert_eq!(e_nfa.coreachable_states(), BTreeSet::from([0, 1, 3]));
+}
+
+#[test]
+fn trim_removes_and_renumbers() {
+    let e_nfa = cluttered();
+    let (trimmed, renumbered) = e_nfa.trim();
+
+    // State 3 takes the place of the dead state 2
+    assert_eq!(renumbered, vec![Some(0), Some(1), None, Some(2)]);
+    assert_eq!(trimmed.states(), 3);
+    assert_eq!(trimmed.accept_states(), &HashSet::from([2]));
+    assert_eq!(equivalent(&e_nfa, &trimmed), Ok(Equivalence::Equivalent));
+}
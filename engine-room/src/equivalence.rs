@@ -0,0 +1,200 @@
+//! Language equivalence of finite automata
+
+use crate::{FiniteAutomaton, MachineError};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+/// # Equivalence
+///
+/// The result of comparing the languages of two machines with [`equivalent`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Equivalence {
+    /// Both machines accept exactly the same inputs
+    Equivalent,
+    /// `input` is the shortest, and among those the lexicographically smallest, input that is
+    /// accepted by exactly one of the machines
+    Counterexample {
+        input: Vec<u16>,
+        accepted_by_first: bool,
+    },
+}
+
+/// Checks if two finite automata accept the same language.
+///
+/// Both machines are determinized on the fly and explored together breadth first, following
+/// characters in increasing order. The first pair of state sets where exactly one machine accepts
+/// is therefore reached by the shortlex-least counterexample. Both machines must have the same
+/// number of characters.
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::{dfa::Dfa, equivalent, Equivalence};
+/// use std::collections::HashSet;
+///
+/// // Odd number of 0s
+/// let odd = Dfa::build(vec![1, 0], HashSet::from([1]), 2, 1).unwrap();
+/// // Even number of 0s
+/// let even = Dfa::build(vec![1, 0], HashSet::from([0]), 2, 1).unwrap();
+///
+/// assert_eq!(equivalent(&odd, &odd), Ok(Equivalence::Equivalent));
+/// assert_eq!(
+///     equivalent(&odd, &even),
+///     Ok(Equivalence::Counterexample {
+///         input: vec![],
+///         accepted_by_first: false
+///     })
+/// );
+/// ```
+pub fn equivalent(
+    first: &impl FiniteAutomaton,
+    second: &impl FiniteAutomaton,
+) -> Result<Equivalence, MachineError> {
+    if first.chars() != second.chars() {
+        return Err(MachineError::AlphabetMismatch {
+            first: first.chars(),
+            second: second.chars(),
+        });
+    }
+
+    // Each explored pair remembers the pair it was reached from and the character read, so the
+    // counterexample can be rebuilt once a distinguishing pair is found
+    let start = (first.start_states(), second.start_states());
+    let mut seen: HashSet<(BTreeSet<u16>, BTreeSet<u16>)> = HashSet::from([start.clone()]);
+    let mut explored: Vec<(Option<usize>, u16)> = vec![(None, 0)];
+    let mut q = VecDeque::from([(start, 0)]);
+
+    while let Some(((first_states, second_states), index)) = q.pop_front() {
+        let accepted_by_first = first.is_accepting(&first_states);
+        if accepted_by_first != second.is_accepting(&second_states) {
+            let mut input = vec![];
+            let mut cur = index;
+            while let (Some(parent), c) = explored[cur] {
+                input.push(c);
+                cur = parent;
+            }
+            input.reverse();
+            return Ok(Equivalence::Counterexample {
+                input,
+                accepted_by_first,
+            });
+        }
+
+        for c in 0..first.chars() {
+            let next = (
+                first.next_states(&first_states, c),
+                second.next_states(&second_states, c),
+            );
+            if seen.insert(next.clone()) {
+                explored.push((Some(index), c));
+                q.push_back((next, explored.len() - 1));
+            }
+        }
+    }
+
+    Ok(Equivalence::Equivalent)
+}
+
+#[cfg(test)]
+mod equivalence_tests {
+    use super::{equivalent, Equivalence};
+    use crate::{dfa::Dfa, e_nfa::EpsilonNfa, machine_utils::ends_in_one, MachineError};
+    use std::collections::HashSet;
+
+    #[test]
+    fn nfa_equals_its_subset_construction() {
+        let nfa = ends_in_one();
+        let (dfa, _) = Dfa::from_nfa(&nfa).unwrap();
+
+        assert_eq!(equivalent(&nfa, &dfa), Ok(Equivalence::Equivalent));
+        assert_eq!(equivalent(&dfa, &nfa), Ok(Equivalence::Equivalent));
+        assert_eq!(
+            equivalent(&dfa.minimize().0, &nfa),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+
+    #[test]
+    fn shortlex_least_counterexample() {
+        // Accepts strings ending in 01
+        let ends_in_01 = Dfa::build(vec![1, 0, 1, 2, 1, 0], HashSet::from([2]), 3, 2).unwrap();
+
+        // "1" is the shortest string ending in 1 but not 01
+        assert_eq!(
+            equivalent(&ends_in_one(), &ends_in_01),
+            Ok(Equivalence::Counterexample {
+                input: vec![1],
+                accepted_by_first: true
+            })
+        );
+    }
+
+    #[test]
+    fn counterexample_prefers_smaller_chars() {
+        // `first` accepts every string of length 2 while `second` rejects 01 and 10
+        let first = Dfa::build(
+            vec![1, 2, 3, 3, 4, 4, 5, 5, 5, 5, 5, 5],
+            HashSet::from([3, 4]),
+            6,
+            2,
+        )
+        .unwrap();
+        let second = Dfa::build(
+            vec![1, 2, 3, 5, 5, 4, 5, 5, 5, 5, 5, 5],
+            HashSet::from([3, 4]),
+            6,
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(
+            equivalent(&first, &second),
+            Ok(Equivalence::Counterexample {
+                input: vec![0, 1],
+                accepted_by_first: true
+            })
+        );
+    }
+
+    #[test]
+    fn epsilon_nfa_against_dfa() {
+        // Accepts 0*1* using an epsilon transition between the two loops
+        let e_nfa = EpsilonNfa::build(
+            vec![
+                HashSet::from([0]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+            ],
+            HashSet::from([1]),
+            2,
+            2,
+        )
+        .unwrap();
+        let dfa = Dfa::build(vec![0, 1, 2, 1, 2, 2], HashSet::from([0, 1]), 3, 2).unwrap();
+        let wrong_dfa = Dfa::build(vec![0, 1, 1, 1, 2, 2], HashSet::from([0, 1]), 3, 2).unwrap();
+
+        assert_eq!(equivalent(&e_nfa, &dfa), Ok(Equivalence::Equivalent));
+        assert_eq!(
+            equivalent(&e_nfa, &wrong_dfa),
+            Ok(Equivalence::Counterexample {
+                input: vec![1, 0],
+                accepted_by_first: false
+            })
+        );
+    }
+
+    #[test]
+    fn alphabet_mismatch() {
+        let one_char = Dfa::build(vec![0], HashSet::from([0]), 1, 1).unwrap();
+
+        assert_eq!(
+            equivalent(&one_char, &ends_in_one()),
+            Err(MachineError::AlphabetMismatch {
+                first: 1,
+                second: 2
+            })
+        );
+    }
+}
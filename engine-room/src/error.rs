@@ -0,0 +1,197 @@
+//! Error type shared by every machine and builder in engine-room
+
+use std::{error::Error, fmt};
+
+/// # Machine Error
+///
+/// Describes why building, editing or running a state machine failed. Each variant carries the
+/// offending state, character or index so the caller can point the user at the exact problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MachineError {
+    /// The input contains a character at `index` that is not part of the machine's alphabet
+    InvalidInputChar { index: usize, char: u16, chars: u16 },
+    /// The transition table does not have `states * chars` entries (plus the epsilon column for
+    /// epsilon machines)
+    WrongTableLength { expected: usize, actual: usize },
+    /// The transition table entry at `index` points at a state that does not exist
+    InvalidTransitionTarget {
+        index: usize,
+        state: u16,
+        states: u16,
+    },
+    /// One of the accept states does not exist
+    InvalidAcceptState { state: u16, states: u16 },
+    /// The reject state of a Turing machine does not exist
+    InvalidRejectState { state: u16, states: u16 },
+    /// The accept and reject states of a Turing machine are the same state
+    SameHaltingState { state: u16 },
+    /// A state passed to a builder does not exist
+    StateOutOfRange { state: u16, states: u16 },
+    /// A character passed to a builder is not part of the alphabet
+    CharOutOfRange { char: u16, chars: u16 },
+    /// A machine must have at least one state
+    NoStates,
+    /// A machine must have at least one character in its alphabet
+    NoChars,
+    /// The only remaining state of a machine cannot be removed
+    LastState,
+    /// The only remaining character of a machine cannot be removed
+    LastChar,
+    /// A deterministic machine is missing the transition for `char` out of `state`
+    MissingTransition { state: u16, char: u16 },
+    /// A construction would need more states than can be numbered by a `u16`
+    TooManyStates,
+    /// Two machines that must share an alphabet have a different number of characters
+    AlphabetMismatch { first: u16, second: u16 },
+    /// A stack symbol is not part of the stack alphabet of a pushdown automaton
+    StackCharOutOfRange { char: u16, stack_chars: u16 },
+    /// A pushdown automaton needs at least one stack symbol to start its stack with
+    NoStackChars,
+    /// A tape symbol is not part of the tape alphabet of a Turing machine
+    TapeCharOutOfRange { char: u16, tape_chars: u16 },
+    /// A construction would need more tape symbols than can be numbered by a `u16`
+    TooManyTapeChars,
+    /// The transition table entry at `index` uses a tape movement the machine does not support
+    InvalidMovement { index: usize },
+    /// A multi-tape machine must have at least one tape
+    NoTapes,
+    /// A transition of a multi-tape machine reads or moves a different number of tapes than the
+    /// machine has
+    WrongTapeCount { expected: u16, actual: usize },
+    /// A grammar must have at least one nonterminal
+    NoNonterminals,
+    /// A production uses a nonterminal that the grammar does not have
+    NonterminalOutOfRange { nonterminal: u16, nonterminals: u16 },
+    /// The operation only works on grammars in Chomsky normal form
+    NotChomskyNormalForm,
+    /// A grammar would need more nonterminals, or grammar symbols, than can be numbered by a `u16`
+    TooManyNonterminals,
+    /// The production of a grammar is not of the form `A -> w` or `A -> wB`
+    NotRightLinear { production: usize },
+    /// A transducer must have at least one symbol in its output alphabet
+    NoOutputs,
+    /// An output symbol is not part of the output alphabet of a transducer
+    OutputOutOfRange { output: u16, outputs: u16 },
+    /// A machine that is not guaranteed to halt ran out of its budget before deciding on the input
+    BudgetExhausted,
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MachineError::InvalidInputChar { index, char, chars } => write!(
+                f,
+                "input character {char} at index {index} is not in the alphabet of {chars} characters"
+            ),
+            MachineError::WrongTableLength { expected, actual } => write!(
+                f,
+                "transition table has {actual} entries but {expected} were expected"
+            ),
+            MachineError::InvalidTransitionTarget {
+                index,
+                state,
+                states,
+            } => write!(
+                f,
+                "transition table entry {index} points at state {state} but the machine only has {states} states"
+            ),
+            MachineError::InvalidAcceptState { state, states } => write!(
+                f,
+                "accept state {state} does not exist in a machine with {states} states"
+            ),
+            MachineError::InvalidRejectState { state, states } => write!(
+                f,
+                "reject state {state} does not exist in a machine with {states} states"
+            ),
+            MachineError::SameHaltingState { state } => write!(
+                f,
+                "state {state} cannot be both the accept and the reject state"
+            ),
+            MachineError::StateOutOfRange { state, states } => write!(
+                f,
+                "state {state} does not exist in a machine with {states} states"
+            ),
+            MachineError::CharOutOfRange { char, chars } => write!(
+                f,
+                "character {char} is not in the alphabet of {chars} characters"
+            ),
+            MachineError::NoStates => write!(f, "a machine must have at least one state"),
+            MachineError::NoChars => write!(f, "a machine must have at least one character"),
+            MachineError::LastState => write!(f, "the last state of a machine cannot be removed"),
+            MachineError::LastChar => {
+                write!(f, "the last character of a machine cannot be removed")
+            }
+            MachineError::MissingTransition { state, char } => write!(
+                f,
+                "state {state} has no transition for character {char}"
+            ),
+            MachineError::TooManyStates => write!(
+                f,
+                "the machine would need more than {} states",
+                u16::MAX
+            ),
+            MachineError::AlphabetMismatch { first, second } => write!(
+                f,
+                "the machines have different alphabets of {first} and {second} characters"
+            ),
+            MachineError::StackCharOutOfRange { char, stack_chars } => write!(
+                f,
+                "symbol {char} is not in the stack alphabet of {stack_chars} symbols"
+            ),
+            MachineError::NoStackChars => {
+                write!(f, "a pushdown automaton must have at least one stack symbol")
+            }
+            MachineError::TapeCharOutOfRange { char, tape_chars } => write!(
+                f,
+                "symbol {char} is not in the tape alphabet of {tape_chars} symbols"
+            ),
+            MachineError::TooManyTapeChars => write!(
+                f,
+                "the machine would need more than {} tape symbols",
+                u16::MAX
+            ),
+            MachineError::InvalidMovement { index } => write!(
+                f,
+                "transition table entry {index} uses a tape movement this machine does not support"
+            ),
+            MachineError::NoTapes => write!(f, "a multi-tape machine must have at least one tape"),
+            MachineError::WrongTapeCount { expected, actual } => write!(
+                f,
+                "a transition uses {actual} tapes but the machine has {expected} tapes"
+            ),
+            MachineError::NoNonterminals => {
+                write!(f, "a grammar must have at least one nonterminal")
+            }
+            MachineError::NonterminalOutOfRange {
+                nonterminal,
+                nonterminals,
+            } => write!(
+                f,
+                "nonterminal {nonterminal} does not exist in a grammar with {nonterminals} nonterminals"
+            ),
+            MachineError::NotChomskyNormalForm => {
+                write!(f, "the grammar is not in Chomsky normal form")
+            }
+            MachineError::TooManyNonterminals => write!(
+                f,
+                "the grammar would need more than {} nonterminals",
+                u16::MAX
+            ),
+            MachineError::NotRightLinear { production } => {
+                write!(f, "production {production} is not right linear")
+            }
+            MachineError::NoOutputs => {
+                write!(f, "a transducer must have at least one output symbol")
+            }
+            MachineError::OutputOutOfRange { output, outputs } => write!(
+                f,
+                "output {output} is not part of an output alphabet with {outputs} symbols"
+            ),
+            MachineError::BudgetExhausted => {
+                write!(f, "the machine ran out of its budget before halting")
+            }
+        }
+    }
+}
+
+impl Error for MachineError {}
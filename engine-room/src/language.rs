@@ -0,0 +1,384 @@
+//! Properties of the language accepted by a finite automaton
+//!
+//! Every function determinizes the machine on the fly, so the same questions can be asked of a
+//! `Dfa`, `Nfa` or `EpsilonNfa`. The sets of states reachable from the start states are explored
+//! once, and each of them is then treated as a single state of a deterministic machine.
+
+use crate::{big_uint::BigUint, machine_utils::table_lookup, FiniteAutomaton};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+mod words;
+
+pub use words::{accepted_words, rejected_words, Words};
+
+/// Checks if the machine accepts no input at all
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::{dfa::Dfa, language};
+/// use std::collections::HashSet;
+///
+/// // State 1 accepts but can not be reached
+/// let dfa = Dfa::build(vec![0, 1], HashSet::from([1]), 2, 1).unwrap();
+/// assert!(language::is_empty(&dfa));
+/// ```
+pub fn is_empty(machine: &impl FiniteAutomaton) -> bool {
+    !SubsetGraph::explore(machine).accepting.contains(&true)
+}
+
+/// Checks if the machine accepts every input
+pub fn is_universal(machine: &impl FiniteAutomaton) -> bool {
+    !SubsetGraph::explore(machine).accepting.contains(&false)
+}
+
+/// Checks if the machine accepts only finitely many inputs
+pub fn is_finite(machine: &impl FiniteAutomaton) -> bool {
+    let graph = SubsetGraph::explore(machine);
+    let live = graph.can_reach(true);
+    graph.topological_order(&live).is_some()
+}
+
+/// Checks if the machine rejects only finitely many inputs
+pub fn is_cofinite(machine: &impl FiniteAutomaton) -> bool {
+    let graph = SubsetGraph::explore(machine);
+    let live = graph.can_reach(false);
+    graph.topological_order(&live).is_some()
+}
+
+/// The number of different inputs of length `len` that the machine accepts
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::{dfa::Dfa, language};
+/// use std::collections::HashSet;
+///
+/// // Accepts every input over two characters
+/// let dfa = Dfa::build(vec![0, 0], HashSet::from([0]), 1, 2).unwrap();
+/// assert_eq!(language::count_accepted(&dfa, 3).to_u64(), Some(8));
+/// assert_eq!(
+///     language::count_accepted(&dfa, 100).to_string(),
+///     "1267650600228229401496703205376"
+/// );
+/// ```
+pub fn count_accepted(machine: &impl FiniteAutomaton, len: usize) -> BigUint {
+    let graph = SubsetGraph::explore(machine);
+
+    // counts[state] is the number of accepted inputs of the current length starting from state
+    let mut counts: Vec<BigUint> = graph
+        .accepting
+        .iter()
+        .map(|&accepting| BigUint::from(accepting as u64))
+        .collect();
+    for _ in 0..len {
+        counts = (0..graph.accepting.len())
+            .map(|state| {
+                graph
+                    .successors(state)
+                    .fold(BigUint::zero(), |sum, next| &sum + &counts[next])
+            })
+            .collect();
+    }
+    counts.swap_remove(0)
+}
+
+/// The longest input the machine accepts, and among those the lexicographically smallest. Returns
+/// `None` when the machine accepts no input or infinitely many.
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::{language, nfa::Nfa};
+/// use std::collections::HashSet;
+///
+/// // Accepts 0 and 01
+/// let nfa = Nfa::build(
+///     vec![
+///         HashSet::from([1]),
+///         HashSet::new(),
+///         HashSet::new(),
+///         HashSet::from([2]),
+///         HashSet::new(),
+///         HashSet::new(),
+///     ],
+///     HashSet::from([1, 2]),
+///     3,
+///     2,
+/// )
+/// .unwrap();
+/// assert_eq!(language::longest_accepted(&nfa), Some(vec![0, 1]));
+/// ```
+pub fn longest_accepted(machine: &impl FiniteAutomaton) -> Option<Vec<u16>> {
+    let graph = SubsetGraph::explore(machine);
+    let live = graph.can_reach(true);
+    if !live[0] {
+        return None;
+    }
+    let order = graph.topological_order(&live)?;
+
+    // longest[state] is the length of the longest accepted input starting from a live state
+    let mut longest = vec![0; live.len()];
+    for &state in order.iter().rev() {
+        longest[state] = (0..graph.chars)
+            .map(|c| graph.next(state, c))
+            .filter(|&next| live[next])
+            .map(|next| longest[next] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+
+    let mut word = vec![];
+    let mut state = 0;
+    while longest[state] > 0 {
+        let c = (0..graph.chars)
+            .find(|&c| {
+                let next = graph.next(state, c);
+                live[next] && longest[next] + 1 == longest[state]
+            })
+            .expect("A live state with a longer input has a successor on it");
+        word.push(c as u16);
+        state = graph.next(state, c);
+    }
+    Some(word)
+}
+
+/// The sets of states reachable from the start states of a machine, numbered in breadth first
+/// order so the start states are state 0
+struct SubsetGraph {
+    /// The next state of each state on each character, laid out like the transition table of a
+    /// `Dfa`
+    transitions: Vec<usize>,
+    accepting: Vec<bool>,
+    chars: usize,
+}
+
+impl SubsetGraph {
+    fn explore(machine: &impl FiniteAutomaton) -> SubsetGraph {
+        let start = machine.start_states();
+        let chars = machine.chars() as usize;
+        let mut subsets = vec![start.clone()];
+        let mut subset_ids: HashMap<BTreeSet<u16>, usize> = HashMap::from([(start, 0)]);
+        let mut transitions = vec![];
+        let mut q = VecDeque::from([0]);
+        while let Some(state) = q.pop_front() {
+            for c in 0..machine.chars() {
+                let next = machine.next_states(&subsets[state], c);
+                let next_state = *subset_ids.entry(next.clone()).or_insert_with(|| {
+                    subsets.push(next);
+                    q.push_back(subsets.len() - 1);
+                    subsets.len() - 1
+                });
+                transitions.push(next_state);
+            }
+        }
+
+        SubsetGraph {
+            transitions,
+            accepting: subsets
+                .iter()
+                .map(|subset| machine.is_accepting(subset))
+                .collect(),
+            chars,
+        }
+    }
+
+    fn next(&self, state: usize, char: usize) -> usize {
+        self.transitions[table_lookup(state, char, self.chars)]
+    }
+
+    fn successors(&self, state: usize) -> impl Iterator<Item = usize> + '_ {
+        self.transitions[state * self.chars..(state + 1) * self.chars]
+            .iter()
+            .copied()
+    }
+
+    /// Which states can reach a state whose acceptance is `accepting`
+    fn can_reach(&self, accepting: bool) -> Vec<bool> {
+        let mut predecessors = vec![vec![]; self.accepting.len()];
+        for state in 0..self.accepting.len() {
+            for next in self.successors(state) {
+                predecessors[next].push(state);
+            }
+        }
+
+        let mut reaches: Vec<bool> = self.accepting.iter().map(|&a| a == accepting).collect();
+        let mut q: VecDeque<usize> = (0..reaches.len()).filter(|&s| reaches[s]).collect();
+        while let Some(state) = q.pop_front() {
+            for &previous in &predecessors[state] {
+                if !reaches[previous] {
+                    reaches[previous] = true;
+                    q.push_back(previous);
+                }
+            }
+        }
+        reaches
+    }
+
+    /// Orders the states in `keep` so every transition between them goes forwards, or returns
+    /// `None` when they contain a cycle
+    fn topological_order(&self, keep: &[bool]) -> Option<Vec<usize>> {
+        let mut incoming = vec![0; keep.len()];
+        for state in (0..keep.len()).filter(|&s| keep[s]) {
+            for next in self.successors(state).filter(|&next| keep[next]) {
+                incoming[next] += 1;
+            }
+        }
+
+        let mut q: VecDeque<usize> = (0..keep.len())
+            .filter(|&s| keep[s] && incoming[s] == 0)
+            .collect();
+        let mut order = vec![];
+        while let Some(state) = q.pop_front() {
+            order.push(state);
+            for next in self.successors(state).filter(|&next| keep[next]) {
+                incoming[next] -= 1;
+                if incoming[next] == 0 {
+                    q.push_back(next);
+                }
+            }
+        }
+        (order.len() == keep.iter().filter(|&&k| k).count()).then_some(order)
+    }
+}
+
+#[cfg(test)]
+mod language_tests {
+    use super::*;
+    use crate::{
+        dfa::Dfa,
+        e_nfa::EpsilonNfa,
+        machine_utils::{all_inputs, ends_in_one},
+        nfa::Nfa,
+        StateMachine,
+    };
+    use std::collections::HashSet;
+
+    // Accepts inputs of length at most 2
+    fn short() -> Dfa {
+        Dfa::build(vec![1, 1, 2, 2, 3, 3, 3, 3], HashSet::from([0, 1, 2]), 4, 2).unwrap()
+    }
+
+    fn brute_force_count(machine: &impl StateMachine, len: usize) -> u64 {
+        all_inputs(machine.chars(), len)
+            .into_iter()
+            .filter(|input| input.len() == len && machine.accepts(input).unwrap())
+            .count() as u64
+    }
+
+    #[test]
+    fn emptiness_and_universality() {
+        let nothing = Dfa::build(vec![0, 0], HashSet::new(), 1, 2).unwrap();
+        let everything = Dfa::build(vec![0, 0], HashSet::from([0]), 1, 2).unwrap();
+
+        assert!(is_empty(&nothing));
+        assert!(!is_universal(&nothing));
+        assert!(is_universal(&everything));
+        assert!(!is_empty(&everything));
+        assert!(!is_empty(&ends_in_one()));
+        assert!(!is_universal(&ends_in_one()));
+
+        // State 1 loops forever, but only the empty input is accepted
+        let nfa = Nfa::build(
+            vec![HashSet::new(), HashSet::from([1])],
+            HashSet::from([0]),
+            2,
+            1,
+        )
+        .unwrap();
+        assert!(!is_empty(&nfa));
+        assert!(!is_universal(&nfa));
+    }
+
+    #[test]
+    fn finiteness() {
+        assert!(is_finite(&short()));
+        assert!(!is_cofinite(&short()));
+        assert!(!is_finite(&ends_in_one()));
+        assert!(!is_cofinite(&ends_in_one()));
+
+        // Rejects only inputs of length at most 2
+        let long = Dfa::build(vec![1, 1, 2, 2, 3, 3, 3, 3], HashSet::from([3]), 4, 2).unwrap();
+        assert!(is_cofinite(&long));
+        assert!(!is_finite(&long));
+
+        // The cycle on state 1 can not reach an accept state
+        let dead_cycle = Dfa::build(vec![1, 2, 1, 1, 1, 1], HashSet::from([2]), 3, 2).unwrap();
+        assert!(is_finite(&dead_cycle));
+        assert_eq!(longest_accepted(&dead_cycle), Some(vec![1]));
+    }
+
+    #[test]
+    fn counts_match_brute_force() {
+        // Accepts 0 followed by any number of 1s, or the empty input
+        let e_nfa = EpsilonNfa::build(
+            vec![
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::from([2]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            ],
+            HashSet::from([1, 2]),
+            3,
+            2,
+        )
+        .unwrap();
+        for len in 0..7 {
+            let nfa = ends_in_one();
+            assert_eq!(
+                count_accepted(&nfa, len).to_u64(),
+                Some(brute_force_count(&nfa, len))
+            );
+            assert_eq!(
+                count_accepted(&short(), len).to_u64(),
+                Some(brute_force_count(&short(), len))
+            );
+            assert_eq!(
+                count_accepted(&e_nfa, len).to_u64(),
+                Some(brute_force_count(&e_nfa, len))
+            );
+        }
+        assert_eq!(
+            count_accepted(&ends_in_one(), 80).to_string(),
+            "604462909807314587353088"
+        );
+    }
+
+    #[test]
+    fn longest_word() {
+        assert_eq!(longest_accepted(&short()), Some(vec![0, 0]));
+        assert_eq!(longest_accepted(&ends_in_one()), None);
+        let nothing = Dfa::build(vec![0, 0], HashSet::new(), 1, 2).unwrap();
+        assert_eq!(longest_accepted(&nothing), None);
+
+        // Accepts only the empty input
+        let epsilon = Dfa::build(vec![1, 1, 1, 1], HashSet::from([0]), 2, 2).unwrap();
+        assert_eq!(longest_accepted(&epsilon), Some(vec![]));
+
+        // Accepts 00 and 110, so the longer word wins over the smaller one
+        let nfa = Nfa::build(
+            vec![
+                HashSet::from([1]),
+                HashSet::from([2]),
+                HashSet::from([4]),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::from([3]),
+                HashSet::from([4]),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            ],
+            HashSet::from([4]),
+            5,
+            2,
+        )
+        .unwrap();
+        assert_eq!(longest_accepted(&nfa), Some(vec![1, 1, 0]));
+    }
+}
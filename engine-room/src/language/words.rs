@@ -0,0 +1,244 @@
+use super::SubsetGraph;
+use crate::FiniteAutomaton;
+
+/// Every input accepted by `machine` in shortlex order, shortest first and then
+/// lexicographically, up to `max_len` characters long when a bound is given.
+///
+/// States that can no longer lead to an accepted input are never followed, so the iterator ends
+/// once every accepted input has been yielded, even without a bound.
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::{dfa::Dfa, language};
+/// use std::collections::HashSet;
+///
+/// // Accepts inputs with an odd number of 0s
+/// let dfa = Dfa::build(vec![1, 0, 0, 1], HashSet::from([1]), 2, 2).unwrap();
+///
+/// let first: Vec<Vec<u16>> = language::accepted_words(&dfa, None).take(4).collect();
+/// assert_eq!(first, vec![vec![0], vec![0, 1], vec![1, 0], vec![0, 0, 0]]);
+/// assert_eq!(language::rejected_words(&dfa, Some(1)).count(), 2);
+/// ```
+pub fn accepted_words(machine: &impl FiniteAutomaton, max_len: Option<usize>) -> Words {
+    Words::new(SubsetGraph::explore(machine), true, max_len)
+}
+
+/// Every input rejected by `machine` in the same order as [`accepted_words`]
+pub fn rejected_words(machine: &impl FiniteAutomaton, max_len: Option<usize>) -> Words {
+    Words::new(SubsetGraph::explore(machine), false, max_len)
+}
+
+/// # Words
+///
+/// An iterator over the inputs a machine accepts, or rejects, in shortlex order. Created by
+/// [`accepted_words`] and [`rejected_words`].
+pub struct Words {
+    graph: SubsetGraph,
+    max_len: Option<usize>,
+    /// `ends[k][state]` is true when an input of exactly `k` characters leads from `state` to a
+    /// state that gives the wanted answer
+    ends: Vec<Vec<bool>>,
+    /// Which states can lead to the wanted answer with an input of any length
+    live: Vec<bool>,
+    /// Which states are reached by the inputs of the current length
+    reached: Vec<bool>,
+    /// The states visited by `word`, starting with state 0
+    path: Vec<usize>,
+    /// The last input yielded, or `None` before the first input of the current length
+    word: Option<Vec<u16>>,
+    done: bool,
+}
+
+impl Words {
+    fn new(graph: SubsetGraph, accepting: bool, max_len: Option<usize>) -> Words {
+        let live = graph.can_reach(accepting);
+        let ends = vec![graph.accepting.iter().map(|&a| a == accepting).collect()];
+        let mut reached = vec![false; live.len()];
+        reached[0] = true;
+        Words {
+            done: !live[0],
+            graph,
+            max_len,
+            ends,
+            live,
+            reached,
+            path: vec![0],
+            word: None,
+        }
+    }
+
+    /// The length of the inputs currently being yielded
+    fn len(&self) -> usize {
+        self.ends.len() - 1
+    }
+
+    /// Moves on to the inputs one character longer. Returns false when there are none left.
+    fn next_len(&mut self) -> bool {
+        if self.max_len.is_some_and(|max_len| self.len() >= max_len) {
+            return false;
+        }
+        let mut reached = vec![false; self.reached.len()];
+        for state in (0..reached.len()).filter(|&state| self.reached[state]) {
+            for next in self.graph.successors(state) {
+                reached[next] = true;
+            }
+        }
+        self.reached = reached;
+        // Once no reached state is live, no longer input can give the wanted answer either
+        if !(0..self.live.len()).any(|state| self.reached[state] && self.live[state]) {
+            return false;
+        }
+
+        let last = &self.ends[self.len()];
+        let ends = (0..last.len())
+            .map(|state| self.graph.successors(state).any(|next| last[next]))
+            .collect();
+        self.ends.push(ends);
+        self.path = vec![0];
+        self.word = None;
+        true
+    }
+
+    /// The smallest character that can extend `word` into an input of the current length
+    /// without leaving the states that give the wanted answer, starting from `from`
+    fn extend_from(&self, word: &[u16], from: usize) -> Option<usize> {
+        let state = self.path[word.len()];
+        let remaining = self.len() - word.len() - 1;
+        (from..self.graph.chars).find(|&c| self.ends[remaining][self.graph.next(state, c)])
+    }
+
+    /// Extends `word` with the smallest characters until it has the current length
+    fn complete(&mut self, mut word: Vec<u16>) -> Vec<u16> {
+        while word.len() < self.len() {
+            let c = self
+                .extend_from(&word, 0)
+                .expect("Every prefix that is followed can be completed");
+            self.path.push(self.graph.next(self.path[word.len()], c));
+            word.push(c as u16);
+        }
+        word
+    }
+
+    /// The next input of the current length after `word`, in lexicographic order
+    fn next_of_len(&mut self) -> Option<Vec<u16>> {
+        let Some(mut word) = self.word.take() else {
+            return self.ends[self.len()][0].then(|| self.complete(vec![]));
+        };
+        while let Some(c) = word.pop() {
+            self.path.pop();
+            if let Some(c) = self.extend_from(&word, c as usize + 1) {
+                self.path.push(self.graph.next(self.path[word.len()], c));
+                word.push(c as u16);
+                return Some(self.complete(word));
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for Words {
+    type Item = Vec<u16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(word) = self.next_of_len() {
+                self.word = Some(word.clone());
+                return Some(word);
+            }
+            self.done = !self.next_len();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod words_tests {
+    use super::*;
+    use crate::{
+        dfa::Dfa,
+        e_nfa::EpsilonNfa,
+        machine_utils::{all_inputs, ends_in_one},
+        StateMachine,
+    };
+    use std::collections::HashSet;
+
+    fn brute_force(machine: &impl StateMachine, accepted: bool, max_len: usize) -> Vec<Vec<u16>> {
+        all_inputs(machine.chars(), max_len)
+            .into_iter()
+            .filter(|input| machine.accepts(input).unwrap() == accepted)
+            .collect()
+    }
+
+    fn words(machine: &impl FiniteAutomaton, accepted: bool, max_len: usize) -> Vec<Vec<u16>> {
+        if accepted {
+            accepted_words(machine, Some(max_len)).collect()
+        } else {
+            rejected_words(machine, Some(max_len)).collect()
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let nfa = ends_in_one();
+        // Accepts 0 followed by any number of 1s, or the empty input
+        let e_nfa = EpsilonNfa::build(
+            vec![
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::from([2]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            ],
+            HashSet::from([1, 2]),
+            3,
+            2,
+        )
+        .unwrap();
+
+        for accepted in [true, false] {
+            assert_eq!(words(&nfa, accepted, 6), brute_force(&nfa, accepted, 6));
+            assert_eq!(words(&e_nfa, accepted, 6), brute_force(&e_nfa, accepted, 6));
+        }
+    }
+
+    #[test]
+    fn finite_languages_end() {
+        // Accepts inputs of length at most 2
+        let short =
+            Dfa::build(vec![1, 1, 2, 2, 3, 3, 3, 3], HashSet::from([0, 1, 2]), 4, 2).unwrap();
+        assert_eq!(
+            accepted_words(&short, None).collect::<Vec<_>>(),
+            brute_force(&short, true, 2)
+        );
+
+        let nothing = Dfa::build(vec![0, 0], HashSet::new(), 1, 2).unwrap();
+        assert_eq!(accepted_words(&nothing, None).next(), None);
+        assert_eq!(
+            rejected_words(&nothing, Some(0)).collect::<Vec<_>>(),
+            vec![vec![]]
+        );
+        assert_eq!(rejected_words(&short, Some(3)).count(), 8);
+    }
+
+    #[test]
+    fn sparse_language() {
+        // Accepts inputs of 0s with a length divisible by 5, where 1 leads to a dead state
+        let mut transition_table = vec![];
+        for state in 0..5 {
+            transition_table.extend([(state + 1) % 5, 5]);
+        }
+        transition_table.extend([5, 5]);
+        let dfa = Dfa::build(transition_table, HashSet::from([0]), 6, 2).unwrap();
+
+        assert_eq!(
+            accepted_words(&dfa, None).take(3).collect::<Vec<_>>(),
+            vec![vec![], vec![0; 5], vec![0; 10]]
+        );
+        assert_eq!(accepted_words(&dfa, Some(9)).count(), 2);
+    }
+}
@@ -1,16 +1,33 @@
 use machine_utils::validate_input;
+use std::collections::BTreeSet;
 
+pub mod big_uint;
+pub mod cfg;
+pub mod computation_tree;
 pub mod dfa;
+pub mod diagnostics;
 pub mod e_nfa;
+pub mod equivalence;
+pub mod error;
+pub mod language;
 pub mod multi_tm;
 pub mod nfa;
+pub mod ntm;
 pub mod pda;
+pub mod regex;
+pub mod simulation;
 pub mod stay_tm;
 pub mod tm;
+pub mod transducer;
 pub mod transitions;
 
 mod machine_utils;
 
+pub use equivalence::{equivalent, Equivalence};
+pub use error::MachineError;
+pub use simulation::{Simulate, Simulation};
+pub use transducer::Transducer;
+
 /// # State Machine
 ///
 /// This trait defines the essential methods for a state machine. A state machine
@@ -23,7 +40,7 @@ mod machine_utils;
 ///
 pub trait StateMachine {
     /// Checks if the state machine accepts the given input.
-    fn accepts(&self, input: &[u16]) -> Result<bool, ()> {
+    fn accepts(&self, input: &[u16]) -> Result<bool, MachineError> {
         validate_input(input, self.chars())?;
         Ok(self.accepts_validated(input))
     }
@@ -31,7 +48,7 @@ pub trait StateMachine {
     fn accepts_validated(&self, input: &[u16]) -> bool;
 
     /// Traces the states and movements of the state machine for the given input.
-    fn trace_states(&self, input: &[u16]) -> Result<Vec<(u16, Vec<TapeMovement>)>, ()> {
+    fn trace_states(&self, input: &[u16]) -> Result<Vec<(u16, Vec<TapeMovement>)>, MachineError> {
         validate_input(input, self.chars())?;
         Ok(self.trace_states_validated(input))
     }
@@ -45,6 +62,46 @@ pub trait StateMachine {
     fn chars(&self) -> u16;
 }
 
+/// # Finite Automaton
+///
+/// A state machine that reads its input once from left to right and has no memory besides the
+/// state it is in. Deterministic and nondeterministic machines are treated alike by tracking the
+/// set of states the machine could be in, which for a `Dfa` always holds exactly one state.
+pub trait FiniteAutomaton: StateMachine {
+    /// The states the machine can be in before reading any input
+    fn start_states(&self) -> BTreeSet<u16>;
+
+    /// The states the machine can be in after reading `char` from any of the given states
+    fn next_states(&self, states: &BTreeSet<u16>, char: u16) -> BTreeSet<u16>;
+
+    /// Checks if any of the given states is an accept state
+    fn is_accepting(&self, states: &BTreeSet<u16>) -> bool;
+}
+
+/// # Verdict
+///
+/// The result of running a machine that is not guaranteed to halt. Such machines are only run for
+/// a limited number of steps or configurations, after which they give up instead of looping
+/// forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    Accept,
+    Reject,
+    /// The machine had not halted when its budget ran out
+    BudgetExhausted,
+}
+
+impl Verdict {
+    /// Whether the machine accepted, or [`MachineError::BudgetExhausted`] when it never decided
+    pub fn accepted(self) -> Result<bool, MachineError> {
+        match self {
+            Verdict::Accept => Ok(true),
+            Verdict::Reject => Ok(false),
+            Verdict::BudgetExhausted => Err(MachineError::BudgetExhausted),
+        }
+    }
+}
+
 pub trait StateMachineBuilder
 where
     Self: From<Self::Machine>,
@@ -1,10 +1,9 @@
-use crate::TapeMovement;
-use std::iter;
+use crate::{MachineError, StateMachineBuilder, TapeMovement};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 
-// TODO: Add better error type
-pub(crate) fn validate_input(input: &[u16], chars: u16) -> Result<(), ()> {
-    if input.iter().any(|&c| c >= chars) {
-        return Err(());
+pub(crate) fn validate_input(input: &[u16], chars: u16) -> Result<(), MachineError> {
+    if let Some((index, &char)) = input.iter().enumerate().find(|(_, &c)| c >= chars) {
+        return Err(MachineError::InvalidInputChar { index, char, chars });
     }
     Ok(())
 }
@@ -15,16 +14,6 @@ pub(crate) fn table_lookup(cur_state: usize, cur_char: usize, chars: usize) -> u
     cur_state * chars + cur_char
 }
 
-pub(crate) fn add_tape_mov(
-    state_trace: Vec<u16>,
-    tape_mov: TapeMovement,
-) -> Vec<(u16, Vec<TapeMovement>)> {
-    state_trace
-        .into_iter()
-        .zip(iter::repeat(vec![tape_mov]))
-        .collect()
-}
-
 pub(crate) fn add_tape_mov_stay_fir(
     state_trace: Vec<u16>,
     tape_mov: TapeMovement,
@@ -42,3 +31,145 @@ pub(crate) fn add_tape_mov_stay_fir(
         })
         .collect()
 }
+
+/// The states that can be reached from `sources` by following `edges`, given as `(from, to)`
+pub(crate) fn reachable_states(
+    states: u16,
+    edges: impl IntoIterator<Item = (u16, u16)>,
+    sources: impl IntoIterator<Item = u16>,
+) -> BTreeSet<u16> {
+    let mut adjacent = vec![vec![]; states as usize];
+    for (from, to) in edges {
+        adjacent[from as usize].push(to);
+    }
+
+    let mut reached: BTreeSet<u16> = sources.into_iter().collect();
+    let mut q: VecDeque<u16> = reached.iter().copied().collect();
+    while let Some(state) = q.pop_front() {
+        for &next in &adjacent[state as usize] {
+            if reached.insert(next) {
+                q.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+/// Swaps the rows of `first` and `second` in a transition table with `stride` entries per state
+pub(crate) fn swap_rows<T>(table: &mut [T], first: u16, second: u16, stride: usize) {
+    if first == second {
+        return;
+    }
+    let (first, second) = (first.min(second), first.max(second));
+
+    // Indexes to the start of the rows for first and second
+    let first_start = table_lookup(first as usize, 0, stride);
+    let second_start = table_lookup(second as usize, 0, stride);
+
+    let (first_extra, second_extra) = table.split_at_mut(second_start);
+    first_extra[first_start..first_start + stride].swap_with_slice(&mut second_extra[..stride]);
+}
+
+/// Swaps `first` and `second` in a set of states, such as the accept states or the targets of a
+/// transition
+pub(crate) fn swap_in_set(set: &mut HashSet<u16>, first: u16, second: u16) {
+    let has_first = set.remove(&first);
+    let has_second = set.remove(&second);
+    if has_first {
+        set.insert(second);
+    }
+    if has_second {
+        set.insert(first);
+    }
+}
+
+/// Removes the column of `char` from a transition table with `stride` entries per state, of
+/// which the first `chars` are characters and the rest, like the epsilon column, stay at the end
+/// of the row. The last character takes the place of the removed one, the same way
+/// `remove_state` renumbers states, and its old number is returned unless `char` was the last.
+pub(crate) fn remove_char_column<T: Clone>(
+    table: &mut Vec<T>,
+    char: u16,
+    chars: u16,
+    stride: usize,
+) -> Result<Option<u16>, MachineError> {
+    if char >= chars {
+        return Err(MachineError::CharOutOfRange { char, chars });
+    }
+    if chars == 1 {
+        return Err(MachineError::LastChar);
+    }
+    let chunks = table.chunks_exact(stride);
+    debug_assert!(chunks.remainder().is_empty());
+
+    let last = chars - 1;
+    *table = chunks
+        .flat_map(|chunk| {
+            let mut row = chunk.to_vec();
+            row.swap(char as usize, last as usize);
+            row.remove(last as usize);
+            row
+        })
+        .collect();
+    if char == last {
+        Ok(None)
+    } else {
+        Ok(Some(last))
+    }
+}
+
+/// Removes every state in `remove` from `builder`, from the highest state down, so each removal
+/// only moves a state that is kept. Returns the new number of every original state, or `None`
+/// when it was removed.
+pub(crate) fn remove_states<B: StateMachineBuilder>(
+    builder: &mut B,
+    states: u16,
+    remove: &BTreeSet<u16>,
+) -> Result<Vec<Option<u16>>, B::Error> {
+    let mut renumbered: Vec<Option<u16>> = (0..states).map(Some).collect();
+    let mut original: Vec<u16> = (0..states).collect();
+    for &state in remove.iter().rev() {
+        renumbered[original[state as usize] as usize] = None;
+        if let Some(moved) = builder.remove_state(state)? {
+            let moved = original[moved as usize];
+            renumbered[moved as usize] = Some(state);
+            original[state as usize] = moved;
+        }
+        original.pop();
+    }
+    Ok(renumbered)
+}
+
+/// Every string over `chars` characters with a length of at most `max_len`, shortest first
+#[cfg(test)]
+pub(crate) fn all_inputs(chars: u16, max_len: usize) -> Vec<Vec<u16>> {
+    let mut inputs = vec![vec![]];
+    let mut last_len = vec![vec![]];
+    for _ in 0..max_len {
+        last_len = last_len
+            .into_iter()
+            .flat_map(|input: Vec<u16>| (0..chars).map(move |c| [input.clone(), vec![c]].concat()))
+            .collect();
+        inputs.extend(last_len.iter().cloned());
+    }
+    inputs
+}
+
+/// A `Nfa` over two characters that accepts the inputs ending in 1
+#[cfg(test)]
+pub(crate) fn ends_in_one() -> crate::nfa::Nfa {
+    use std::collections::HashSet;
+
+    crate::nfa::Nfa::build(
+        vec![
+            HashSet::from([0]),
+            HashSet::from([0, 1]),
+            HashSet::new(),
+            HashSet::new(),
+        ],
+        HashSet::from([1]),
+        2,
+        2,
+    )
+    .unwrap()
+}
@@ -0,0 +1,971 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    machine_utils::{table_lookup, validate_input},
+    simulation::DEFAULT_STEP_BUDGET,
+    tm::{apply_movement, validate_halting_states, Tm},
+    transitions::MultiTmTransition,
+    MachineError, Simulate, StateMachine, TapeMovement, Verdict,
+};
+
+/// # Multi Tm Configuration
+///
+/// A multi-tape Turing machine in `state` with the head of tape `i` over `tapes[i][heads[i]]`
+/// after taking `steps` steps. Every cell past the end of a tape holds the blank symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MultiTmConfiguration {
+    pub state: u16,
+    pub heads: Vec<usize>,
+    pub tapes: Vec<Vec<u16>>,
+    pub steps: usize,
+}
+
+/// # Multi-Tape Turing Machine
+///
+/// A deterministic Turing machine with `tapes` tapes that share one tape alphabet. The input is
+/// written on the first tape and every other tape starts out blank. Each transition reads the
+/// symbol under every head and then writes to and moves every head independently, where a head
+/// may also stay in place. Like a [`Tm`], the blank symbol directly follows the input alphabet
+/// and the machine halts in its accept or reject state or when it has no transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiTm {
+    transition_table: Vec<Option<(u16, Vec<TapeMovement>)>>,
+    accept_state: u16,
+    reject_state: u16,
+    states: u16,
+    chars: u16,
+    tape_chars: u16,
+    tapes: u16,
+    step_budget: usize,
+}
+
+impl MultiTm {
+    /// Builds a multi-tape Turing machine. The symbols read from the tapes are numbered by
+    /// treating them as a base `tape_chars` number with the first tape as the most significant
+    /// digit, and the transition for reading them in `state` is stored at
+    /// `state * tape_chars.pow(tapes) + number`. Each transition holds the next state and one
+    /// movement, with an optional symbol to write, for each tape.
+    pub fn build(
+        transition_table: Vec<Option<(u16, Vec<TapeMovement>)>>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+        tapes: u16,
+    ) -> Result<MultiTm, MachineError> {
+        if states == 0 {
+            return Err(MachineError::NoStates);
+        }
+        if chars == 0 {
+            return Err(MachineError::NoChars);
+        }
+        if tapes == 0 {
+            return Err(MachineError::NoTapes);
+        }
+        if tape_chars <= chars {
+            return Err(MachineError::TapeCharOutOfRange {
+                char: chars,
+                tape_chars,
+            });
+        }
+        let expected = read_combinations(tape_chars, tapes)?
+            .checked_mul(states as usize)
+            .ok_or(MachineError::TooManyStates)?;
+        if transition_table.len() != expected {
+            return Err(MachineError::WrongTableLength {
+                expected,
+                actual: transition_table.len(),
+            });
+        }
+        for (index, (state, movements)) in transition_table
+            .iter()
+            .enumerate()
+            .filter_map(|(index, transition)| Some((index, transition.as_ref()?)))
+        {
+            if *state >= states {
+                return Err(MachineError::InvalidTransitionTarget {
+                    index,
+                    state: *state,
+                    states,
+                });
+            }
+            if movements.len() != tapes as usize {
+                return Err(MachineError::WrongTapeCount {
+                    expected: tapes,
+                    actual: movements.len(),
+                });
+            }
+            for movement in movements {
+                let (TapeMovement::Left(write)
+                | TapeMovement::Right(write)
+                | TapeMovement::Stay(write)) = *movement;
+                if let Some(char) = write.filter(|&char| char >= tape_chars) {
+                    return Err(MachineError::TapeCharOutOfRange { char, tape_chars });
+                }
+            }
+        }
+        validate_halting_states(accept_state, reject_state, states)?;
+
+        Ok(MultiTm {
+            transition_table,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+            tapes,
+            step_budget: DEFAULT_STEP_BUDGET,
+        })
+    }
+
+    /// Builds a multi-tape Turing machine from a list of transitions instead of a full transition
+    /// table. Later transitions replace earlier ones for the same state and symbols.
+    pub fn from_transitions(
+        transitions: impl IntoIterator<Item = MultiTmTransition>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+        tapes: u16,
+    ) -> Result<MultiTm, MachineError> {
+        let combinations = read_combinations(tape_chars, tapes)?;
+        let mut transition_table = vec![None; states as usize * combinations];
+        for MultiTmTransition {
+            start,
+            end,
+            read,
+            movements,
+        } in transitions
+        {
+            if start >= states {
+                return Err(MachineError::StateOutOfRange {
+                    state: start,
+                    states,
+                });
+            }
+            if read.len() != tapes as usize {
+                return Err(MachineError::WrongTapeCount {
+                    expected: tapes,
+                    actual: read.len(),
+                });
+            }
+            if let Some(&char) = read.iter().find(|&&char| char >= tape_chars) {
+                return Err(MachineError::TapeCharOutOfRange { char, tape_chars });
+            }
+            transition_table
+                [table_lookup(start as usize, read_index(&read, tape_chars), combinations)] =
+                Some((end, movements));
+        }
+
+        MultiTm::build(
+            transition_table,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+            tapes,
+        )
+    }
+
+    /// The blank symbol, which directly follows the input alphabet
+    pub fn blank(&self) -> u16 {
+        self.chars
+    }
+
+    pub fn tape_chars(&self) -> u16 {
+        self.tape_chars
+    }
+
+    pub fn tapes(&self) -> u16 {
+        self.tapes
+    }
+
+    pub fn accept_state(&self) -> u16 {
+        self.accept_state
+    }
+
+    pub fn reject_state(&self) -> u16 {
+        self.reject_state
+    }
+
+    /// The transition table in the layout described by [`MultiTm::build`]
+    pub fn transition_table(&self) -> &[Option<(u16, Vec<TapeMovement>)>] {
+        &self.transition_table
+    }
+
+    pub fn step_budget(&self) -> usize {
+        self.step_budget
+    }
+
+    /// Sets the number of steps taken before [`MultiTm::run`] gives up
+    pub fn set_step_budget(&mut self, step_budget: usize) {
+        self.step_budget = step_budget;
+    }
+
+    /// The configuration with `input` on the first tape, every other tape blank and every head
+    /// over the first cell of its tape
+    pub fn initial_configuration(&self, input: &[u16]) -> MultiTmConfiguration {
+        let mut tapes = vec![vec![self.blank()]; self.tapes as usize];
+        if !input.is_empty() {
+            tapes[0] = input.to_vec();
+        }
+        MultiTmConfiguration {
+            state: 0,
+            heads: vec![0; self.tapes as usize],
+            tapes,
+            steps: 0,
+        }
+    }
+
+    /// The transition taken when the heads read `read` in `state`
+    pub fn transition(&self, state: u16, read: &[u16]) -> Option<&(u16, Vec<TapeMovement>)> {
+        self.transition_table[table_lookup(
+            state as usize,
+            read_index(read, self.tape_chars),
+            self.transition_table.len() / self.states as usize,
+        )]
+        .as_ref()
+    }
+
+    /// Takes one step from `configuration`, returning the new configuration and the movements
+    /// the heads made. Returns `None` if the machine has halted.
+    pub fn step(
+        &self,
+        configuration: &MultiTmConfiguration,
+    ) -> Option<(MultiTmConfiguration, Vec<TapeMovement>)> {
+        if configuration.state == self.accept_state || configuration.state == self.reject_state {
+            return None;
+        }
+        let read: Vec<u16> = configuration
+            .tapes
+            .iter()
+            .zip(&configuration.heads)
+            .map(|(tape, &head)| tape[head])
+            .collect();
+        let (state, movements) = self.transition(configuration.state, &read)?;
+
+        let mut next = configuration.clone();
+        let movements = next
+            .tapes
+            .iter_mut()
+            .zip(next.heads.iter_mut())
+            .zip(movements)
+            .map(|((tape, head), &movement)| apply_movement(tape, head, movement, self.blank()))
+            .collect();
+        next.state = *state;
+        next.steps += 1;
+        Some((next, movements))
+    }
+
+    /// Runs the machine on `input` and reports if it accepts, rejects or had not halted by the
+    /// end of its step budget
+    pub fn run(&self, input: &[u16]) -> Result<Verdict, MachineError> {
+        validate_input(input, self.chars)?;
+        let mut configuration = self.initial_configuration(input);
+        for _ in 0..self.step_budget {
+            match self.step(&configuration) {
+                Some((next, _)) => configuration = next,
+                None => break,
+            }
+        }
+        Ok(self.verdict(&configuration))
+    }
+
+    /// Every configuration the machine passes through on `input`, until it halts or its step
+    /// budget runs out
+    pub fn trace(&self, input: &[u16]) -> Result<Vec<MultiTmConfiguration>, MachineError> {
+        validate_input(input, self.chars)?;
+        Ok(self
+            .trace_validated(input)
+            .into_iter()
+            .map(|(configuration, _)| configuration)
+            .collect())
+    }
+
+    fn trace_validated(&self, input: &[u16]) -> Vec<(MultiTmConfiguration, Vec<TapeMovement>)> {
+        let mut trace = vec![(
+            self.initial_configuration(input),
+            vec![TapeMovement::Stay(None); self.tapes as usize],
+        )];
+        for _ in 0..self.step_budget {
+            let Some(next) = self.step(&trace.last().expect("The trace is never empty").0) else {
+                break;
+            };
+            trace.push(next);
+        }
+        trace
+    }
+
+    fn verdict(&self, configuration: &MultiTmConfiguration) -> Verdict {
+        if configuration.state == self.accept_state {
+            Verdict::Accept
+        } else if self.step(configuration).is_none() {
+            Verdict::Reject
+        } else {
+            Verdict::BudgetExhausted
+        }
+    }
+
+    /// Builds a single tape [`Tm`] that accepts the same inputs, using the track construction.
+    ///
+    /// Every cell of the single tape holds one track per tape, and each track stores the symbol
+    /// in that cell of its tape along with a mark for whether that tape's head is there. The input
+    /// symbols and blank keep their meaning and are read as a cell whose first track holds the
+    /// symbol. One step of this machine is simulated by sweeping right from the leftmost head
+    /// mark to collect the symbols under every head, and then sweeping back left to write the new
+    /// symbols and move the marks. Only the states of the new machine that can be reached are
+    /// built.
+    ///
+    /// Each simulated step takes a number of steps that grows with the distance between the
+    /// heads, so the new machine may need a larger step budget than this one. Fails with
+    /// [`MachineError::TooManyTapeChars`] when the track symbols do not fit in a `u16`.
+    pub fn to_single_tape(&self) -> Result<Tm, MachineError> {
+        let tracks = Tracks::new(self)?;
+        let tape_chars = tracks.single_tape_chars();
+
+        let mut numbers = HashMap::from([
+            (Control::Start, 0),
+            (Control::Accept, 1),
+            (Control::Reject, 2),
+        ]);
+        let mut q = VecDeque::from([Control::Start]);
+        let mut transition_table = vec![];
+        while let Some(control) = q.pop_front() {
+            let state = numbers[&control] as usize;
+            if transition_table.len() < (state + 1) * tape_chars as usize {
+                transition_table.resize((state + 1) * tape_chars as usize, None);
+            }
+            if matches!(control, Control::Accept | Control::Reject) {
+                continue;
+            }
+
+            for symbol in 0..tape_chars {
+                let (next, write, right) = tracks.transition(self, &control, symbol);
+                let next_state = match numbers.get(&next) {
+                    Some(&next_state) => next_state,
+                    None => {
+                        // The number of states must also fit in a u16
+                        let next_state = u16::try_from(numbers.len() + 1)
+                            .map_err(|_| MachineError::TooManyStates)?
+                            - 1;
+                        numbers.insert(next.clone(), next_state);
+                        q.push_back(next);
+                        next_state
+                    }
+                };
+                let movement = if right {
+                    TapeMovement::Right(write)
+                } else {
+                    TapeMovement::Left(write)
+                };
+                transition_table[table_lookup(state, symbol as usize, tape_chars as usize)] =
+                    Some((next_state, movement));
+            }
+        }
+
+        let states = numbers.len() as u16;
+        transition_table.resize(states as usize * tape_chars as usize, None);
+        Tm::build(transition_table, 1, 2, states, self.chars, tape_chars)
+    }
+}
+
+impl TryFrom<&MultiTm> for Tm {
+    type Error = MachineError;
+
+    fn try_from(value: &MultiTm) -> Result<Self, Self::Error> {
+        value.to_single_tape()
+    }
+}
+
+/// The number of ways to read one symbol from every tape
+fn read_combinations(tape_chars: u16, tapes: u16) -> Result<usize, MachineError> {
+    (tape_chars as usize)
+        .checked_pow(tapes as u32)
+        .ok_or(MachineError::TooManyStates)
+}
+
+fn read_index(read: &[u16], tape_chars: u16) -> usize {
+    read.iter().fold(0, |index, &char| {
+        index * tape_chars as usize + char as usize
+    })
+}
+
+/// What the single tape machine built by [`MultiTm::to_single_tape`] is doing in each of its
+/// states. Tracks whose head still has to be moved are `Some` in `pending`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Control {
+    /// Marks every head on the first cell
+    Start,
+    /// Steps back onto the first cell after `Start`
+    Rewind,
+    /// Sweeps right collecting the symbol under every head of the multi-tape machine in `state`
+    Collect {
+        state: u16,
+        seen: Vec<Option<u16>>,
+    },
+    /// Sweeps left writing symbols and moving heads before entering `next`
+    Apply {
+        next: u16,
+        pending: Vec<Option<TapeMovement>>,
+    },
+    /// Marks the heads in `right` on the cell right of the one just written, and then the heads
+    /// in `left` on the cell left of it
+    MarkRight {
+        next: u16,
+        pending: Vec<Option<TapeMovement>>,
+        right: Vec<bool>,
+        left: Vec<bool>,
+    },
+    /// Back on the cell that was just written after marking the heads that moved right
+    AfterRight {
+        next: u16,
+        pending: Vec<Option<TapeMovement>>,
+        left: Vec<bool>,
+    },
+    /// Marks the heads in `left` on this cell and continues the sweep from here
+    MarkLeft {
+        next: u16,
+        pending: Vec<Option<TapeMovement>>,
+        left: Vec<bool>,
+    },
+    Accept,
+    Reject,
+}
+
+/// The encoding of the cells of every tape into a single tape symbol
+struct Tracks {
+    chars: u16,
+    tape_chars: u16,
+    tapes: u16,
+}
+
+impl Tracks {
+    fn new(multi_tm: &MultiTm) -> Result<Tracks, MachineError> {
+        let tracks = Tracks {
+            chars: multi_tm.chars,
+            tape_chars: multi_tm.tape_chars,
+            tapes: multi_tm.tapes,
+        };
+        // Every track symbol is a tape symbol with a head mark, after the input symbols and blank
+        (tracks.tape_chars as usize * 2)
+            .checked_pow(tracks.tapes as u32)
+            .and_then(|cells| cells.checked_add(tracks.chars as usize + 1))
+            .filter(|&symbols| symbols <= u16::MAX as usize)
+            .ok_or(MachineError::TooManyTapeChars)?;
+        Ok(tracks)
+    }
+
+    fn single_tape_chars(&self) -> u16 {
+        (self.tape_chars * 2).pow(self.tapes as u32) + self.chars + 1
+    }
+
+    /// The symbol and head mark of every track in a cell holding `symbol`
+    fn decode(&self, symbol: u16) -> Vec<(u16, bool)> {
+        let blank = self.chars;
+        if symbol <= blank {
+            let mut cell = vec![(blank, false); self.tapes as usize];
+            cell[0].0 = symbol;
+            return cell;
+        }
+
+        let mut code = symbol - blank - 1;
+        (0..self.tapes)
+            .map(|_| {
+                let digit = code % (self.tape_chars * 2);
+                code /= self.tape_chars * 2;
+                (digit / 2, digit % 2 == 1)
+            })
+            .collect()
+    }
+
+    fn encode(&self, cell: &[(u16, bool)]) -> u16 {
+        let code = cell.iter().rev().fold(0, |code, &(symbol, marked)| {
+            code * self.tape_chars * 2 + symbol * 2 + marked as u16
+        });
+        code + self.chars + 1
+    }
+
+    /// The transition of the single tape machine for reading `symbol` while doing `control`, as
+    /// the next control, the symbol to write and whether to move right instead of left
+    fn transition(
+        &self,
+        multi_tm: &MultiTm,
+        control: &Control,
+        symbol: u16,
+    ) -> (Control, Option<u16>, bool) {
+        let mut cell = self.decode(symbol);
+        match control.clone() {
+            Control::Start => {
+                cell.iter_mut().for_each(|(_, marked)| *marked = true);
+                (Control::Rewind, Some(self.encode(&cell)), true)
+            }
+            Control::Rewind => (Self::enter(multi_tm, 0), None, false),
+            Control::Collect { state, mut seen } => {
+                for (seen, &(symbol, marked)) in seen.iter_mut().zip(&cell) {
+                    if marked {
+                        *seen = Some(symbol);
+                    }
+                }
+                let Some(read) = seen.iter().copied().collect::<Option<Vec<u16>>>() else {
+                    return (Control::Collect { state, seen }, None, true);
+                };
+                match multi_tm.transition(state, &read) {
+                    Some((next, movements)) => self.apply(
+                        multi_tm,
+                        *next,
+                        movements.iter().copied().map(Some).collect(),
+                        cell,
+                        false,
+                    ),
+                    None => (Control::Reject, None, true),
+                }
+            }
+            Control::Apply { next, pending } => self.apply(multi_tm, next, pending, cell, false),
+            Control::MarkRight {
+                next,
+                pending,
+                right,
+                left,
+            } => {
+                for (track, _) in right.iter().enumerate().filter(|(_, &right)| right) {
+                    cell[track].1 = true;
+                }
+                (
+                    Control::AfterRight {
+                        next,
+                        pending,
+                        left,
+                    },
+                    Some(self.encode(&cell)),
+                    false,
+                )
+            }
+            Control::AfterRight {
+                next,
+                pending,
+                left,
+            } => {
+                if left.contains(&true) {
+                    (
+                        Control::MarkLeft {
+                            next,
+                            pending,
+                            left,
+                        },
+                        None,
+                        false,
+                    )
+                } else {
+                    (Self::continue_sweep(multi_tm, next, pending), None, false)
+                }
+            }
+            Control::MarkLeft {
+                next,
+                pending,
+                left,
+            } => {
+                for (track, _) in left.iter().enumerate().filter(|(_, &left)| left) {
+                    cell[track].1 = true;
+                }
+                self.apply(multi_tm, next, pending, cell, true)
+            }
+            Control::Accept | Control::Reject => {
+                unreachable!("Halting states have no transitions")
+            }
+        }
+    }
+
+    /// Writes the new symbol of every pending track whose head is in `cell`. Heads that stay are
+    /// done, while heads that move are marked on the neighbouring cell next.
+    fn apply(
+        &self,
+        multi_tm: &MultiTm,
+        next: u16,
+        mut pending: Vec<Option<TapeMovement>>,
+        mut cell: Vec<(u16, bool)>,
+        mut changed: bool,
+    ) -> (Control, Option<u16>, bool) {
+        let mut right = vec![false; self.tapes as usize];
+        let mut left = vec![false; self.tapes as usize];
+        for (track, (symbol, marked)) in cell.iter_mut().enumerate() {
+            if !*marked {
+                continue;
+            }
+            let Some(movement) = pending[track].take() else {
+                continue;
+            };
+            let (TapeMovement::Left(write)
+            | TapeMovement::Right(write)
+            | TapeMovement::Stay(write)) = movement;
+            if let Some(write) = write {
+                *symbol = write;
+                changed = true;
+            }
+            match movement {
+                TapeMovement::Right(_) => right[track] = true,
+                TapeMovement::Left(_) => left[track] = true,
+                TapeMovement::Stay(_) => continue,
+            }
+            *marked = false;
+            changed = true;
+        }
+
+        let write = changed.then(|| self.encode(&cell));
+        if right.contains(&true) {
+            let control = Control::MarkRight {
+                next,
+                pending,
+                right,
+                left,
+            };
+            (control, write, true)
+        } else if left.contains(&true) {
+            (
+                Control::MarkLeft {
+                    next,
+                    pending,
+                    left,
+                },
+                write,
+                false,
+            )
+        } else {
+            (Self::continue_sweep(multi_tm, next, pending), write, false)
+        }
+    }
+
+    /// Keeps sweeping left while there are heads left to move, and otherwise starts the next step
+    fn continue_sweep(
+        multi_tm: &MultiTm,
+        next: u16,
+        pending: Vec<Option<TapeMovement>>,
+    ) -> Control {
+        if pending.iter().any(Option::is_some) {
+            Control::Apply { next, pending }
+        } else {
+            Self::enter(multi_tm, next)
+        }
+    }
+
+    /// Enters `state` of the multi-tape machine. Every head is at or right of the cell the single
+    /// tape head moves onto, so collecting can start from there.
+    fn enter(multi_tm: &MultiTm, state: u16) -> Control {
+        if state == multi_tm.accept_state {
+            Control::Accept
+        } else if state == multi_tm.reject_state {
+            Control::Reject
+        } else {
+            Control::Collect {
+                state,
+                seen: vec![None; multi_tm.tapes as usize],
+            }
+        }
+    }
+}
+
+impl StateMachine for MultiTm {
+    /// Checks if the machine halts in its accept state. Returns
+    /// [`MachineError::BudgetExhausted`] if it has not halted within its step budget.
+    fn accepts(&self, input: &[u16]) -> Result<bool, MachineError> {
+        self.run(input)?.accepted()
+    }
+
+    /// Accepts when the machine halts in its accept state within the step budget
+    fn accepts_validated(&self, input: &[u16]) -> bool {
+        self.run(input) == Ok(Verdict::Accept)
+    }
+
+    /// The states the machine passes through along with the movement of every head, and the
+    /// symbols written, on the step into each state
+    fn trace_states_validated(&self, input: &[u16]) -> Vec<(u16, Vec<TapeMovement>)> {
+        self.trace_validated(input)
+            .into_iter()
+            .map(|(configuration, movements)| (configuration.state, movements))
+            .collect()
+    }
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn chars(&self) -> u16 {
+        self.chars
+    }
+}
+
+impl Simulate for MultiTm {
+    type Configuration = MultiTmConfiguration;
+
+    fn start_configuration(&self, input: &[u16]) -> MultiTmConfiguration {
+        self.initial_configuration(input)
+    }
+
+    fn next_configuration(
+        &self,
+        configuration: &MultiTmConfiguration,
+        _input: &[u16],
+    ) -> Option<MultiTmConfiguration> {
+        if configuration.steps >= self.step_budget {
+            return None;
+        }
+        self.step(configuration).map(|(next, _)| next)
+    }
+
+    fn is_accepting_configuration(
+        &self,
+        configuration: &MultiTmConfiguration,
+        _input: &[u16],
+    ) -> bool {
+        configuration.state == self.accept_state
+    }
+}
+
+#[cfg(test)]
+mod multi_tm_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    const BLANK: u16 = 2;
+    const X: u16 = 3;
+
+    fn transition(
+        start: u16,
+        read: [u16; 2],
+        end: u16,
+        movements: [TapeMovement; 2],
+    ) -> MultiTmTransition {
+        MultiTmTransition {
+            start,
+            end,
+            read: read.to_vec(),
+            movements: movements.to_vec(),
+        }
+    }
+
+    // Decides 0^n 1^n by copying the 0s onto the second tape above an X and then crossing them
+    // off with the 1s
+    fn zeros_then_ones() -> MultiTm {
+        use TapeMovement::{Left, Right, Stay};
+        MultiTm::from_transitions(
+            [
+                transition(0, [0, BLANK], 1, [Stay(None), Right(Some(X))]),
+                transition(0, [1, BLANK], 1, [Stay(None), Right(Some(X))]),
+                transition(0, [BLANK, BLANK], 3, [Stay(None), Stay(None)]),
+                transition(1, [0, BLANK], 1, [Right(None), Right(Some(0))]),
+                transition(1, [1, BLANK], 2, [Stay(None), Left(None)]),
+                transition(2, [1, 0], 2, [Right(None), Left(None)]),
+                transition(2, [BLANK, X], 3, [Stay(None), Stay(None)]),
+            ],
+            3,
+            4,
+            5,
+            2,
+            4,
+            2,
+        )
+        .unwrap()
+    }
+
+    fn is_zeros_then_ones(input: &[u16]) -> bool {
+        let zeros = input.iter().take_while(|&&c| c == 0).count();
+        zeros * 2 == input.len() && input[zeros..].iter().all(|&c| c == 1)
+    }
+
+    #[test]
+    fn budget_exhausted() {
+        let mut multi_tm = zeros_then_ones();
+        multi_tm.set_step_budget(2);
+
+        assert_eq!(
+            multi_tm.run(&[0, 0, 1, 1]).unwrap(),
+            Verdict::BudgetExhausted
+        );
+        assert_eq!(
+            multi_tm.accepts(&[0, 0, 1, 1]),
+            Err(MachineError::BudgetExhausted)
+        );
+        assert!(!multi_tm.accepts_validated(&[0, 0, 1, 1]));
+    }
+
+    #[test]
+    fn decides_zeros_then_ones() {
+        let multi_tm = zeros_then_ones();
+        for input in all_inputs(2, 8) {
+            assert_eq!(
+                multi_tm.accepts(&input).unwrap(),
+                is_zeros_then_ones(&input),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn trace_states_moves_every_head() {
+        let multi_tm = zeros_then_ones();
+        let trace = multi_tm.trace_states(&[0, 1]).unwrap();
+
+        assert_eq!(
+            trace[..3],
+            [
+                (0, vec![TapeMovement::Stay(None); 2]),
+                (
+                    1,
+                    vec![TapeMovement::Stay(None), TapeMovement::Right(Some(X))]
+                ),
+                (
+                    1,
+                    vec![TapeMovement::Right(None), TapeMovement::Right(Some(0))]
+                ),
+            ]
+        );
+        let last = multi_tm.trace(&[0, 1]).unwrap().pop().unwrap();
+        assert_eq!(last.state, 3);
+        assert_eq!(last.tapes[1], vec![X, 0, BLANK]);
+    }
+
+    #[test]
+    fn single_tape_decides_same_language() {
+        let multi_tm = zeros_then_ones();
+        let tm = multi_tm.to_single_tape().unwrap();
+
+        assert_eq!(tm.chars(), 2);
+        assert_eq!(tm.blank(), BLANK);
+        for input in all_inputs(2, 7) {
+            assert_eq!(
+                tm.run(&input).unwrap(),
+                multi_tm.run(&input).unwrap(),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn single_tape_keeps_every_tape_on_a_track() {
+        let multi_tm = zeros_then_ones();
+        let tm = Tm::try_from(&multi_tm).unwrap();
+        let tracks = Tracks::new(&multi_tm).unwrap();
+
+        for input in [vec![0, 0, 1, 1], vec![0, 0, 0, 1, 1], vec![1, 0]] {
+            let multi_last = multi_tm.trace(&input).unwrap().pop().unwrap();
+            let last = tm.trace(&input).unwrap().pop().unwrap();
+
+            let cells: Vec<_> = last.tape.iter().map(|&s| tracks.decode(s)).collect();
+            for (tape, (contents, &head)) in
+                multi_last.tapes.iter().zip(&multi_last.heads).enumerate()
+            {
+                let track: Vec<u16> = cells.iter().map(|cell| cell[tape].0).collect();
+                let len = contents.len().max(track.len());
+                let pad = |symbols: &[u16]| {
+                    let mut symbols = symbols.to_vec();
+                    symbols.resize(len, BLANK);
+                    symbols
+                };
+                assert_eq!(pad(&track), pad(contents), "{input:?} tape {tape}");
+
+                let marks: Vec<usize> = (0..cells.len())
+                    .filter(|&cell| cells[cell][tape].1)
+                    .collect();
+                assert_eq!(marks, vec![head], "{input:?} tape {tape}");
+            }
+        }
+    }
+
+    #[test]
+    fn left_at_first_cell_stays_on_single_tape() {
+        use TapeMovement::{Left, Right};
+        // Moves the second head left from the first cell, and accepts if it is still there by
+        // reading the X it wrote
+        let multi_tm = MultiTm::from_transitions(
+            [
+                transition(0, [0, BLANK], 1, [Right(None), Left(Some(X))]),
+                transition(1, [BLANK, X], 2, [Right(None), Right(None)]),
+            ],
+            2,
+            3,
+            4,
+            2,
+            4,
+            2,
+        )
+        .unwrap();
+        let tm = multi_tm.to_single_tape().unwrap();
+
+        assert_eq!(multi_tm.run(&[0]).unwrap(), Verdict::Accept);
+        assert_eq!(tm.run(&[0]).unwrap(), Verdict::Accept);
+        assert_eq!(tm.run(&[1]).unwrap(), Verdict::Reject);
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            MultiTm::build(vec![], 0, 1, 2, 2, 4, 0).unwrap_err(),
+            MachineError::NoTapes
+        );
+        assert_eq!(
+            MultiTm::build(vec![None; 16], 0, 1, 2, 2, 4, 2).unwrap_err(),
+            MachineError::WrongTableLength {
+                expected: 32,
+                actual: 16
+            }
+        );
+        assert_eq!(
+            MultiTm::from_transitions(
+                [MultiTmTransition {
+                    start: 0,
+                    end: 1,
+                    read: vec![0],
+                    movements: vec![TapeMovement::Stay(None)],
+                }],
+                0,
+                1,
+                2,
+                2,
+                4,
+                2
+            )
+            .unwrap_err(),
+            MachineError::WrongTapeCount {
+                expected: 2,
+                actual: 1
+            }
+        );
+        assert_eq!(
+            MultiTm::from_transitions(
+                [transition(
+                    0,
+                    [0, 0],
+                    1,
+                    [TapeMovement::Stay(None), TapeMovement::Right(Some(4))]
+                )],
+                0,
+                1,
+                2,
+                2,
+                4,
+                2
+            )
+            .unwrap_err(),
+            MachineError::TapeCharOutOfRange {
+                char: 4,
+                tape_chars: 4
+            }
+        );
+        assert_eq!(
+            MultiTm::build(vec![None; 32], 1, 1, 2, 2, 4, 2).unwrap_err(),
+            MachineError::SameHaltingState { state: 1 }
+        );
+    }
+
+    #[test]
+    fn too_many_track_symbols() {
+        // Four tapes of 16 symbols need 32^4 track symbols, which do not fit in a u16
+        let multi_tm = MultiTm::build(vec![None; 2 * 16usize.pow(4)], 0, 1, 2, 2, 16, 4).unwrap();
+        assert_eq!(
+            multi_tm.to_single_tape().unwrap_err(),
+            MachineError::TooManyTapeChars
+        );
+    }
+}
@@ -1,79 +1,287 @@
 use crate::{
-    machine_utils::{add_tape_mov_stay_fir, table_lookup},
+    diagnostics::{accept_state_diagnostics, Diagnostic, DiagnosticKind},
+    machine_utils::{
+        add_tape_mov_stay_fir, reachable_states, remove_char_column, remove_states, swap_in_set,
+        swap_rows, table_lookup,
+    },
     transitions::SingleChar,
-    StateMachine, StateMachineBuilder, TapeMovement,
+    FiniteAutomaton, MachineError, StateMachine, StateMachineBuilder, TapeMovement,
+};
+use std::{
+    cmp::max,
+    collections::{BTreeSet, HashSet},
+    iter,
 };
-use std::{cmp::max, collections::HashSet};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Nfa {
     transition_table: Vec<HashSet<u16>>,
     accept_states: HashSet<u16>,
-    //TODO:
     states: u16,
     chars: u16,
 }
 
-//TODO: create
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NfaBuilder {
     transition_table: Vec<HashSet<u16>>,
     accept_states: HashSet<u16>,
-    max_state: u16,
-    max_char: u16,
+    states: u16,
+    chars: u16,
+}
+
+impl NfaBuilder {
+    pub fn new(base: Nfa) -> Self {
+        let Nfa {
+            transition_table,
+            accept_states,
+            states,
+            chars,
+        } = base;
+        Self {
+            transition_table,
+            accept_states,
+            states,
+            chars,
+        }
+    }
+
+    /// Adds `transition.end` to the states reached from `transition.start` on `transition.char`.
+    /// Returns whether the target was newly added.
+    pub fn add_transition(&mut self, transition: SingleChar) -> Result<bool, MachineError> {
+        let index = self.transition_index(&transition)?;
+        Ok(self.transition_table[index].insert(transition.end))
+    }
+
+    /// Removes `transition.end` from the states reached from `transition.start` on
+    /// `transition.char`. Returns whether the target was present.
+    pub fn remove_transition(&mut self, transition: SingleChar) -> Result<bool, MachineError> {
+        let index = self.transition_index(&transition)?;
+        Ok(self.transition_table[index].remove(&transition.end))
+    }
+
+    /// Checks the builder for accept states that are missing or can not be reached from the start
+    /// state. These are only warnings, as they do not stop the builder from being turned into a
+    /// `Nfa`.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let chars = self.chars as usize;
+        let edges = self
+            .transition_table
+            .iter()
+            .enumerate()
+            .flat_map(|(index, set)| set.iter().map(move |&to| ((index / chars) as u16, to)));
+        accept_state_diagnostics(self.states, edges, &self.accept_states)
+    }
+
+    /// Checks the builder like [`NfaBuilder::validate`], and also reports every state and
+    /// character without exactly one transition, as the machine is meant to be deterministic.
+    /// These errors come first, in the order of the transition table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{
+    ///     diagnostics::DiagnosticKind,
+    ///     nfa::{Nfa, NfaBuilder},
+    /// };
+    /// use std::collections::HashSet;
+    ///
+    /// let nfa = Nfa::build(
+    ///     vec![HashSet::from([0, 1]), HashSet::new()],
+    ///     HashSet::from([1]),
+    ///     2,
+    ///     1,
+    /// )
+    /// .unwrap();
+    /// let kinds: Vec<DiagnosticKind> = NfaBuilder::from(nfa)
+    ///     .validate_as_dfa()
+    ///     .iter()
+    ///     .map(|diagnostic| diagnostic.kind)
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     kinds,
+    ///     vec![
+    ///         DiagnosticKind::DuplicateTransition,
+    ///         DiagnosticKind::MissingTransition
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate_as_dfa(&self) -> Vec<Diagnostic> {
+        let chars = self.chars as usize;
+        let mut diagnostics: Vec<Diagnostic> = self
+            .transition_table
+            .iter()
+            .enumerate()
+            .filter_map(|(index, set)| {
+                let kind = match set.len() {
+                    0 => DiagnosticKind::MissingTransition,
+                    1 => return None,
+                    _ => DiagnosticKind::DuplicateTransition,
+                };
+                Some(Diagnostic::new(
+                    kind,
+                    Some((index / chars) as u16),
+                    Some((index % chars) as u16),
+                ))
+            })
+            .collect();
+        diagnostics.extend(self.validate());
+        diagnostics
+    }
+
+    fn transition_index(&self, transition: &SingleChar) -> Result<usize, MachineError> {
+        let &SingleChar { start, end, char } = transition;
+        self.check_state(start)?;
+        self.check_state(end)?;
+        if char >= self.chars {
+            return Err(MachineError::CharOutOfRange {
+                char,
+                chars: self.chars,
+            });
+        }
+        Ok(table_lookup(
+            start as usize,
+            char as usize,
+            self.chars as usize,
+        ))
+    }
+
+    fn check_state(&self, state: u16) -> Result<(), MachineError> {
+        if state >= self.states {
+            return Err(MachineError::StateOutOfRange {
+                state,
+                states: self.states,
+            });
+        }
+        Ok(())
+    }
+
+    fn swap_state(&mut self, first: u16, second: u16) {
+        debug_assert!(first < self.states);
+        debug_assert!(second < self.states);
+        swap_rows(
+            &mut self.transition_table,
+            first,
+            second,
+            self.chars as usize,
+        );
+
+        for set in self.transition_table.iter_mut() {
+            swap_in_set(set, first, second);
+        }
+
+        swap_in_set(&mut self.accept_states, first, second);
+    }
 }
 
 impl StateMachineBuilder for NfaBuilder {
     type Trasition = SingleChar;
-
     type Machine = Nfa;
-
-    type Error = ();
+    type Error = MachineError;
 
     fn add_state(&mut self) -> u16 {
-        todo!()
+        self.transition_table
+            .extend(iter::repeat_n(HashSet::new(), self.chars as usize));
+        self.states += 1;
+        debug_assert!(
+            self.transition_table.len() == (self.chars as usize) * (self.states as usize)
+        );
+
+        self.states - 1
     }
 
     fn remove_state(&mut self, state: u16) -> Result<Option<u16>, Self::Error> {
-        todo!()
+        self.check_state(state)?;
+        if self.states == 1 {
+            return Err(MachineError::LastState);
+        }
+
+        self.swap_state(state, self.states - 1);
+        self.states -= 1;
+        self.transition_table
+            .truncate(self.states as usize * self.chars as usize);
+
+        for set in self.transition_table.iter_mut() {
+            set.remove(&self.states);
+        }
+
+        self.accept_states.remove(&self.states);
+
+        debug_assert!(
+            self.transition_table.len() == (self.chars as usize) * (self.states as usize)
+        );
+        if self.states == state {
+            Ok(None)
+        } else {
+            Ok(Some(self.states))
+        }
     }
 
-    fn set_transition(&mut self, transition: Self::Trasition) -> Result<(), Self::Error> {
-        todo!()
+    /// Replaces every state reached from `transition.start` on `transition.char` with just
+    /// `transition.end`. Use [`NfaBuilder::add_transition`] to keep the existing targets.
+    fn set_transition(&mut self, transition: SingleChar) -> Result<(), Self::Error> {
+        let index = self.transition_index(&transition)?;
+        self.transition_table[index] = HashSet::from([transition.end]);
+        Ok(())
     }
 
     fn set_start_state(&mut self, new_start_state: u16) -> Result<(), Self::Error> {
-        todo!()
+        self.check_state(new_start_state)?;
+        self.swap_state(0, new_start_state);
+        Ok(())
     }
 
     fn add_accept_state(&mut self, state: u16) -> Result<bool, Self::Error> {
-        todo!()
+        self.check_state(state)?;
+        Ok(self.accept_states.insert(state))
     }
 
     fn remove_accept_state(&mut self, state: u16) -> Result<bool, Self::Error> {
-        todo!()
+        self.check_state(state)?;
+        Ok(self.accept_states.remove(&state))
     }
 
     fn add_char(&mut self) {
-        todo!()
+        let chunks = self.transition_table.chunks_exact(self.chars as usize);
+        debug_assert!(chunks.remainder().is_empty());
+        debug_assert!(chunks.len() == self.states as usize);
+
+        self.transition_table = chunks
+            .flat_map(|chunk| chunk.iter().cloned().chain([HashSet::new()]))
+            .collect();
+        self.chars += 1;
+        debug_assert!(self.transition_table.len() == (self.chars * self.states) as usize);
     }
 
     fn remove_char(&mut self, char: u16) -> Result<Option<u16>, Self::Error> {
-        todo!()
+        let moved = remove_char_column(
+            &mut self.transition_table,
+            char,
+            self.chars,
+            self.chars as usize,
+        )?;
+        self.chars -= 1;
+        debug_assert!(self.transition_table.len() == (self.chars * self.states) as usize);
+        Ok(moved)
     }
 }
 
-//TODO:
 impl From<Nfa> for NfaBuilder {
     fn from(value: Nfa) -> Self {
-        todo!()
+        NfaBuilder::new(value)
     }
 }
+
 impl TryFrom<NfaBuilder> for Nfa {
-    type Error = ();
+    type Error = MachineError;
 
     fn try_from(value: NfaBuilder) -> Result<Self, Self::Error> {
-        todo!()
+        Nfa::build(
+            value.transition_table,
+            value.accept_states,
+            value.states,
+            value.chars,
+        )
     }
 }
 
@@ -83,21 +291,33 @@ impl Nfa {
         accept_states: HashSet<u16>,
         states: u16,
         chars: u16,
-    ) -> Result<Nfa, ()> {
-        if states == 0 || chars == 0 {
-            return Err(());
+    ) -> Result<Nfa, MachineError> {
+        if states == 0 {
+            return Err(MachineError::NoStates);
         }
-        if transition_table.len() != (states * chars) as usize {
-            return Err(());
+        if chars == 0 {
+            return Err(MachineError::NoChars);
         }
-
-        if transition_table
+        if transition_table.len() != states as usize * chars as usize {
+            return Err(MachineError::WrongTableLength {
+                expected: states as usize * chars as usize,
+                actual: transition_table.len(),
+            });
+        }
+        if let Some((index, state)) = transition_table
             .iter()
-            .flatten()
-            .chain(accept_states.iter())
-            .any(|&item| item >= states)
+            .enumerate()
+            .flat_map(|(index, set)| set.iter().map(move |&state| (index, state)))
+            .find(|&(_, state)| state >= states)
         {
-            return Err(());
+            return Err(MachineError::InvalidTransitionTarget {
+                index,
+                state,
+                states,
+            });
+        }
+        if let Some(&state) = accept_states.iter().find(|&&state| state >= states) {
+            return Err(MachineError::InvalidAcceptState { state, states });
         }
 
         Ok(Nfa {
@@ -187,6 +407,71 @@ impl Nfa {
     pub fn transition_table(&self) -> &[HashSet<u16>] {
         &self.transition_table
     }
+
+    /// The states that can be reached from the start state
+    pub fn reachable_states(&self) -> BTreeSet<u16> {
+        reachable_states(self.states, self.edges(), [0])
+    }
+
+    /// The states that can reach an accept state
+    pub fn coreachable_states(&self) -> BTreeSet<u16> {
+        reachable_states(
+            self.states,
+            self.edges().map(|(from, to)| (to, from)),
+            self.accept_states.iter().copied(),
+        )
+    }
+
+    /// Removes every state that is unreachable or can not reach an accept state, except the start
+    /// state. The states are renumbered and reported in the same way as [`Dfa::trim`].
+    ///
+    /// [`Dfa::trim`]: crate::dfa::Dfa::trim
+    pub fn trim(&self) -> (Nfa, Vec<Option<u16>>) {
+        let useful: BTreeSet<u16> = self
+            .reachable_states()
+            .intersection(&self.coreachable_states())
+            .copied()
+            .collect();
+        let remove = (1..self.states)
+            .filter(|state| !useful.contains(state))
+            .collect();
+
+        let mut builder = NfaBuilder::from(self.clone());
+        let renumbered = remove_states(&mut builder, self.states, &remove)
+            .expect("The start state is always kept");
+        let nfa = Nfa::try_from(builder).expect("Removing states keeps the Nfa valid");
+        (nfa, renumbered)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.transition_table
+            .iter()
+            .enumerate()
+            .flat_map(|(index, set)| {
+                let from = (index / self.chars as usize) as u16;
+                set.iter().map(move |&to| (from, to))
+            })
+    }
+}
+
+impl FiniteAutomaton for Nfa {
+    fn start_states(&self) -> BTreeSet<u16> {
+        BTreeSet::from([0])
+    }
+
+    fn next_states(&self, states: &BTreeSet<u16>, char: u16) -> BTreeSet<u16> {
+        states
+            .iter()
+            .flat_map(|&s| {
+                &self.transition_table[table_lookup(s as usize, char as usize, self.chars as usize)]
+            })
+            .copied()
+            .collect()
+    }
+
+    fn is_accepting(&self, states: &BTreeSet<u16>) -> bool {
+        states.iter().any(|s| self.accept_states.contains(s))
+    }
 }
 
 impl StateMachine for Nfa {
@@ -204,11 +489,11 @@ impl StateMachine for Nfa {
             cur_states = next_states;
             next_states = HashSet::new()
         }
-        return cur_states.iter().any(|s| self.accept_states.contains(s));
+        cur_states.iter().any(|s| self.accept_states.contains(s))
     }
 
     fn trace_states_validated(&self, input: &[u16]) -> Vec<(u16, Vec<TapeMovement>)> {
-        if input.len() == 0 {
+        if input.is_empty() {
             return vec![(0, vec![TapeMovement::Stay(None)])];
         }
         let mut max_len = 0;
@@ -243,6 +528,8 @@ impl StateMachine for Nfa {
 
 #[cfg(test)]
 mod nfa_tests {
+    mod nfa_builder_tests;
+    mod trim_tests;
 
     use crate::machine_utils::add_tape_mov_stay_fir;
     use crate::{StateMachine, TapeMovement};
@@ -0,0 +1,402 @@
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticKind},
+    machine_utils::ends_in_one,
+    nfa::{Nfa, NfaBuilder},
+    transitions::SingleChar,
+    MachineError, StateMachine, StateMachineBuilder,
+};
+use std::{cmp::min, collections::HashSet};
+
+#[test]
+fn create_builder_from_scratch() {
+    let _builder = NfaBuilder::new(ends_in_one());
+}
+
+#[test]
+fn create_builder_from_into() {
+    let _builder: NfaBuilder = ends_in_one().into();
+}
+
+#[test]
+fn round_trip() {
+    let nfa = ends_in_one();
+    let builder: NfaBuilder = nfa.clone().into();
+
+    assert_eq!(Nfa::try_from(builder).unwrap(), nfa);
+}
+
+#[test]
+fn add_state() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+
+    assert_eq!(builder.add_state(), 2);
+    assert_eq!(builder.states, 2 + 1);
+    assert_eq!(builder.transition_table.len(), 6);
+
+    // Unlike a Dfa, a Nfa without transitions out of a state is still valid
+    let new_nfa: Nfa = builder.try_into().unwrap();
+    assert!(new_nfa.accepts(&[0, 1]).unwrap());
+}
+
+#[test]
+fn remove_state_last() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+
+    assert_eq!(builder.remove_state(1).unwrap(), None);
+    let new_nfa: Nfa = builder.try_into().unwrap();
+    assert_eq!(new_nfa.states, 1);
+    assert_eq!(new_nfa.chars, 2);
+    assert_eq!(
+        new_nfa.transition_table,
+        vec![HashSet::from([0]), HashSet::from([0])]
+    );
+    assert_eq!(new_nfa.accept_states, HashSet::new());
+}
+
+#[test]
+fn remove_state_first() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+
+    assert_eq!(builder.remove_state(0).unwrap(), Some(1));
+    let new_nfa: Nfa = builder.try_into().unwrap();
+    assert_eq!(new_nfa.states, 1);
+    assert_eq!(
+        new_nfa.transition_table,
+        vec![HashSet::new(), HashSet::new()]
+    );
+    assert_eq!(new_nfa.accept_states, HashSet::from([0]));
+}
+
+#[test]
+fn remove_state_large() {
+    // Every state moves to every state with a lower number on every char
+    let table = (0..8)
+        .flat_map(|state| vec![(0..state).collect::<HashSet<u16>>(); 3])
+        .collect();
+    let nfa = Nfa::build(table, HashSet::from([0, 2, 4, 6]), 8, 3).unwrap();
+    let mut builder: NfaBuilder = nfa.into();
+
+    assert_eq!(builder.remove_state(1).unwrap(), Some(7));
+    assert_eq!(builder.states, 7);
+    assert_eq!(builder.chars, 3);
+    assert_eq!(builder.accept_states, HashSet::from([0, 2, 4, 6]));
+    // The old state 7 is now state 1 and moves to every other state
+    assert_eq!(
+        builder.transition_table[3..6],
+        vec![HashSet::from([0, 2, 3, 4, 5, 6]); 3]
+    );
+    // State 2 used to move to 0 and 1, but 1 was removed
+    assert_eq!(builder.transition_table[6..9], vec![HashSet::from([0]); 3]);
+    // State 3 used to move to 0, 1 and 2
+    assert_eq!(
+        builder.transition_table[9..12],
+        vec![HashSet::from([0, 2]); 3]
+    );
+
+    assert_eq!(builder.remove_state(1).unwrap(), Some(6));
+    assert_eq!(builder.states, 6);
+    assert_eq!(builder.accept_states, HashSet::from([0, 2, 4, 1]));
+    assert_eq!(
+        builder.transition_table[3..6],
+        vec![HashSet::from([0, 2, 3, 4, 5]); 3]
+    );
+}
+
+#[test]
+fn remove_state_invalid_no_effect() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let copy = builder.clone();
+
+    for i in 2..100 {
+        assert_eq!(
+            builder.remove_state(i).unwrap_err(),
+            MachineError::StateOutOfRange {
+                state: i,
+                states: 2
+            }
+        );
+        assert_eq!(builder, copy);
+    }
+
+    assert_eq!(builder.remove_state(0).unwrap(), Some(1));
+    let copy = builder.clone();
+
+    assert_eq!(
+        builder.remove_state(0).unwrap_err(),
+        MachineError::LastState
+    );
+    assert_eq!(builder, copy);
+}
+
+#[test]
+fn add_then_remove() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let copy = builder.clone();
+
+    for i in 1..100 {
+        for _ in 0..i {
+            builder.add_state();
+        }
+
+        for _ in 0..i {
+            builder
+                .remove_state(min(builder.states - 1, 2 + i % 7))
+                .unwrap();
+        }
+        assert_eq!(builder, copy);
+    }
+}
+
+#[test]
+fn set_transition() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+
+    builder
+        .set_transition(SingleChar {
+            start: 0,
+            end: 1,
+            char: 1,
+        })
+        .unwrap();
+    assert_eq!(builder.transition_table[1], HashSet::from([1]));
+
+    assert_eq!(
+        builder
+            .set_transition(SingleChar {
+                start: 0,
+                end: 2,
+                char: 0,
+            })
+            .unwrap_err(),
+        MachineError::StateOutOfRange {
+            state: 2,
+            states: 2
+        }
+    );
+    assert_eq!(
+        builder
+            .set_transition(SingleChar {
+                start: 2,
+                end: 0,
+                char: 0,
+            })
+            .unwrap_err(),
+        MachineError::StateOutOfRange {
+            state: 2,
+            states: 2
+        }
+    );
+    assert_eq!(
+        builder
+            .set_transition(SingleChar {
+                start: 0,
+                end: 0,
+                char: 2,
+            })
+            .unwrap_err(),
+        MachineError::CharOutOfRange { char: 2, chars: 2 }
+    );
+
+    assert_eq!(builder.add_state(), 2);
+    assert!(builder
+        .set_transition(SingleChar {
+            start: 2,
+            end: 1,
+            char: 0,
+        })
+        .is_ok());
+}
+
+#[test]
+fn add_and_remove_transition() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let to_one = SingleChar {
+        start: 1,
+        end: 1,
+        char: 0,
+    };
+
+    assert!(builder.add_transition(to_one).unwrap());
+    assert!(!builder.add_transition(to_one).unwrap());
+    assert_eq!(builder.transition_table[2], HashSet::from([1]));
+
+    assert!(builder
+        .add_transition(SingleChar {
+            start: 1,
+            end: 0,
+            char: 0,
+        })
+        .unwrap());
+    assert_eq!(builder.transition_table[2], HashSet::from([0, 1]));
+
+    assert!(builder.remove_transition(to_one).unwrap());
+    assert!(!builder.remove_transition(to_one).unwrap());
+    assert_eq!(builder.transition_table[2], HashSet::from([0]));
+
+    assert_eq!(
+        builder
+            .add_transition(SingleChar {
+                start: 1,
+                end: 5,
+                char: 0,
+            })
+            .unwrap_err(),
+        MachineError::StateOutOfRange {
+            state: 5,
+            states: 2
+        }
+    );
+}
+
+#[test]
+fn set_start_state() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let copy = builder.clone();
+    builder.set_start_state(0).unwrap();
+    assert_eq!(builder, copy);
+
+    builder.set_start_state(1).unwrap();
+    assert_eq!(
+        builder.transition_table,
+        vec![
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::from([1]),
+            HashSet::from([1, 0]),
+        ]
+    );
+    assert_eq!(builder.accept_states, HashSet::from([0]));
+    assert_eq!(builder.states, 2);
+    assert_eq!(builder.chars, 2);
+}
+
+#[test]
+fn set_start_state_invalid() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let copy = builder.clone();
+    for i in 2..100 {
+        assert!(builder.set_start_state(i).is_err());
+    }
+
+    assert_eq!(builder, copy);
+}
+
+#[test]
+fn add_char() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let copy = builder.clone();
+    builder.add_char();
+    builder.remove_char(2).unwrap();
+    assert_eq!(builder, copy);
+
+    builder.add_char();
+    assert_eq!(
+        builder.transition_table,
+        vec![
+            HashSet::from([0]),
+            HashSet::from([0, 1]),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        ]
+    );
+    assert_eq!(builder.accept_states, copy.accept_states);
+    assert_eq!(builder.states, copy.states);
+    assert_eq!(builder.chars, 3);
+}
+
+#[test]
+fn remove_char() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+
+    assert_eq!(
+        builder.remove_char(2).unwrap_err(),
+        MachineError::CharOutOfRange { char: 2, chars: 2 }
+    );
+    assert_eq!(builder.remove_char(0).unwrap(), Some(1));
+    assert_eq!(
+        builder.transition_table,
+        vec![HashSet::from([0, 1]), HashSet::new()]
+    );
+    assert_eq!(builder.remove_char(0).unwrap_err(), MachineError::LastChar);
+}
+
+#[test]
+fn add_accept_state() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let copy = builder.clone();
+
+    for i in 2..100 {
+        assert!(builder.add_accept_state(i).is_err());
+        assert_eq!(builder, copy);
+    }
+    assert!(!builder.add_accept_state(1).unwrap());
+    assert_eq!(builder, copy);
+
+    assert!(builder.add_accept_state(0).unwrap());
+    assert_eq!(builder.transition_table, copy.transition_table);
+    assert_eq!(builder.accept_states, HashSet::from([0, 1]));
+}
+
+#[test]
+fn remove_accept_state() {
+    let mut builder: NfaBuilder = ends_in_one().into();
+
+    assert!(!builder.remove_accept_state(0).unwrap());
+    assert!(builder.remove_accept_state(1).unwrap());
+    assert_eq!(builder.accept_states, HashSet::new());
+    assert!(builder.remove_accept_state(2).is_err());
+}
+
+#[test]
+fn validate() {
+    assert_eq!(NfaBuilder::from(ends_in_one()).validate(), vec![]);
+
+    let mut builder: NfaBuilder = ends_in_one().into();
+    let unreachable = builder.add_state();
+    builder.add_accept_state(unreachable).unwrap();
+    assert_eq!(
+        builder.validate(),
+        vec![Diagnostic::new(
+            DiagnosticKind::UnreachableAcceptState,
+            Some(2),
+            None
+        )]
+    );
+
+    builder.remove_accept_state(1).unwrap();
+    builder.remove_accept_state(2).unwrap();
+    assert_eq!(
+        builder.validate(),
+        vec![Diagnostic::new(DiagnosticKind::NoAcceptStates, None, None)]
+    );
+}
+
+#[test]
+fn validate_as_dfa() {
+    let diagnostics = NfaBuilder::from(ends_in_one()).validate_as_dfa();
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic::new(DiagnosticKind::DuplicateTransition, Some(0), Some(1)),
+            Diagnostic::new(DiagnosticKind::MissingTransition, Some(1), Some(0)),
+            Diagnostic::new(DiagnosticKind::MissingTransition, Some(1), Some(1)),
+        ]
+    );
+
+    // Accepts strings ending in 1 without any choices to make
+    let deterministic = Nfa::build(
+        vec![
+            HashSet::from([0]),
+            HashSet::from([1]),
+            HashSet::from([0]),
+            HashSet::from([1]),
+        ],
+        HashSet::from([1]),
+        2,
+        2,
+    )
+    .unwrap();
+    assert_eq!(NfaBuilder::from(deterministic).validate_as_dfa(), vec![]);
+}
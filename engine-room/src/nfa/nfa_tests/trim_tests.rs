@@ -0,0 +1,69 @@
+use crate::{equivalent, nfa::Nfa, Equivalence};
+use std::collections::{BTreeSet, HashSet};
+
+// Accepts strings ending in 1 through state 2. State 1 is dead, state 3 is unreachable and state
+// 4 is both.
+fn cluttered() -> Nfa {
+    Nfa::build(
+        vec![
+            HashSet::from([0, 1]),
+            HashSet::from([0, 2]),
+            HashSet::from([1]),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::from([2]),
+            HashSet::from([4]),
+            HashSet::new(),
+            HashSet::from([4]),
+        ],
+        HashSet::from([2]),
+        5,
+        2,
+    )
+    .unwrap()
+}
+
+#[test]
+fn reachable_and_coreachable() {
+    let nfa = cluttered();
+
+    assert_eq!(nfa.reachable_states(), BTreeSet::from([0, 1, 2]));
+    assert_eq!(nfa.coreachable_states(), BTreeSet::from([0, 2, 3]));
+}
+
+#[test]
+fn trim_removes_and_renumbers() {
+    let nfa = cluttered();
+    let (trimmed, renumbered) = nfa.trim();
+
+    // State 2 takes the place of state 1
+    assert_eq!(renumbered, vec![Some(0), None, Some(1), None, None]);
+    assert_eq!(
+        trimmed.transition_table(),
+        &[
+            HashSet::from([0]),
+            HashSet::from([0, 1]),
+            HashSet::new(),
+            HashSet::new(),
+        ]
+    );
+    assert_eq!(trimmed.accept_states(), &HashSet::from([1]));
+    assert_eq!(equivalent(&nfa, &trimmed), Ok(Equivalence::Equivalent));
+}
+
+#[test]
+fn trim_keeps_start_state() {
+    // Nothing is accepted
+    let nfa = Nfa::build(
+        vec![HashSet::from([1]), HashSet::from([0])],
+        HashSet::new(),
+        2,
+        1,
+    )
+    .unwrap();
+    let (trimmed, renumbered) = nfa.trim();
+
+    assert_eq!(renumbered, vec![Some(0), None]);
+    assert_eq!(trimmed.transition_table(), &[HashSet::new()]);
+}
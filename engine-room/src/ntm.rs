@@ -0,0 +1,565 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use crate::{
+    machine_utils::{table_lookup, validate_input},
+    simulation::{DEFAULT_CONFIGURATION_BUDGET, DEFAULT_STEP_BUDGET},
+    tm::{validate_halting_states, validate_table, TmConfiguration},
+    transitions::TmTransition,
+    MachineError, Simulate, StateMachine, TapeMovement, Verdict,
+};
+
+/// # Nondeterministic Turing Machine
+///
+/// A single tape Turing machine that may have any number of transitions for the same state and
+/// symbol. The tape alphabet works the same as for a [`Tm`](crate::tm::Tm), and every branch
+/// halts as soon as it enters the accept or reject state or has no transition for the symbol
+/// under the head. The machine accepts if any branch accepts.
+///
+/// Branches can run forever, so the machine is run as a breadth first search over its
+/// configurations. The search skips configurations it has already seen, does not follow a branch
+/// past its step budget and gives up after exploring its configuration budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ntm {
+    transition_table: Vec<HashSet<(u16, TapeMovement)>>,
+    accept_state: u16,
+    reject_state: u16,
+    states: u16,
+    chars: u16,
+    tape_chars: u16,
+    step_budget: usize,
+    configuration_budget: usize,
+}
+
+impl Ntm {
+    /// Builds a nondeterministic Turing machine. The cell for reading `symbol` in `state` is
+    /// stored at `state * tape_chars + symbol`, so the table has a length of
+    /// `states * tape_chars`. Each cell holds every `(next_state, movement)` pair the machine can
+    /// take, where the movement carries the symbol to write before moving.
+    pub fn build(
+        transition_table: Vec<HashSet<(u16, TapeMovement)>>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+    ) -> Result<Ntm, MachineError> {
+        validate_table(&transition_table, states, chars, tape_chars, false)?;
+        validate_halting_states(accept_state, reject_state, states)?;
+
+        Ok(Ntm {
+            transition_table,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+            step_budget: DEFAULT_STEP_BUDGET,
+            configuration_budget: DEFAULT_CONFIGURATION_BUDGET,
+        })
+    }
+
+    /// Builds a nondeterministic Turing machine from a list of transitions instead of a full
+    /// transition table
+    pub fn from_transitions(
+        transitions: impl IntoIterator<Item = TmTransition>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+    ) -> Result<Ntm, MachineError> {
+        let mut transition_table = vec![HashSet::new(); states as usize * tape_chars as usize];
+        for TmTransition {
+            start,
+            end,
+            read,
+            movement,
+        } in transitions
+        {
+            if start >= states {
+                return Err(MachineError::StateOutOfRange {
+                    state: start,
+                    states,
+                });
+            }
+            if read >= tape_chars {
+                return Err(MachineError::TapeCharOutOfRange {
+                    char: read,
+                    tape_chars,
+                });
+            }
+            transition_table[table_lookup(start as usize, read as usize, tape_chars as usize)]
+                .insert((end, movement));
+        }
+        Ntm::build(
+            transition_table,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+        )
+    }
+
+    /// The blank symbol, which directly follows the input alphabet
+    pub fn blank(&self) -> u16 {
+        self.chars
+    }
+
+    pub fn tape_chars(&self) -> u16 {
+        self.tape_chars
+    }
+
+    pub fn accept_state(&self) -> u16 {
+        self.accept_state
+    }
+
+    pub fn reject_state(&self) -> u16 {
+        self.reject_state
+    }
+
+    /// The transition table in the layout described by [`Ntm::build`]
+    pub fn transition_table(&self) -> &[HashSet<(u16, TapeMovement)>] {
+        &self.transition_table
+    }
+
+    pub fn step_budget(&self) -> usize {
+        self.step_budget
+    }
+
+    /// Sets the number of steps a single branch may take before [`Ntm::run`] stops following it
+    pub fn set_step_budget(&mut self, step_budget: usize) {
+        self.step_budget = step_budget;
+    }
+
+    pub fn configuration_budget(&self) -> usize {
+        self.configuration_budget
+    }
+
+    /// Sets the number of configurations explored before [`Ntm::run`] gives up
+    pub fn set_configuration_budget(&mut self, configuration_budget: usize) {
+        self.configuration_budget = configuration_budget;
+    }
+
+    /// The configuration with `input` on the tape and the head over its first symbol, see
+    /// [`TmConfiguration::new`]
+    pub fn initial_configuration(&self, input: &[u16]) -> TmConfiguration {
+        TmConfiguration::new(input, self.blank())
+    }
+
+    /// Every configuration that can be reached from `configuration` in one step, along with the
+    /// movement the head made to get there. The configurations are sorted, and there are none
+    /// once the machine has halted.
+    pub fn next_configurations(
+        &self,
+        configuration: &TmConfiguration,
+    ) -> Vec<(TmConfiguration, TapeMovement)> {
+        if configuration.state == self.accept_state || configuration.state == self.reject_state {
+            return vec![];
+        }
+        let cell = &self.transition_table[table_lookup(
+            configuration.state as usize,
+            configuration.tape[configuration.head] as usize,
+            self.tape_chars as usize,
+        )];
+
+        let mut next: Vec<_> = cell
+            .iter()
+            .map(|&(state, movement)| {
+                let mut next = configuration.clone();
+                let movement = next.apply(movement, self.blank());
+                next.state = state;
+                next.steps += 1;
+                (next, movement)
+            })
+            .collect();
+        next.sort_by(|(a, _), (b, _)| a.cmp(b));
+        next
+    }
+
+    /// Runs the machine on `input` and reports if it accepts, rejects or ran out of steps or
+    /// configurations before finding out
+    pub fn run(&self, input: &[u16]) -> Result<Verdict, MachineError> {
+        validate_input(input, self.chars)?;
+        Ok(self.search(input).0)
+    }
+
+    /// The configurations along the shortest accepting computation on `input`, or `None` if no
+    /// accepting computation was found within the budgets
+    pub fn accepting_path(
+        &self,
+        input: &[u16],
+    ) -> Result<Option<Vec<TmConfiguration>>, MachineError> {
+        validate_input(input, self.chars)?;
+        Ok(match self.search(input) {
+            (Verdict::Accept, path) => Some(
+                path.into_iter()
+                    .map(|(configuration, _)| configuration)
+                    .collect(),
+            ),
+            _ => None,
+        })
+    }
+
+    /// Breadth first search over the configurations of the machine, remembering where each one
+    /// was reached from so the path to it can be rebuilt. Returns the path to the accepting
+    /// configuration, or to the deepest configuration explored when none was found.
+    fn search(&self, input: &[u16]) -> (Verdict, Vec<(TmConfiguration, TapeMovement)>) {
+        let start = self.initial_configuration(input);
+        let mut seen = HashSet::from([self.seen_key(&start)]);
+        let mut explored: Vec<(TmConfiguration, TapeMovement, Option<usize>)> =
+            vec![(start, TapeMovement::Stay(None), None)];
+        let mut cut_off = false;
+        let mut last = 0;
+        let mut q = VecDeque::from([0]);
+
+        let verdict = loop {
+            let Some(index) = q.pop_front() else {
+                break if cut_off {
+                    Verdict::BudgetExhausted
+                } else {
+                    Verdict::Reject
+                };
+            };
+            last = index;
+            let configuration = &explored[index].0;
+            if configuration.state == self.accept_state {
+                break Verdict::Accept;
+            }
+            let next = self.next_configurations(configuration);
+            if next.is_empty() {
+                continue;
+            }
+            if configuration.steps >= self.step_budget {
+                cut_off = true;
+                continue;
+            }
+            if explored.len() >= self.configuration_budget {
+                break Verdict::BudgetExhausted;
+            }
+
+            for (next, movement) in next {
+                if seen.insert(self.seen_key(&next)) {
+                    explored.push((next, movement, Some(index)));
+                    q.push_back(explored.len() - 1);
+                }
+            }
+        };
+
+        let mut path = vec![];
+        let mut cur = Some(last);
+        while let Some(index) = cur {
+            let (configuration, movement, parent) = &explored[index];
+            path.push((configuration.clone(), *movement));
+            cur = *parent;
+        }
+        path.reverse();
+        (verdict, path)
+    }
+
+    /// The part of `configuration` that decides how the machine continues. Configurations that
+    /// only differ in their step count or in blanks past the head behave the same.
+    fn seen_key(&self, configuration: &TmConfiguration) -> TmConfiguration {
+        let len = configuration
+            .tape_contents(self.blank())
+            .len()
+            .max(configuration.head + 1);
+        TmConfiguration {
+            state: configuration.state,
+            head: configuration.head,
+            tape: configuration.tape[..len].to_vec(),
+            steps: 0,
+        }
+    }
+}
+
+impl StateMachine for Ntm {
+    /// Checks if an accepting configuration is found. Returns [`MachineError::BudgetExhausted`]
+    /// if a budget runs out first, as the input may still be accepted.
+    fn accepts(&self, input: &[u16]) -> Result<bool, MachineError> {
+        self.run(input)?.accepted()
+    }
+
+    /// Accepts when an accepting configuration is found within the budgets
+    fn accepts_validated(&self, input: &[u16]) -> bool {
+        self.search(input).0 == Verdict::Accept
+    }
+
+    /// The states along the shortest accepting computation, or along the deepest computation
+    /// explored when the input is not accepted, along with the movement of the head on the step
+    /// into each state
+    fn trace_states_validated(&self, input: &[u16]) -> Vec<(u16, Vec<TapeMovement>)> {
+        self.search(input)
+            .1
+            .into_iter()
+            .map(|(configuration, movement)| (configuration.state, vec![movement]))
+            .collect()
+    }
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn chars(&self) -> u16 {
+        self.chars
+    }
+}
+
+/// # Ntm Frontier
+///
+/// The configuration of an `Ntm` in a [`Simulation`](crate::Simulation): every configuration
+/// reached by taking the same number of steps that has not been reached sooner. `seen` holds the
+/// configurations reached so far with their step counts cleared and the blanks past the head
+/// removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NtmFrontier {
+    pub active: BTreeSet<TmConfiguration>,
+    pub seen: BTreeSet<TmConfiguration>,
+}
+
+impl Simulate for Ntm {
+    type Configuration = NtmFrontier;
+
+    fn start_configuration(&self, input: &[u16]) -> NtmFrontier {
+        let start = self.initial_configuration(input);
+        NtmFrontier {
+            seen: BTreeSet::from([self.seen_key(&start)]),
+            active: BTreeSet::from([start]),
+        }
+    }
+
+    /// Takes every possible step at once. The machine halts when a configuration accepts, when
+    /// there is nothing left to explore or when either budget runs out.
+    fn next_configuration(&self, frontier: &NtmFrontier, input: &[u16]) -> Option<NtmFrontier> {
+        if frontier.active.is_empty()
+            || frontier.seen.len() >= self.configuration_budget
+            || frontier
+                .active
+                .iter()
+                .all(|configuration| configuration.steps >= self.step_budget)
+            || self.is_accepting_configuration(frontier, input)
+        {
+            return None;
+        }
+
+        let mut seen = frontier.seen.clone();
+        let active = frontier
+            .active
+            .iter()
+            .flat_map(|configuration| self.next_configurations(configuration))
+            .map(|(next, _)| next)
+            .filter(|next| seen.insert(self.seen_key(next)))
+            .collect();
+        Some(NtmFrontier { active, seen })
+    }
+
+    fn is_accepting_configuration(&self, frontier: &NtmFrontier, _input: &[u16]) -> bool {
+        frontier
+            .active
+            .iter()
+            .any(|configuration| configuration.state == self.accept_state)
+    }
+}
+
+#[cfg(test)]
+mod ntm_tests {
+    use super::*;
+    use crate::{machine_utils::all_inputs, Simulation};
+
+    const BLANK: u16 = 2;
+
+    fn transition(start: u16, read: u16, end: u16, movement: TapeMovement) -> TmTransition {
+        TmTransition {
+            start,
+            end,
+            read,
+            movement,
+        }
+    }
+
+    // Accepts strings containing 11 by guessing which 1 is the first of the pair
+    fn contains_one_one() -> Ntm {
+        use TapeMovement::Right;
+        Ntm::from_transitions(
+            [
+                transition(0, 0, 0, Right(None)),
+                transition(0, 1, 0, Right(None)),
+                transition(0, 1, 1, Right(None)),
+                transition(1, 1, 2, Right(None)),
+            ],
+            2,
+            3,
+            4,
+            2,
+            3,
+        )
+        .unwrap()
+    }
+
+    // Accepts strings containing a 1, with a branch that walks right over the blanks forever
+    fn contains_one_forever() -> Ntm {
+        use TapeMovement::Right;
+        Ntm::from_transitions(
+            [
+                transition(0, 0, 0, Right(None)),
+                transition(0, 1, 0, Right(None)),
+                transition(0, 1, 1, Right(None)),
+                transition(0, BLANK, 0, Right(None)),
+            ],
+            1,
+            2,
+            3,
+            2,
+            3,
+        )
+        .unwrap()
+    }
+
+    fn has_one_one(input: &[u16]) -> bool {
+        input.windows(2).any(|pair| pair == [1, 1])
+    }
+
+    #[test]
+    fn decides_contains_one_one() {
+        let ntm = contains_one_one();
+        for input in all_inputs(2, 8) {
+            let expected = if has_one_one(&input) {
+                Verdict::Accept
+            } else {
+                Verdict::Reject
+            };
+            assert_eq!(ntm.run(&input).unwrap(), expected, "{input:?}");
+            assert_eq!(
+                ntm.accepts(&input).unwrap(),
+                has_one_one(&input),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn accepting_path() {
+        let ntm = contains_one_one();
+        let path = ntm.accepting_path(&[0, 1, 1]).unwrap().unwrap();
+
+        let summary: Vec<_> = path.iter().map(|c| (c.state, c.head, c.steps)).collect();
+        assert_eq!(summary, vec![(0, 0, 0), (0, 1, 1), (1, 2, 2), (2, 3, 3)]);
+        assert_eq!(ntm.accepting_path(&[1, 0, 1]).unwrap(), None);
+
+        assert_eq!(
+            ntm.trace_states(&[1, 1]).unwrap(),
+            vec![
+                (0, vec![TapeMovement::Stay(None)]),
+                (1, vec![TapeMovement::Right(None)]),
+                (2, vec![TapeMovement::Right(None)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn infinite_branch() {
+        let mut ntm = contains_one_forever();
+        ntm.set_step_budget(20);
+
+        assert_eq!(ntm.run(&[0, 0, 1]).unwrap(), Verdict::Accept);
+        assert_eq!(ntm.run(&[0, 0]).unwrap(), Verdict::BudgetExhausted);
+        assert_eq!(ntm.accepts(&[0, 0]), Err(MachineError::BudgetExhausted));
+        assert!(!ntm.accepts_validated(&[0, 0]));
+
+        ntm.set_configuration_budget(3);
+        assert_eq!(ntm.run(&[0, 0, 0, 1]).unwrap(), Verdict::BudgetExhausted);
+    }
+
+    #[test]
+    fn repeated_configurations_reject() {
+        // Moving left at the first cell stays there, so the machine cycles between two states
+        let ntm = Ntm::from_transitions(
+            [
+                transition(0, 0, 1, TapeMovement::Left(None)),
+                transition(1, 0, 0, TapeMovement::Left(None)),
+            ],
+            2,
+            3,
+            4,
+            2,
+            3,
+        )
+        .unwrap();
+
+        assert_eq!(ntm.run(&[0]).unwrap(), Verdict::Reject);
+    }
+
+    #[test]
+    fn simulation_matches_run() {
+        let ntm = contains_one_one();
+        for input in all_inputs(2, 6) {
+            let mut simulation = Simulation::new(&ntm, &input).unwrap();
+            simulation.run_to_end();
+            assert_eq!(
+                simulation.is_accepting(),
+                ntm.accepts(&input).unwrap(),
+                "{input:?}"
+            );
+        }
+
+        let mut ntm = contains_one_forever();
+        ntm.set_step_budget(10);
+        let mut simulation = Simulation::new(&ntm, &[0]).unwrap();
+        simulation.run_to_end();
+        assert!(!simulation.is_accepting());
+        assert_eq!(simulation.steps(), 10);
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            Ntm::build(vec![HashSet::new(); 3], 0, 1, 2, 2, 3).unwrap_err(),
+            MachineError::WrongTableLength {
+                expected: 6,
+                actual: 3
+            }
+        );
+        assert_eq!(
+            Ntm::from_transitions(
+                [
+                    transition(0, 0, 0, TapeMovement::Right(None)),
+                    transition(0, 0, 1, TapeMovement::Stay(None)),
+                ],
+                0,
+                1,
+                2,
+                1,
+                2
+            )
+            .unwrap_err(),
+            MachineError::InvalidMovement { index: 0 }
+        );
+        assert_eq!(
+            Ntm::from_transitions(
+                [transition(1, 1, 5, TapeMovement::Right(None))],
+                0,
+                1,
+                2,
+                1,
+                2
+            )
+            .unwrap_err(),
+            MachineError::InvalidTransitionTarget {
+                index: 3,
+                state: 5,
+                states: 2
+            }
+        );
+        assert_eq!(
+            Ntm::from_transitions([], 0, 2, 2, 1, 2).unwrap_err(),
+            MachineError::InvalidRejectState {
+                state: 2,
+                states: 2
+            }
+        );
+        assert_eq!(
+            Ntm::from_transitions([], 1, 1, 2, 1, 2).unwrap_err(),
+            MachineError::SameHaltingState { state: 1 }
+        );
+    }
+}
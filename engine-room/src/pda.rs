@@ -0,0 +1,695 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use crate::{
+    machine_utils::{table_lookup, validate_input},
+    simulation::DEFAULT_CONFIGURATION_BUDGET,
+    transitions::PdaTransition,
+    MachineError, Simulate, StateMachine, TapeMovement, Verdict,
+};
+
+/// # Acceptance
+///
+/// How a `Pda` decides to accept once the whole input has been read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Acceptance {
+    /// The machine is in one of its accept states
+    FinalState,
+    /// The stack is empty, including the initial stack symbol, no matter which state the machine
+    /// is in
+    EmptyStack,
+}
+
+/// # Pda Configuration
+///
+/// A `Pda` in `state` after reading the first `position` characters of the input. The top of the
+/// stack is the last element of `stack`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PdaConfiguration {
+    pub state: u16,
+    pub position: usize,
+    pub stack: Vec<u16>,
+}
+
+/// # Pushdown Automaton
+///
+/// A nondeterministic pushdown automaton. The stack has its own alphabet of `stack_chars` symbols
+/// and starts out holding only the initial stack symbol 0, the same way the machine starts out in
+/// state 0. Every transition may read an input character or epsilon, pop a stack
+/// symbol or epsilon and then pushes a (possibly empty) string of stack symbols.
+///
+/// Epsilon transitions can push forever, so the machine is run as a breadth first search over its
+/// configurations that gives up after a configurable number of configurations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pda {
+    transition_table: Vec<HashSet<(u16, Vec<u16>)>>,
+    accept_states: HashSet<u16>,
+    states: u16,
+    chars: u16,
+    stack_chars: u16,
+    acceptance: Acceptance,
+    configuration_budget: usize,
+}
+
+impl Pda {
+    /// Builds a pushdown automaton. The transition table has a row for every state and input
+    /// character, followed by a row for epsilon (index `chars`). Every row has a cell for each stack
+    /// symbol, followed by a cell for not popping (index `stack_chars`). Each cell holds the
+    /// `(next_state, push)` pairs the machine can move to, so the cell for reading `char` and
+    /// popping `pop` in `state` is at
+    /// `(state * (chars + 1) + char) * (stack_chars + 1) + pop`, giving the table a length of
+    /// `states * (chars + 1) * (stack_chars + 1)`.
+    pub fn build(
+        transition_table: Vec<HashSet<(u16, Vec<u16>)>>,
+        accept_states: HashSet<u16>,
+        states: u16,
+        chars: u16,
+        stack_chars: u16,
+        acceptance: Acceptance,
+    ) -> Result<Pda, MachineError> {
+        if states == 0 {
+            return Err(MachineError::NoStates);
+        }
+        if chars == 0 {
+            return Err(MachineError::NoChars);
+        }
+        if stack_chars == 0 {
+            return Err(MachineError::NoStackChars);
+        }
+        let expected = states as usize * (chars as usize + 1) * (stack_chars as usize + 1);
+        if transition_table.len() != expected {
+            return Err(MachineError::WrongTableLength {
+                expected,
+                actual: transition_table.len(),
+            });
+        }
+        for (index, (state, push)) in transition_table
+            .iter()
+            .enumerate()
+            .flat_map(|(index, set)| set.iter().map(move |target| (index, target)))
+        {
+            if *state >= states {
+                return Err(MachineError::InvalidTransitionTarget {
+                    index,
+                    state: *state,
+                    states,
+                });
+            }
+            if let Some(&char) = push.iter().find(|&&char| char >= stack_chars) {
+                return Err(MachineError::StackCharOutOfRange { char, stack_chars });
+            }
+        }
+        if let Some(&state) = accept_states.iter().find(|&&state| state >= states) {
+            return Err(MachineError::InvalidAcceptState { state, states });
+        }
+
+        Ok(Pda {
+            transition_table,
+            accept_states,
+            states,
+            chars,
+            stack_chars,
+            acceptance,
+            configuration_budget: DEFAULT_CONFIGURATION_BUDGET,
+        })
+    }
+
+    /// Builds a pushdown automaton from a list of transitions instead of a full transition table
+    pub fn from_transitions(
+        transitions: impl IntoIterator<Item = PdaTransition>,
+        accept_states: HashSet<u16>,
+        states: u16,
+        chars: u16,
+        stack_chars: u16,
+        acceptance: Acceptance,
+    ) -> Result<Pda, MachineError> {
+        let len = states as usize * (chars as usize + 1) * (stack_chars as usize + 1);
+        let mut transition_table = vec![HashSet::new(); len];
+        for PdaTransition {
+            start,
+            end,
+            char,
+            pop,
+            push,
+        } in transitions
+        {
+            if start >= states {
+                return Err(MachineError::StateOutOfRange {
+                    state: start,
+                    states,
+                });
+            }
+            if let Some(char) = char.filter(|&char| char >= chars) {
+                return Err(MachineError::CharOutOfRange { char, chars });
+            }
+            if let Some(char) = pop.filter(|&char| char >= stack_chars) {
+                return Err(MachineError::StackCharOutOfRange { char, stack_chars });
+            }
+            transition_table[Self::lookup(start, char, pop, chars, stack_chars)]
+                .insert((end, push));
+        }
+
+        Pda::build(
+            transition_table,
+            accept_states,
+            states,
+            chars,
+            stack_chars,
+            acceptance,
+        )
+    }
+
+    fn lookup(
+        state: u16,
+        char: Option<u16>,
+        pop: Option<u16>,
+        chars: u16,
+        stack_chars: u16,
+    ) -> usize {
+        let row = table_lookup(
+            state as usize,
+            char.unwrap_or(chars) as usize,
+            chars as usize + 1,
+        );
+        table_lookup(
+            row,
+            pop.unwrap_or(stack_chars) as usize,
+            stack_chars as usize + 1,
+        )
+    }
+
+    pub fn stack_chars(&self) -> u16 {
+        self.stack_chars
+    }
+
+    pub fn acceptance(&self) -> Acceptance {
+        self.acceptance
+    }
+
+    pub fn accept_states(&self) -> &HashSet<u16> {
+        &self.accept_states
+    }
+
+    /// The transition table in the layout described by [`Pda::build`]
+    pub fn transition_table(&self) -> &[HashSet<(u16, Vec<u16>)>] {
+        &self.transition_table
+    }
+
+    pub fn configuration_budget(&self) -> usize {
+        self.configuration_budget
+    }
+
+    /// Sets the number of configurations explored before [`Pda::run`] gives up
+    pub fn set_configuration_budget(&mut self, configuration_budget: usize) {
+        self.configuration_budget = configuration_budget;
+    }
+
+    /// The start state with nothing read and only the initial stack symbol on the stack
+    pub fn initial_configuration(&self) -> PdaConfiguration {
+        PdaConfiguration {
+            state: 0,
+            position: 0,
+            stack: vec![0],
+        }
+    }
+
+    /// Checks if the machine accepts when it is in `configuration` after reading all of `input`
+    pub fn is_accepting(&self, configuration: &PdaConfiguration, input: &[u16]) -> bool {
+        configuration.position == input.len()
+            && match self.acceptance {
+                Acceptance::FinalState => self.accept_states.contains(&configuration.state),
+                Acceptance::EmptyStack => configuration.stack.is_empty(),
+            }
+    }
+
+    /// Every configuration that can be reached from `configuration` in one transition, epsilon
+    /// transitions included
+    pub fn next_configurations(
+        &self,
+        configuration: &PdaConfiguration,
+        input: &[u16],
+    ) -> Vec<PdaConfiguration> {
+        let PdaConfiguration {
+            state,
+            position,
+            ref stack,
+        } = *configuration;
+        let chars = input
+            .get(position)
+            .map(|&char| Some(char))
+            .into_iter()
+            .chain([None]);
+        let pops: Vec<_> = stack
+            .last()
+            .map(|&top| Some(top))
+            .into_iter()
+            .chain([None])
+            .collect();
+
+        let mut next = vec![];
+        for char in chars {
+            for &pop in &pops {
+                let cell = &self.transition_table
+                    [Self::lookup(state, char, pop, self.chars, self.stack_chars)];
+                for (end, push) in cell {
+                    let mut stack = stack.clone();
+                    if pop.is_some() {
+                        stack.pop();
+                    }
+                    stack.extend(push.iter().rev());
+                    next.push(PdaConfiguration {
+                        state: *end,
+                        position: position + char.is_some() as usize,
+                        stack,
+                    });
+                }
+            }
+        }
+        next
+    }
+
+    /// Runs the machine on `input` and reports if it accepts, rejects or ran out of
+    /// configurations before finding out
+    pub fn run(&self, input: &[u16]) -> Result<Verdict, MachineError> {
+        validate_input(input, self.chars)?;
+        Ok(self.search(input).0)
+    }
+
+    /// The configurations along an accepting computation on `input`. If the input is not accepted
+    /// the computation that read the most input is returned instead.
+    pub fn trace(&self, input: &[u16]) -> Result<Vec<PdaConfiguration>, MachineError> {
+        validate_input(input, self.chars)?;
+        Ok(self.search(input).1)
+    }
+
+    /// Breadth first search over the configurations of the machine, remembering where each one
+    /// was reached from so the path to it can be rebuilt
+    fn search(&self, input: &[u16]) -> (Verdict, Vec<PdaConfiguration>) {
+        let start = self.initial_configuration();
+        let mut seen = HashSet::from([start.clone()]);
+        let mut explored: Vec<(PdaConfiguration, Option<usize>)> = vec![(start, None)];
+        let mut furthest = 0;
+        let mut q = VecDeque::from([0]);
+
+        let verdict = loop {
+            let Some(index) = q.pop_front() else {
+                break Verdict::Reject;
+            };
+            let configuration = &explored[index].0;
+            if self.is_accepting(configuration, input) {
+                furthest = index;
+                break Verdict::Accept;
+            }
+            if configuration.position > explored[furthest].0.position {
+                furthest = index;
+            }
+            if explored.len() >= self.configuration_budget {
+                break Verdict::BudgetExhausted;
+            }
+
+            for next in self.next_configurations(configuration, input) {
+                if seen.insert(next.clone()) {
+                    explored.push((next, Some(index)));
+                    q.push_back(explored.len() - 1);
+                }
+            }
+        };
+
+        let mut path = vec![];
+        let mut cur = Some(furthest);
+        while let Some(index) = cur {
+            path.push(explored[index].0.clone());
+            cur = explored[index].1;
+        }
+        path.reverse();
+        (verdict, path)
+    }
+}
+
+impl StateMachine for Pda {
+    /// Checks if an accepting configuration is found. Returns [`MachineError::BudgetExhausted`]
+    /// if the configuration budget runs out first, as the input may still be accepted.
+    fn accepts(&self, input: &[u16]) -> Result<bool, MachineError> {
+        self.run(input)?.accepted()
+    }
+
+    /// Accepts when an accepting configuration is found within the configuration budget
+    fn accepts_validated(&self, input: &[u16]) -> bool {
+        self.search(input).0 == Verdict::Accept
+    }
+
+    /// The states of the computation returned by [`Pda::trace`]. The input tape moves right when
+    /// a character is read and stays put on epsilon transitions.
+    fn trace_states_validated(&self, input: &[u16]) -> Vec<(u16, Vec<TapeMovement>)> {
+        let path = self.search(input).1;
+        let mut prev_position = 0;
+        path.into_iter()
+            .map(|configuration| {
+                let movement = if configuration.position > prev_position {
+                    TapeMovement::Right(None)
+                } else {
+                    TapeMovement::Stay(None)
+                };
+                prev_position = configuration.position;
+                (configuration.state, vec![movement])
+            })
+            .collect()
+    }
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn chars(&self) -> u16 {
+        self.chars
+    }
+}
+
+/// # Pda Frontier
+///
+/// The configuration of a `Pda` in a [`Simulation`](crate::Simulation): every configuration
+/// reached by taking the same number of transitions that has not been reached sooner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdaFrontier {
+    pub active: BTreeSet<PdaConfiguration>,
+    pub seen: BTreeSet<PdaConfiguration>,
+}
+
+impl Simulate for Pda {
+    type Configuration = PdaFrontier;
+
+    fn start_configuration(&self, _input: &[u16]) -> PdaFrontier {
+        let start = BTreeSet::from([self.initial_configuration()]);
+        PdaFrontier {
+            active: start.clone(),
+            seen: start,
+        }
+    }
+
+    /// Takes every possible transition at once. The machine halts when a configuration accepts,
+    /// when there is nothing left to explore or when the configuration budget runs out.
+    fn next_configuration(&self, frontier: &PdaFrontier, input: &[u16]) -> Option<PdaFrontier> {
+        if frontier.active.is_empty()
+            || frontier.seen.len() >= self.configuration_budget
+            || self.is_accepting_configuration(frontier, input)
+        {
+            return None;
+        }
+
+        let mut seen = frontier.seen.clone();
+        let active = frontier
+            .active
+            .iter()
+            .flat_map(|configuration| self.next_configurations(configuration, input))
+            .filter(|next| seen.insert(next.clone()))
+            .collect();
+        Some(PdaFrontier { active, seen })
+    }
+
+    fn is_accepting_configuration(&self, frontier: &PdaFrontier, input: &[u16]) -> bool {
+        frontier
+            .active
+            .iter()
+            .any(|configuration| self.is_accepting(configuration, input))
+    }
+}
+
+#[cfg(test)]
+mod pda_tests {
+    use super::*;
+    use crate::{machine_utils::all_inputs, Simulation};
+
+    fn transition(
+        start: u16,
+        end: u16,
+        char: Option<u16>,
+        pop: Option<u16>,
+        push: &[u16],
+    ) -> PdaTransition {
+        PdaTransition {
+            start,
+            end,
+            char,
+            pop,
+            push: push.to_vec(),
+        }
+    }
+
+    // Accepts 0^n 1^n. The initial stack symbol 0 marks the bottom of the stack and 1 counts the
+    // 0s read
+    fn zeros_then_ones(acceptance: Acceptance) -> Pda {
+        Pda::from_transitions(
+            [
+                transition(0, 1, None, None, &[]),
+                transition(1, 1, Some(0), None, &[1]),
+                transition(1, 2, Some(1), Some(1), &[]),
+                transition(2, 2, Some(1), Some(1), &[]),
+                transition(1, 3, None, Some(0), &[]),
+                transition(2, 3, None, Some(0), &[]),
+            ],
+            HashSet::from([3]),
+            4,
+            2,
+            2,
+            acceptance,
+        )
+        .unwrap()
+    }
+
+    // Accepts even length palindromes, guessing the middle with an epsilon transition. Stack
+    // symbols 1 and 2 stand for the characters 0 and 1 read in the first half.
+    fn even_palindromes() -> Pda {
+        Pda::from_transitions(
+            [
+                transition(0, 0, Some(0), None, &[1]),
+                transition(0, 0, Some(1), None, &[2]),
+                transition(0, 1, None, None, &[]),
+                transition(1, 1, Some(0), Some(1), &[]),
+                transition(1, 1, Some(1), Some(2), &[]),
+                transition(1, 1, None, Some(0), &[]),
+            ],
+            HashSet::new(),
+            2,
+            2,
+            3,
+            Acceptance::EmptyStack,
+        )
+        .unwrap()
+    }
+
+    fn is_zeros_then_ones(input: &[u16]) -> bool {
+        let zeros = input.iter().take_while(|&&c| c == 0).count();
+        zeros * 2 == input.len() && input[zeros..].iter().all(|&c| c == 1)
+    }
+
+    #[test]
+    fn zeros_then_ones_final_state() {
+        let pda = zeros_then_ones(Acceptance::FinalState);
+        for input in all_inputs(2, 8) {
+            assert_eq!(
+                pda.accepts(&input).unwrap(),
+                is_zeros_then_ones(&input),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn zeros_then_ones_empty_stack() {
+        let pda = zeros_then_ones(Acceptance::EmptyStack);
+        for input in all_inputs(2, 8) {
+            assert_eq!(
+                pda.accepts(&input).unwrap(),
+                is_zeros_then_ones(&input),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn palindromes() {
+        let pda = even_palindromes();
+        for input in all_inputs(2, 8) {
+            let reversed: Vec<u16> = input.iter().rev().copied().collect();
+            assert_eq!(
+                pda.accepts(&input).unwrap(),
+                input.len() % 2 == 0 && input == reversed,
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn push_first_symbol_on_top() {
+        let pda = Pda::from_transitions(
+            [transition(0, 0, Some(0), None, &[0, 1])],
+            HashSet::new(),
+            1,
+            1,
+            2,
+            Acceptance::EmptyStack,
+        )
+        .unwrap();
+        let trace = pda.trace(&[0]).unwrap();
+
+        assert_eq!(trace.last().unwrap().stack, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn trace_follows_accepting_path() {
+        let pda = zeros_then_ones(Acceptance::FinalState);
+        let trace = pda.trace(&[0, 1]).unwrap();
+
+        let states: Vec<_> = trace.iter().map(|c| (c.state, c.position)).collect();
+        assert_eq!(states, vec![(0, 0), (1, 0), (1, 1), (2, 2), (3, 2)]);
+        assert_eq!(trace[2].stack, vec![0, 1]);
+        assert_eq!(trace[4].stack, vec![]);
+
+        assert_eq!(
+            pda.trace_states(&[0, 1]).unwrap(),
+            vec![
+                (0, vec![TapeMovement::Stay(None)]),
+                (1, vec![TapeMovement::Stay(None)]),
+                (1, vec![TapeMovement::Right(None)]),
+                (2, vec![TapeMovement::Right(None)]),
+                (3, vec![TapeMovement::Stay(None)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_rejected_reads_furthest() {
+        let pda = zeros_then_ones(Acceptance::FinalState);
+        let trace = pda.trace(&[0, 1, 1]).unwrap();
+
+        assert_eq!(trace.last().unwrap().position, 2);
+        assert_eq!(pda.run(&[0, 1, 1]).unwrap(), Verdict::Reject);
+    }
+
+    #[test]
+    fn epsilon_push_loop_exhausts_budget() {
+        // Pushes forever without reading anything
+        let mut pda = Pda::from_transitions(
+            [transition(0, 0, None, None, &[0])],
+            HashSet::from([1]),
+            2,
+            1,
+            1,
+            Acceptance::FinalState,
+        )
+        .unwrap();
+        pda.set_configuration_budget(1000);
+
+        assert_eq!(pda.run(&[0]).unwrap(), Verdict::BudgetExhausted);
+        assert_eq!(pda.accepts(&[0]), Err(MachineError::BudgetExhausted));
+        assert!(!pda.accepts_validated(&[0]));
+    }
+
+    #[test]
+    fn epsilon_cycle_without_push_rejects() {
+        let pda = Pda::from_transitions(
+            [
+                transition(0, 1, None, None, &[]),
+                transition(1, 0, None, None, &[]),
+            ],
+            HashSet::new(),
+            2,
+            1,
+            1,
+            Acceptance::FinalState,
+        )
+        .unwrap();
+
+        assert_eq!(pda.run(&[0]).unwrap(), Verdict::Reject);
+    }
+
+    #[test]
+    fn simulation_matches_accepts() {
+        let pda = even_palindromes();
+        for input in all_inputs(2, 6) {
+            let mut simulation = Simulation::new(&pda, &input).unwrap();
+            simulation.run_to_end();
+            assert_eq!(
+                simulation.is_accepting(),
+                pda.accepts(&input).unwrap(),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn empty_stack_needs_initial_symbol_popped() {
+        // Only accepts once the initial stack symbol is popped after reading a 0
+        let pda = Pda::from_transitions(
+            [transition(0, 0, Some(0), Some(0), &[])],
+            HashSet::new(),
+            1,
+            1,
+            1,
+            Acceptance::EmptyStack,
+        )
+        .unwrap();
+
+        assert_eq!(pda.initial_configuration().stack, vec![0]);
+        assert!(!pda.accepts(&[]).unwrap());
+        assert!(pda.accepts(&[0]).unwrap());
+        assert!(!pda.accepts(&[0, 0]).unwrap());
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            Pda::build(vec![], HashSet::new(), 1, 1, 0, Acceptance::EmptyStack).unwrap_err(),
+            MachineError::NoStackChars
+        );
+        assert_eq!(
+            Pda::build(vec![], HashSet::new(), 1, 1, 1, Acceptance::FinalState).unwrap_err(),
+            MachineError::WrongTableLength {
+                expected: 4,
+                actual: 0
+            }
+        );
+        assert_eq!(
+            Pda::from_transitions(
+                [transition(0, 0, None, None, &[2])],
+                HashSet::new(),
+                1,
+                1,
+                2,
+                Acceptance::FinalState,
+            )
+            .unwrap_err(),
+            MachineError::StackCharOutOfRange {
+                char: 2,
+                stack_chars: 2
+            }
+        );
+        assert_eq!(
+            Pda::from_transitions(
+                [transition(0, 3, Some(0), None, &[])],
+                HashSet::new(),
+                1,
+                1,
+                1,
+                Acceptance::FinalState,
+            )
+            .unwrap_err(),
+            MachineError::InvalidTransitionTarget {
+                index: 1,
+                state: 3,
+                states: 1
+            }
+        );
+        assert_eq!(
+            Pda::from_transitions(
+                [transition(0, 0, Some(1), None, &[])],
+                HashSet::new(),
+                1,
+                1,
+                1,
+                Acceptance::FinalState,
+            )
+            .unwrap_err(),
+            MachineError::CharOutOfRange { char: 1, chars: 1 }
+        );
+    }
+}
@@ -0,0 +1,652 @@
+//! Regular expressions over a machine's alphabet
+
+use crate::{e_nfa::EpsilonNfa, machine_utils::table_lookup, MachineError};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display},
+    iter,
+};
+
+mod state_elimination;
+
+pub use state_elimination::EliminationOrder;
+
+/// Symbol used for the empty string in patterns
+pub const EPSILON: char = 'ε';
+/// Symbol used for the empty language in patterns
+pub const EMPTY_SET: char = '∅';
+
+/// # Regex
+///
+/// The syntax tree of a regular expression. Characters are stored as indexes into the alphabet
+/// the expression was parsed with, the same `u16` characters used by every `StateMachine`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Regex {
+    /// Matches nothing, written `∅`
+    Empty,
+    /// Matches only the empty string, written `ε`
+    Epsilon,
+    /// Matches a single character
+    Char(u16),
+    /// Matches the first expression followed by the second
+    Concat(Box<Regex>, Box<Regex>),
+    /// Matches either expression, written `a|b`
+    Union(Box<Regex>, Box<Regex>),
+    /// Matches zero or more repetitions, written `a*`
+    Star(Box<Regex>),
+    /// Matches one or more repetitions, written `a+`
+    Plus(Box<Regex>),
+    /// Matches zero or one repetitions, written `a?`
+    Optional(Box<Regex>),
+}
+
+/// # Regex Error
+///
+/// Why a pattern could not be parsed. `position` is the index of the offending character in the
+/// pattern, counted in characters rather than bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegexError {
+    pub position: usize,
+    pub kind: RegexErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegexErrorKind {
+    /// The character is neither an operator nor part of the alphabet
+    UnknownChar(char),
+    /// The character is an operator that cannot appear here, like a leading `*` or a stray `)`
+    UnexpectedChar(char),
+    /// The `(` at this position is never closed
+    UnclosedGroup,
+    /// The pattern ended where an expression was expected
+    UnexpectedEnd,
+}
+
+impl Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self.position;
+        match self.kind {
+            RegexErrorKind::UnknownChar(c) => write!(
+                f,
+                "'{c}' at position {position} is not an operator or part of the alphabet"
+            ),
+            RegexErrorKind::UnexpectedChar(c) => {
+                write!(f, "unexpected '{c}' at position {position}")
+            }
+            RegexErrorKind::UnclosedGroup => {
+                write!(f, "the group opened at position {position} is never closed")
+            }
+            RegexErrorKind::UnexpectedEnd => {
+                write!(f, "expected an expression at position {position}")
+            }
+        }
+    }
+}
+
+impl Error for RegexError {}
+
+impl Regex {
+    /// Parses a pattern where every character of `alphabet` stands for its index in the alphabet.
+    ///
+    /// Supported syntax, from loosest to tightest binding: union `a|b`, concatenation `ab`, the
+    /// postfix operators `a*`, `a+` and `a?`, and grouping with `(a)`. `ε` matches the empty
+    /// string and `∅` matches nothing. Whitespace is ignored, and operators take priority over
+    /// alphabet characters with the same symbol.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::regex::Regex;
+    ///
+    /// let regex = Regex::parse("(a|b)*abb", &['a', 'b']).unwrap();
+    /// let e_nfa = regex.to_epsilon_nfa(2).unwrap();
+    /// ```
+    pub fn parse(pattern: &str, alphabet: &[char]) -> Result<Regex, RegexError> {
+        let mut parser = Parser {
+            pattern: pattern
+                .chars()
+                .enumerate()
+                .filter(|(_, c)| !c.is_whitespace())
+                .collect(),
+            pos: 0,
+            alphabet,
+        };
+        let regex = parser.union()?;
+        match parser.peek() {
+            None => Ok(regex),
+            Some((position, c)) => Err(RegexError {
+                position,
+                kind: RegexErrorKind::UnexpectedChar(c),
+            }),
+        }
+    }
+
+    /// Builds an `EpsilonNfa` over `chars` characters accepting the language of this expression
+    /// using Thompson's construction. Every sub expression gets its own start and accept state,
+    /// joined to the others only through epsilon transitions.
+    pub fn to_epsilon_nfa(&self, chars: u16) -> Result<EpsilonNfa, MachineError> {
+        if chars == 0 {
+            return Err(MachineError::NoChars);
+        }
+        let mut thompson = Thompson {
+            transition_table: vec![],
+            chars,
+            states: 0,
+        };
+        let (start, accept) = thompson.fragment(self)?;
+        // Every fragment adds its start state first, so the outermost start is already state 0
+        debug_assert_eq!(start, 0);
+
+        EpsilonNfa::build(
+            thompson.transition_table,
+            HashSet::from([accept]),
+            thompson.states,
+            chars,
+        )
+    }
+}
+
+impl Regex {
+    /// Joins two expressions with `|`, simplifying away `∅` and duplicates and writing a union
+    /// with `ε` using `?` or `*`
+    pub fn union(first: Regex, second: Regex) -> Regex {
+        match (first, second) {
+            (Regex::Empty, other) | (other, Regex::Empty) => other,
+            (first, second) if first == second => first,
+            (Regex::Epsilon, other) | (other, Regex::Epsilon) => match other {
+                other if other.is_nullable() => other,
+                Regex::Plus(inner) => Regex::Star(inner),
+                other => Regex::Optional(Box::new(other)),
+            },
+            (first, second) => Regex::Union(Box::new(first), Box::new(second)),
+        }
+    }
+
+    /// Joins two expressions one after the other, simplifying away `∅` and `ε` and writing
+    /// `aa*` and `a*a` as `a+`
+    pub fn concat(first: Regex, second: Regex) -> Regex {
+        match (first, second) {
+            (Regex::Empty, _) | (_, Regex::Empty) => Regex::Empty,
+            (Regex::Epsilon, other) | (other, Regex::Epsilon) => other,
+            (first, Regex::Star(inner)) if first == *inner => Regex::Plus(inner),
+            (Regex::Star(inner), second) if second == *inner => Regex::Plus(inner),
+            (first, second) => Regex::Concat(Box::new(first), Box::new(second)),
+        }
+    }
+
+    /// Repeats an expression zero or more times, removing nested repetition
+    pub fn star(regex: Regex) -> Regex {
+        match regex {
+            Regex::Empty | Regex::Epsilon => Regex::Epsilon,
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => Regex::Star(inner),
+            other => Regex::Star(Box::new(other)),
+        }
+    }
+
+    /// Checks if the expression matches the empty string
+    pub fn is_nullable(&self) -> bool {
+        match self {
+            Regex::Empty | Regex::Char(_) => false,
+            Regex::Epsilon | Regex::Star(_) | Regex::Optional(_) => true,
+            Regex::Concat(first, second) => first.is_nullable() && second.is_nullable(),
+            Regex::Union(first, second) => first.is_nullable() || second.is_nullable(),
+            Regex::Plus(inner) => inner.is_nullable(),
+        }
+    }
+
+    /// The number of nodes in the syntax tree
+    pub fn size(&self) -> usize {
+        match self {
+            Regex::Empty | Regex::Epsilon | Regex::Char(_) => 1,
+            Regex::Concat(first, second) | Regex::Union(first, second) => {
+                1 + first.size() + second.size()
+            }
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => 1 + inner.size(),
+        }
+    }
+
+    /// Writes the expression in the syntax accepted by [`Regex::parse`], using the character at
+    /// each index of `alphabet` and only the parentheses that are needed.
+    pub fn to_pattern(&self, alphabet: &[char]) -> Result<String, MachineError> {
+        let mut pattern = String::new();
+        self.write_pattern(alphabet, &mut pattern)?;
+        Ok(pattern)
+    }
+
+    fn write_pattern(&self, alphabet: &[char], pattern: &mut String) -> Result<(), MachineError> {
+        match self {
+            Regex::Empty => pattern.push(EMPTY_SET),
+            Regex::Epsilon => pattern.push(EPSILON),
+            &Regex::Char(c) => pattern.push(*alphabet.get(c as usize).ok_or(
+                MachineError::CharOutOfRange {
+                    char: c,
+                    chars: alphabet.len() as u16,
+                },
+            )?),
+            Regex::Union(first, second) => {
+                first.write_pattern(alphabet, pattern)?;
+                pattern.push('|');
+                second.write_pattern(alphabet, pattern)?;
+            }
+            Regex::Concat(first, second) => {
+                for child in [first, second] {
+                    child.write_grouped(matches!(**child, Regex::Union(..)), alphabet, pattern)?;
+                }
+            }
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                let group = matches!(**inner, Regex::Union(..) | Regex::Concat(..));
+                inner.write_grouped(group, alphabet, pattern)?;
+                pattern.push(match self {
+                    Regex::Star(_) => '*',
+                    Regex::Plus(_) => '+',
+                    _ => '?',
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn write_grouped(
+        &self,
+        group: bool,
+        alphabet: &[char],
+        pattern: &mut String,
+    ) -> Result<(), MachineError> {
+        if group {
+            pattern.push('(');
+        }
+        self.write_pattern(alphabet, pattern)?;
+        if group {
+            pattern.push(')');
+        }
+        Ok(())
+    }
+}
+
+struct Parser<'a> {
+    /// The non-whitespace characters of the pattern along with their position in the pattern
+    pattern: Vec<(usize, char)>,
+    pos: usize,
+    alphabet: &'a [char],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<(usize, char)> {
+        self.pattern.get(self.pos).copied()
+    }
+
+    fn end_position(&self) -> usize {
+        self.pattern.last().map_or(0, |&(position, _)| position + 1)
+    }
+
+    fn union(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.concat()?;
+        while let Some((_, '|')) = self.peek() {
+            self.pos += 1;
+            regex = Regex::Union(Box::new(regex), Box::new(self.concat()?));
+        }
+        Ok(regex)
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.postfix()?;
+        while let Some((_, c)) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            regex = Regex::Concat(Box::new(regex), Box::new(self.postfix()?));
+        }
+        Ok(regex)
+    }
+
+    fn postfix(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.atom()?;
+        while let Some((_, c)) = self.peek() {
+            regex = match c {
+                '*' => Regex::Star(Box::new(regex)),
+                '+' => Regex::Plus(Box::new(regex)),
+                '?' => Regex::Optional(Box::new(regex)),
+                _ => break,
+            };
+            self.pos += 1;
+        }
+        Ok(regex)
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let Some((position, c)) = self.peek() else {
+            return Err(RegexError {
+                position: self.end_position(),
+                kind: RegexErrorKind::UnexpectedEnd,
+            });
+        };
+        let unexpected = RegexError {
+            position,
+            kind: RegexErrorKind::UnexpectedChar(c),
+        };
+
+        let regex = match c {
+            '(' => {
+                self.pos += 1;
+                let inner = self.union()?;
+                match self.peek() {
+                    Some((_, ')')) => inner,
+                    Some((position, c)) => {
+                        return Err(RegexError {
+                            position,
+                            kind: RegexErrorKind::UnexpectedChar(c),
+                        })
+                    }
+                    None => {
+                        return Err(RegexError {
+                            position,
+                            kind: RegexErrorKind::UnclosedGroup,
+                        })
+                    }
+                }
+            }
+            ')' | '|' | '*' | '+' | '?' => return Err(unexpected),
+            EPSILON => Regex::Epsilon,
+            EMPTY_SET => Regex::Empty,
+            c => match self.alphabet.iter().position(|&a| a == c) {
+                Some(index) => Regex::Char(index as u16),
+                None => {
+                    return Err(RegexError {
+                        position,
+                        kind: RegexErrorKind::UnknownChar(c),
+                    })
+                }
+            },
+        };
+        self.pos += 1;
+        Ok(regex)
+    }
+}
+
+struct Thompson {
+    /// Transition table in the `EpsilonNfa` layout, growing by one row per added state
+    transition_table: Vec<HashSet<u16>>,
+    chars: u16,
+    states: u16,
+}
+
+impl Thompson {
+    fn add_state(&mut self) -> Result<u16, MachineError> {
+        let state = self.states;
+        self.states = self
+            .states
+            .checked_add(1)
+            .ok_or(MachineError::TooManyStates)?;
+        self.transition_table
+            .extend(iter::repeat_n(HashSet::new(), self.chars as usize + 1));
+        Ok(state)
+    }
+
+    fn add_transition(&mut self, start: u16, char: u16, end: u16) {
+        self.transition_table[table_lookup(start as usize, char as usize, self.chars as usize + 1)]
+            .insert(end);
+    }
+
+    fn add_epsilon(&mut self, start: u16, end: u16) {
+        self.add_transition(start, self.chars, end);
+    }
+
+    /// Adds the states for `regex` and returns its start and accept state
+    fn fragment(&mut self, regex: &Regex) -> Result<(u16, u16), MachineError> {
+        let start = self.add_state()?;
+        let accept = match regex {
+            Regex::Empty => self.add_state()?,
+            Regex::Epsilon => {
+                let accept = self.add_state()?;
+                self.add_epsilon(start, accept);
+                accept
+            }
+            &Regex::Char(c) => {
+                if c >= self.chars {
+                    return Err(MachineError::CharOutOfRange {
+                        char: c,
+                        chars: self.chars,
+                    });
+                }
+                let accept = self.add_state()?;
+                self.add_transition(start, c, accept);
+                accept
+            }
+            Regex::Concat(first, second) => {
+                let (first_start, first_accept) = self.fragment(first)?;
+                let (second_start, second_accept) = self.fragment(second)?;
+                self.add_epsilon(start, first_start);
+                self.add_epsilon(first_accept, second_start);
+                second_accept
+            }
+            Regex::Union(first, second) => {
+                let (first_start, first_accept) = self.fragment(first)?;
+                let (second_start, second_accept) = self.fragment(second)?;
+                let accept = self.add_state()?;
+                self.add_epsilon(start, first_start);
+                self.add_epsilon(start, second_start);
+                self.add_epsilon(first_accept, accept);
+                self.add_epsilon(second_accept, accept);
+                accept
+            }
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                let (inner_start, inner_accept) = self.fragment(inner)?;
+                let accept = self.add_state()?;
+                self.add_epsilon(start, inner_start);
+                self.add_epsilon(inner_accept, accept);
+                if !matches!(regex, Regex::Plus(_)) {
+                    self.add_epsilon(start, accept);
+                }
+                if !matches!(regex, Regex::Optional(_)) {
+                    self.add_epsilon(inner_accept, inner_start);
+                }
+                accept
+            }
+        };
+        Ok((start, accept))
+    }
+}
+
+#[cfg(test)]
+mod regex_tests {
+    use super::{Regex, RegexError, RegexErrorKind};
+    use crate::{
+        dfa::Dfa, equivalent, machine_utils::all_inputs, Equivalence, MachineError, StateMachine,
+    };
+    use std::collections::HashSet;
+
+    fn char(c: u16) -> Box<Regex> {
+        Box::new(Regex::Char(c))
+    }
+
+    #[test]
+    fn parse_precedence() {
+        assert_eq!(
+            Regex::parse("a|bc*", &['a', 'b', 'c']),
+            Ok(Regex::Union(
+                char(0),
+                Box::new(Regex::Concat(char(1), Box::new(Regex::Star(char(2)))))
+            ))
+        );
+        assert_eq!(
+            Regex::parse("(a|b)+?", &['a', 'b']),
+            Ok(Regex::Optional(Box::new(Regex::Plus(Box::new(
+                Regex::Union(char(0), char(1))
+            )))))
+        );
+        assert_eq!(
+            Regex::parse(" a b c ", &['a', 'b', 'c']),
+            Ok(Regex::Concat(
+                Box::new(Regex::Concat(char(0), char(1))),
+                char(2)
+            ))
+        );
+        assert_eq!(
+            Regex::parse("ε|∅", &['a']),
+            Ok(Regex::Union(
+                Box::new(Regex::Epsilon),
+                Box::new(Regex::Empty)
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_errors_have_positions() {
+        let error = |position, kind| Err(RegexError { position, kind });
+
+        assert_eq!(
+            Regex::parse("ab(a|c)", &['a', 'b']),
+            error(5, RegexErrorKind::UnknownChar('c'))
+        );
+        assert_eq!(
+            Regex::parse("a(b|a", &['a', 'b']),
+            error(1, RegexErrorKind::UnclosedGroup)
+        );
+        assert_eq!(
+            Regex::parse("a|*", &['a']),
+            error(2, RegexErrorKind::UnexpectedChar('*'))
+        );
+        assert_eq!(
+            Regex::parse("a)", &['a']),
+            error(1, RegexErrorKind::UnexpectedChar(')'))
+        );
+        assert_eq!(
+            Regex::parse("a|", &['a']),
+            error(2, RegexErrorKind::UnexpectedEnd)
+        );
+        assert_eq!(
+            Regex::parse("", &['a']),
+            error(0, RegexErrorKind::UnexpectedEnd)
+        );
+        assert_eq!(
+            Regex::parse("()", &['a']),
+            error(1, RegexErrorKind::UnexpectedChar(')'))
+        );
+        // Positions count characters, not bytes
+        assert_eq!(
+            Regex::parse("εε x", &['a']),
+            error(3, RegexErrorKind::UnknownChar('x'))
+        );
+    }
+
+    #[test]
+    fn thompson_matches_dfa() {
+        let e_nfa = Regex::parse("(a|b)*abb", &['a', 'b'])
+            .unwrap()
+            .to_epsilon_nfa(2)
+            .unwrap();
+        // The textbook minimal Dfa for (a|b)*abb
+        let dfa = Dfa::build(vec![1, 0, 1, 2, 1, 3, 1, 0], HashSet::from([3]), 4, 2).unwrap();
+
+        assert_eq!(equivalent(&e_nfa, &dfa), Ok(Equivalence::Equivalent));
+        assert!(e_nfa.accepts(&[1, 0, 1, 1]).unwrap());
+        assert!(!e_nfa.accepts(&[0, 1, 1, 0]).unwrap());
+    }
+
+    #[test]
+    fn thompson_operators() {
+        let alphabet = ['a', 'b'];
+        type Expected = fn(&[u16]) -> bool;
+        let cases: [(&str, Expected); 8] = [
+            ("∅", |_| false),
+            ("ε", |input| input.is_empty()),
+            ("a", |input| input == [0]),
+            ("a+b", |input| {
+                input.len() >= 2
+                    && input[input.len() - 1] == 1
+                    && input[..input.len() - 1].iter().all(|&c| c == 0)
+            }),
+            ("a?b?", |input| {
+                [&[][..], &[0], &[1], &[0, 1]].contains(&input)
+            }),
+            ("(ab)*", |input| {
+                input.len() % 2 == 0 && input.chunks(2).all(|chunk| chunk == [0, 1])
+            }),
+            ("a∅|b", |input| input == [1]),
+            ("(a|ε)(b|ε)*", |input| {
+                input.iter().skip(1).all(|&c| c == 1)
+            }),
+        ];
+
+        for (pattern, expected) in cases {
+            let e_nfa = Regex::parse(pattern, &alphabet)
+                .unwrap()
+                .to_epsilon_nfa(2)
+                .unwrap();
+            for input in all_inputs(2, 6) {
+                assert_eq!(
+                    e_nfa.accepts(&input).unwrap(),
+                    expected(&input),
+                    "{pattern} {input:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_round_trip() {
+        let alphabet = ['a', 'b', 'c'];
+        let patterns = [
+            "a|bc*",
+            "(a|b)*abb",
+            "(ab)+c?",
+            "a(b|c)",
+            "(a|ε)*",
+            "ε|∅",
+            "a**",
+        ];
+
+        for pattern in patterns {
+            let regex = Regex::parse(pattern, &alphabet).unwrap();
+            assert_eq!(regex.to_pattern(&alphabet).unwrap(), pattern);
+        }
+    }
+
+    #[test]
+    fn simplifying_constructors() {
+        let a = || Regex::Char(0);
+
+        assert_eq!(Regex::union(Regex::Empty, a()), a());
+        assert_eq!(Regex::union(a(), a()), a());
+        assert_eq!(
+            Regex::union(Regex::Epsilon, a()),
+            Regex::Optional(Box::new(a()))
+        );
+        assert_eq!(
+            Regex::union(Regex::Plus(Box::new(a())), Regex::Epsilon),
+            Regex::Star(Box::new(a()))
+        );
+        assert_eq!(
+            Regex::union(Regex::Epsilon, Regex::Star(Box::new(a()))),
+            Regex::Star(Box::new(a()))
+        );
+        assert_eq!(Regex::concat(a(), Regex::Empty), Regex::Empty);
+        assert_eq!(Regex::concat(Regex::Epsilon, a()), a());
+        assert_eq!(
+            Regex::concat(a(), Regex::Star(Box::new(a()))),
+            Regex::Plus(Box::new(a()))
+        );
+        assert_eq!(Regex::star(Regex::Empty), Regex::Epsilon);
+        assert_eq!(
+            Regex::star(Regex::Optional(Box::new(a()))),
+            Regex::Star(Box::new(a()))
+        );
+    }
+
+    #[test]
+    fn pattern_char_outside_alphabet() {
+        assert_eq!(
+            Regex::Char(1).to_pattern(&['a']),
+            Err(MachineError::CharOutOfRange { char: 1, chars: 1 })
+        );
+    }
+
+    #[test]
+    fn char_outside_alphabet() {
+        assert_eq!(
+            Regex::Char(3).to_epsilon_nfa(2).unwrap_err(),
+            MachineError::CharOutOfRange { char: 3, chars: 2 }
+        );
+    }
+}
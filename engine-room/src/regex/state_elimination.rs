@@ -0,0 +1,347 @@
+use super::Regex;
+use crate::{dfa::Dfa, e_nfa::EpsilonNfa, nfa::Nfa, MachineError, StateMachine};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// # Elimination Order
+///
+/// Chooses which state is removed next while converting a machine into a `Regex`. Every order
+/// produces an equivalent expression, but the size and readability of the result can differ a
+/// lot between them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EliminationOrder {
+    /// Removes the states from the lowest numbered to the highest
+    Ascending,
+    /// Removes the state with the fewest paths through it first, that is the smallest number of
+    /// incoming edges times outgoing edges
+    FewestPaths,
+    /// Removes the state whose removal adds the least to the total size of the expressions on the
+    /// edges first
+    SmallestExpressions,
+    /// Removes the states in the given order. Any states left out are removed afterwards in
+    /// ascending order.
+    Custom(Vec<u16>),
+}
+
+impl Regex {
+    /// Converts a `Dfa` into an equivalent `Regex` by state elimination. See
+    /// [`Regex::from_nfa`].
+    pub fn from_dfa(dfa: &Dfa, order: EliminationOrder) -> Result<Regex, MachineError> {
+        let chars = dfa.chars() as usize;
+        let edges = dfa
+            .transition_table()
+            .iter()
+            .enumerate()
+            .map(|(index, &end)| (index / chars, Some((index % chars) as u16), end));
+        Gnfa::new(dfa.states(), edges, dfa.accept_states().iter().copied()).eliminate(order)
+    }
+
+    /// Converts a `Nfa` into an equivalent `Regex` by state elimination. The machine is turned
+    /// into a generalized NFA whose edges are labeled with expressions, with a fresh start and
+    /// accept state, and then states are removed one at a time in the given `order`, rerouting
+    /// every path through a removed state `k` as `in k_loop* out`.
+    pub fn from_nfa(nfa: &Nfa, order: EliminationOrder) -> Result<Regex, MachineError> {
+        let chars = nfa.chars() as usize;
+        let edges = nfa
+            .transition_table()
+            .iter()
+            .enumerate()
+            .flat_map(|(index, ends)| {
+                ends.iter()
+                    .map(move |&end| (index / chars, Some((index % chars) as u16), end))
+            });
+        Gnfa::new(nfa.states(), edges, nfa.accept_states().iter().copied()).eliminate(order)
+    }
+
+    /// Converts a `EpsilonNfa` into an equivalent `Regex` by state elimination. Epsilon
+    /// transitions become `ε` edges. See [`Regex::from_nfa`].
+    pub fn from_epsilon_nfa(
+        e_nfa: &EpsilonNfa,
+        order: EliminationOrder,
+    ) -> Result<Regex, MachineError> {
+        let stride = e_nfa.chars() as usize + 1;
+        let chars = e_nfa.chars();
+        let edges = e_nfa
+            .transition_table()
+            .iter()
+            .enumerate()
+            .flat_map(|(index, ends)| {
+                let char = (index % stride) as u16;
+                let label = (char != chars).then_some(char);
+                ends.iter().map(move |&end| (index / stride, label, end))
+            });
+        Gnfa::new(e_nfa.states(), edges, e_nfa.accept_states().iter().copied()).eliminate(order)
+    }
+}
+
+/// Generalized NFA used for state elimination. States `0..states` are the states of the original
+/// machine, `states` is the new start state and `states + 1` the new accept state.
+struct Gnfa {
+    states: usize,
+    /// The expression on the edge between each pair of states, missing edges are `∅`
+    edges: BTreeMap<(usize, usize), Regex>,
+}
+
+impl Gnfa {
+    /// Builds the generalized NFA from `(start, char, end)` transitions, where a char of `None` is
+    /// an epsilon transition
+    fn new(
+        states: u16,
+        transitions: impl Iterator<Item = (usize, Option<u16>, u16)>,
+        accept_states: impl Iterator<Item = u16>,
+    ) -> Gnfa {
+        let mut gnfa = Gnfa {
+            states: states as usize,
+            edges: BTreeMap::new(),
+        };
+        for (start, char, end) in transitions {
+            let label = char.map_or(Regex::Epsilon, Regex::Char);
+            gnfa.add_edge(start, end as usize, label);
+        }
+        gnfa.add_edge(gnfa.states, 0, Regex::Epsilon);
+        for accept_state in accept_states {
+            gnfa.add_edge(accept_state as usize, gnfa.states + 1, Regex::Epsilon);
+        }
+        gnfa
+    }
+
+    fn add_edge(&mut self, start: usize, end: usize, regex: Regex) {
+        let edge = match self.edges.remove(&(start, end)) {
+            Some(existing) => Regex::union(existing, regex),
+            None => regex,
+        };
+        if edge != Regex::Empty {
+            self.edges.insert((start, end), edge);
+        }
+    }
+
+    /// The states with an edge into and out of `state`, not counting its self loop
+    fn neighbors(&self, state: usize) -> (Vec<usize>, Vec<usize>) {
+        let incoming = self
+            .edges
+            .keys()
+            .filter(|&&(start, end)| end == state && start != state)
+            .map(|&(start, _)| start)
+            .collect();
+        let outgoing = self
+            .edges
+            .keys()
+            .filter(|&&(start, end)| start == state && end != state)
+            .map(|&(_, end)| end)
+            .collect();
+        (incoming, outgoing)
+    }
+
+    /// How much the total size of the edge expressions grows by removing `state`
+    fn elimination_weight(&self, state: usize) -> isize {
+        let (incoming, outgoing) = self.neighbors(state);
+        let in_count = incoming.len() as isize;
+        let out_count = outgoing.len() as isize;
+        let loop_size = self
+            .edges
+            .get(&(state, state))
+            .map_or(0, |regex| regex.size() as isize);
+        let in_size: isize = incoming
+            .iter()
+            .map(|&start| self.edges[&(start, state)].size() as isize)
+            .sum();
+        let out_size: isize = outgoing
+            .iter()
+            .map(|&end| self.edges[&(state, end)].size() as isize)
+            .sum();
+
+        in_size * (out_count - 1)
+            + out_size * (in_count - 1)
+            + loop_size * (in_count * out_count - 1)
+    }
+
+    fn eliminate(mut self, order: EliminationOrder) -> Result<Regex, MachineError> {
+        let mut remaining: BTreeSet<usize> = (0..self.states).collect();
+        let mut custom = match order {
+            EliminationOrder::Custom(ref states) => {
+                if let Some(&state) = states.iter().find(|&&s| s as usize >= self.states) {
+                    return Err(MachineError::StateOutOfRange {
+                        state,
+                        states: self.states as u16,
+                    });
+                }
+                states.iter().map(|&s| s as usize).collect()
+            }
+            _ => vec![],
+        }
+        .into_iter();
+
+        while let Some(&first) = remaining.first() {
+            let state = match order {
+                EliminationOrder::Ascending => first,
+                EliminationOrder::FewestPaths => *remaining
+                    .iter()
+                    .min_by_key(|&&s| {
+                        let (incoming, outgoing) = self.neighbors(s);
+                        incoming.len() * outgoing.len()
+                    })
+                    .expect("remaining is not empty"),
+                EliminationOrder::SmallestExpressions => *remaining
+                    .iter()
+                    .min_by_key(|&&s| self.elimination_weight(s))
+                    .expect("remaining is not empty"),
+                EliminationOrder::Custom(_) => custom
+                    .by_ref()
+                    .find(|s| remaining.contains(s))
+                    .unwrap_or(first),
+            };
+            remaining.remove(&state);
+            self.remove_state(state);
+        }
+
+        Ok(self
+            .edges
+            .remove(&(self.states, self.states + 1))
+            .unwrap_or(Regex::Empty))
+    }
+
+    fn remove_state(&mut self, state: usize) {
+        let (incoming, outgoing) = self.neighbors(state);
+        let loop_star = self
+            .edges
+            .remove(&(state, state))
+            .map_or(Regex::Epsilon, Regex::star);
+
+        for &start in &incoming {
+            for &end in &outgoing {
+                let path = Regex::concat(
+                    Regex::concat(self.edges[&(start, state)].clone(), loop_star.clone()),
+                    self.edges[&(state, end)].clone(),
+                );
+                self.add_edge(start, end, path);
+            }
+        }
+        self.edges
+            .retain(|&(start, end), _| start != state && end != state);
+    }
+}
+
+#[cfg(test)]
+mod state_elimination_tests {
+    use super::EliminationOrder;
+    use crate::{
+        dfa::Dfa, e_nfa::EpsilonNfa, equivalent, machine_utils::ends_in_one, regex::Regex,
+        Equivalence, MachineError,
+    };
+    use std::collections::HashSet;
+
+    fn orders() -> [EliminationOrder; 4] {
+        [
+            EliminationOrder::Ascending,
+            EliminationOrder::FewestPaths,
+            EliminationOrder::SmallestExpressions,
+            EliminationOrder::Custom(vec![1, 0]),
+        ]
+    }
+
+    #[test]
+    fn odd_length_dfa() {
+        let dfa = Dfa::build(vec![1, 0], HashSet::from([1]), 2, 1).unwrap();
+        let regex = Regex::from_dfa(&dfa, EliminationOrder::Ascending).unwrap();
+
+        assert_eq!(regex.to_pattern(&['a']).unwrap(), "a(aa)*");
+    }
+
+    #[test]
+    fn no_accept_states() {
+        let dfa = Dfa::build(vec![1, 0], HashSet::new(), 2, 1).unwrap();
+
+        for order in orders() {
+            assert_eq!(Regex::from_dfa(&dfa, order), Ok(Regex::Empty));
+        }
+    }
+
+    #[test]
+    fn dfa_round_trip() {
+        // Accepts strings with a number of 1s divisible by 3
+        let dfa = Dfa::build(vec![0, 1, 1, 2, 2, 0], HashSet::from([0]), 3, 2).unwrap();
+
+        for order in orders() {
+            let regex = Regex::from_dfa(&dfa, order.clone()).unwrap();
+            let e_nfa = regex.to_epsilon_nfa(2).unwrap();
+            assert_eq!(
+                equivalent(&dfa, &e_nfa),
+                Ok(Equivalence::Equivalent),
+                "{order:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn nfa_round_trip() {
+        let nfa = ends_in_one();
+
+        for order in orders() {
+            let regex = Regex::from_nfa(&nfa, order.clone()).unwrap();
+            let e_nfa = regex.to_epsilon_nfa(2).unwrap();
+            assert_eq!(
+                equivalent(&nfa, &e_nfa),
+                Ok(Equivalence::Equivalent),
+                "{order:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn regex_round_trip() {
+        let alphabet = ['a', 'b', 'c'];
+        let patterns = ["(a|b)*abb", "a?b+c*", "(ab|ba)*c", "((a|ε)b)*|c∅", "ε", "∅"];
+
+        for pattern in patterns {
+            let e_nfa = Regex::parse(pattern, &alphabet)
+                .unwrap()
+                .to_epsilon_nfa(3)
+                .unwrap();
+            for order in orders() {
+                let regex = Regex::from_epsilon_nfa(&e_nfa, order.clone()).unwrap();
+                let reparsed = Regex::parse(&regex.to_pattern(&alphabet).unwrap(), &alphabet)
+                    .unwrap()
+                    .to_epsilon_nfa(3)
+                    .unwrap();
+                assert_eq!(
+                    equivalent(&e_nfa, &reparsed),
+                    Ok(Equivalence::Equivalent),
+                    "{pattern} {order:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn epsilon_edges() {
+        // 0 -ε-> 1 with 1 looping on a and accepting
+        let e_nfa = EpsilonNfa::build(
+            vec![
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::from([1]),
+                HashSet::new(),
+            ],
+            HashSet::from([1]),
+            2,
+            1,
+        )
+        .unwrap();
+        let regex = Regex::from_epsilon_nfa(&e_nfa, EliminationOrder::Ascending).unwrap();
+
+        assert_eq!(regex.to_pattern(&['a']).unwrap(), "a*");
+    }
+
+    #[test]
+    fn custom_order_out_of_range() {
+        let dfa = Dfa::build(vec![1, 0], HashSet::from([1]), 2, 1).unwrap();
+
+        assert_eq!(
+            Regex::from_dfa(&dfa, EliminationOrder::Custom(vec![0, 2])),
+            Err(MachineError::StateOutOfRange {
+                state: 2,
+                states: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Step by step simulation of a machine on a single input
+
+use crate::{machine_utils::validate_input, FiniteAutomaton, MachineError, StateMachine};
+use std::collections::BTreeSet;
+
+/// The number of steps a Turing machine takes before giving up, unless changed with
+/// `set_step_budget`
+pub const DEFAULT_STEP_BUDGET: usize = 100_000;
+
+/// The number of configurations a nondeterministic machine explores before giving up, unless
+/// changed with `set_configuration_budget`
+pub const DEFAULT_CONFIGURATION_BUDGET: usize = 100_000;
+
+/// # Simulate
+///
+/// A machine that can be run one step at a time. A configuration is everything needed to continue
+/// the computation from some point, such as the current state and how much of the input has been
+/// read.
+pub trait Simulate: StateMachine {
+    type Configuration: Clone;
+
+    /// The configuration of the machine before it has taken any steps on `input`
+    fn start_configuration(&self, input: &[u16]) -> Self::Configuration;
+
+    /// The configuration reached by taking one step from `configuration`, or `None` if the
+    /// machine has halted
+    fn next_configuration(
+        &self,
+        configuration: &Self::Configuration,
+        input: &[u16],
+    ) -> Option<Self::Configuration>;
+
+    /// Checks if the machine accepts when it halts in `configuration`
+    fn is_accepting_configuration(
+        &self,
+        configuration: &Self::Configuration,
+        input: &[u16],
+    ) -> bool;
+}
+
+/// # Finite Configuration
+///
+/// The configuration of a finite automaton: every state it could be in after reading the first
+/// `position` characters of the input. A `Dfa` is always in exactly one state, while a
+/// nondeterministic machine keeps the full set of active states instead of guessing one path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FiniteConfiguration {
+    pub states: BTreeSet<u16>,
+    pub position: usize,
+}
+
+impl<T: FiniteAutomaton> Simulate for T {
+    type Configuration = FiniteConfiguration;
+
+    fn start_configuration(&self, _input: &[u16]) -> FiniteConfiguration {
+        FiniteConfiguration {
+            states: self.start_states(),
+            position: 0,
+        }
+    }
+
+    /// Reads the next character. The machine halts once the input is consumed or when no state
+    /// is active anymore, since every branch has died.
+    fn next_configuration(
+        &self,
+        configuration: &FiniteConfiguration,
+        input: &[u16],
+    ) -> Option<FiniteConfiguration> {
+        if configuration.states.is_empty() {
+            return None;
+        }
+        let &char = input.get(configuration.position)?;
+        Some(FiniteConfiguration {
+            states: self.next_states(&configuration.states, char),
+            position: configuration.position + 1,
+        })
+    }
+
+    fn is_accepting_configuration(
+        &self,
+        configuration: &FiniteConfiguration,
+        input: &[u16],
+    ) -> bool {
+        configuration.position == input.len() && self.is_accepting(&configuration.states)
+    }
+}
+
+/// # Simulation
+///
+/// Runs a machine on an input one step at a time. Every configuration that has been reached is
+/// kept, so the simulation can be stepped backwards and then forwards again without recomputing
+/// anything.
+///
+/// # Examples
+///
+/// ```
+/// use engine_room::{dfa::Dfa, Simulation};
+/// use std::collections::{BTreeSet, HashSet};
+///
+/// // Odd number of 0s
+/// let dfa = Dfa::build(vec![1, 0], HashSet::from([1]), 2, 1).unwrap();
+/// let mut simulation = Simulation::new(&dfa, &[0, 0, 0]).unwrap();
+///
+/// simulation.step();
+/// assert_eq!(simulation.current_configuration().states, BTreeSet::from([1]));
+/// simulation.step_back();
+/// assert_eq!(simulation.current_configuration().position, 0);
+///
+/// simulation.run_to_end();
+/// assert!(simulation.is_halted());
+/// assert!(simulation.is_accepting());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Simulation<'a, M: Simulate> {
+    machine: &'a M,
+    input: Vec<u16>,
+    history: Vec<M::Configuration>,
+    cursor: usize,
+}
+
+impl<'a, M: Simulate> Simulation<'a, M> {
+    /// Starts a simulation of `machine` on `input`
+    pub fn new(machine: &'a M, input: &[u16]) -> Result<Self, MachineError> {
+        validate_input(input, machine.chars())?;
+        Ok(Simulation {
+            machine,
+            input: input.to_vec(),
+            history: vec![machine.start_configuration(input)],
+            cursor: 0,
+        })
+    }
+
+    /// Takes one step and returns the new configuration, or `None` if the machine has halted
+    pub fn step(&mut self) -> Option<&M::Configuration> {
+        if self.cursor + 1 == self.history.len() {
+            let next = self
+                .machine
+                .next_configuration(&self.history[self.cursor], &self.input)?;
+            self.history.push(next);
+        }
+        self.cursor += 1;
+        Some(&self.history[self.cursor])
+    }
+
+    /// Undoes one step and returns the previous configuration, or `None` if the simulation is
+    /// already at the start
+    pub fn step_back(&mut self) -> Option<&M::Configuration> {
+        self.cursor = self.cursor.checked_sub(1)?;
+        Some(&self.history[self.cursor])
+    }
+
+    /// Steps until the machine halts and returns the final configuration
+    pub fn run_to_end(&mut self) -> &M::Configuration {
+        while self.step().is_some() {}
+        self.current_configuration()
+    }
+
+    pub fn current_configuration(&self) -> &M::Configuration {
+        &self.history[self.cursor]
+    }
+
+    /// The number of steps taken to reach the current configuration
+    pub fn steps(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.cursor + 1 == self.history.len()
+            && self
+                .machine
+                .next_configuration(self.current_configuration(), &self.input)
+                .is_none()
+    }
+
+    /// Checks if the machine has halted and accepts the input
+    pub fn is_accepting(&self) -> bool {
+        self.is_halted()
+            && self
+                .machine
+                .is_accepting_configuration(self.current_configuration(), &self.input)
+    }
+
+    pub fn input(&self) -> &[u16] {
+        &self.input
+    }
+}
+
+#[cfg(test)]
+mod simulation_tests {
+    use super::*;
+    use crate::{
+        dfa::Dfa,
+        e_nfa::EpsilonNfa,
+        machine_utils::{all_inputs, ends_in_one},
+        nfa::Nfa,
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn dfa_single_state_configurations() {
+        // Odd number of 0s
+        let dfa = Dfa::build(vec![1, 0], HashSet::from([1]), 2, 1).unwrap();
+        let mut simulation = Simulation::new(&dfa, &[0, 0]).unwrap();
+
+        assert_eq!(
+            simulation.current_configuration(),
+            &FiniteConfiguration {
+                states: BTreeSet::from([0]),
+                position: 0
+            }
+        );
+        assert!(!simulation.is_halted());
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([1]));
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([0]));
+        assert_eq!(simulation.step(), None);
+        assert!(simulation.is_halted());
+        assert!(!simulation.is_accepting());
+        assert_eq!(simulation.steps(), 2);
+    }
+
+    #[test]
+    fn nfa_tracks_every_active_state() {
+        let nfa = ends_in_one();
+        let mut simulation = Simulation::new(&nfa, &[1, 0, 1]).unwrap();
+
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([0, 1]));
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([0]));
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([0, 1]));
+        assert!(simulation.is_accepting());
+    }
+
+    #[test]
+    fn dead_nfa_halts_early() {
+        // Accepts only the string 0
+        let nfa = Nfa::build(
+            vec![
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            ],
+            HashSet::from([1]),
+            2,
+            2,
+        )
+        .unwrap();
+        let mut simulation = Simulation::new(&nfa, &[1, 0, 0]).unwrap();
+
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::new());
+        assert!(simulation.is_halted());
+        assert_eq!(simulation.run_to_end().position, 1);
+        assert!(!simulation.is_accepting());
+    }
+
+    #[test]
+    fn epsilon_nfa_configurations_are_closed() {
+        // Accepts 0* 1*
+        let e_nfa = EpsilonNfa::build(
+            vec![
+                HashSet::from([0]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+                HashSet::from([1]),
+                HashSet::new(),
+            ],
+            HashSet::from([1]),
+            2,
+            2,
+        )
+        .unwrap();
+        let mut simulation = Simulation::new(&e_nfa, &[0, 1]).unwrap();
+
+        assert_eq!(
+            simulation.current_configuration().states,
+            BTreeSet::from([0, 1])
+        );
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([0, 1]));
+        assert_eq!(simulation.step().unwrap().states, BTreeSet::from([1]));
+        assert!(simulation.is_accepting());
+    }
+
+    #[test]
+    fn step_back_and_forward() {
+        let nfa = ends_in_one();
+        let mut simulation = Simulation::new(&nfa, &[1, 1, 0]).unwrap();
+
+        assert_eq!(simulation.step_back(), None);
+        let end = simulation.run_to_end().clone();
+        assert_eq!(simulation.step_back().unwrap().position, 2);
+        assert_eq!(simulation.step_back().unwrap().position, 1);
+        assert!(!simulation.is_halted());
+        assert_eq!(simulation.steps(), 1);
+        assert_eq!(simulation.run_to_end(), &end);
+        assert_eq!(simulation.steps(), 3);
+    }
+
+    #[test]
+    fn matches_accepts() {
+        let nfa = ends_in_one();
+        for input in all_inputs(2, 6) {
+            let mut simulation = Simulation::new(&nfa, &input).unwrap();
+            simulation.run_to_end();
+            assert_eq!(
+                simulation.is_accepting(),
+                nfa.accepts(&input).unwrap(),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            Simulation::new(&ends_in_one(), &[0, 2]).unwrap_err(),
+            MachineError::InvalidInputChar {
+                index: 1,
+                char: 2,
+                chars: 2
+            }
+        );
+    }
+}
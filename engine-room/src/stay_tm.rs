@@ -0,0 +1,413 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    tm::{table_from_transitions, Tm, TmConfiguration},
+    transitions::TmTransition,
+    MachineError, Simulate, StateMachine, TapeMovement, Verdict,
+};
+
+/// # Stay Turing Machine
+///
+/// A deterministic single tape Turing machine whose transitions may leave the head in place with
+/// [`TapeMovement::Stay`], in addition to moving it left or right. Otherwise it works exactly
+/// like a [`Tm`], which it can be converted into with [`StayTm::to_tm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StayTm {
+    tm: Tm,
+}
+
+impl StayTm {
+    /// Builds a stay Turing machine from a transition table in the layout described by
+    /// [`Tm::build`]
+    pub fn build(
+        transition_table: Vec<Option<(u16, TapeMovement)>>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+    ) -> Result<StayTm, MachineError> {
+        Ok(StayTm {
+            tm: Tm::build_with_stay(
+                transition_table,
+                accept_state,
+                reject_state,
+                states,
+                chars,
+                tape_chars,
+                true,
+            )?,
+        })
+    }
+
+    /// Builds a stay Turing machine from a list of transitions instead of a full transition
+    /// table. Later transitions replace earlier ones for the same state and symbol.
+    pub fn from_transitions(
+        transitions: impl IntoIterator<Item = TmTransition>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+    ) -> Result<StayTm, MachineError> {
+        StayTm::build(
+            table_from_transitions(transitions, states, tape_chars)?,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+        )
+    }
+
+    /// Builds a [`Tm`] that behaves the same, ending with the same tape and head position. Every
+    /// stay is replaced by a move right into a new state that then moves back left into the
+    /// original target. One new state is added for each state that is the target of a stay, in
+    /// increasing order of the target and numbered after the existing states.
+    ///
+    /// A stay takes two steps in the new machine, so the same run can take up to twice as many
+    /// steps. The returned `Tm` therefore gets twice this machine's step budget, so it accepts or
+    /// rejects every input this machine accepts or rejects within its budget. It may also decide
+    /// inputs this machine runs out of steps on. Step counts and budgets of the two machines do
+    /// not mean the same thing, so call [`Tm::set_step_budget`] on the result to limit it to a
+    /// number of `Tm` steps instead.
+    pub fn to_tm(&self) -> Result<Tm, MachineError> {
+        let tape_chars = self.tm.tape_chars();
+        let mut transition_table = self.tm.transition_table().to_vec();
+
+        // The new state that moves back left into each target of a stay
+        let mut step_back: BTreeMap<u16, u16> = transition_table
+            .iter()
+            .flatten()
+            .filter(|(_, movement)| matches!(movement, TapeMovement::Stay(_)))
+            .map(|&(state, _)| (state, 0))
+            .collect();
+        let states = u16::try_from(self.tm.states() as usize + step_back.len())
+            .map_err(|_| MachineError::TooManyStates)?;
+        for (new_state, target) in (self.tm.states()..).zip(step_back.values_mut()) {
+            *target = new_state;
+        }
+
+        for transition in transition_table.iter_mut().flatten() {
+            if let (state, TapeMovement::Stay(write)) = *transition {
+                *transition = (step_back[&state], TapeMovement::Right(write));
+            }
+        }
+        // The new states are numbered in the same order as their targets, so their rows can be
+        // added in that order
+        for &state in step_back.keys() {
+            transition_table.extend(std::iter::repeat_n(
+                Some((state, TapeMovement::Left(None))),
+                tape_chars as usize,
+            ));
+        }
+
+        let mut tm = Tm::build(
+            transition_table,
+            self.tm.accept_state(),
+            self.tm.reject_state(),
+            states,
+            self.tm.chars(),
+            tape_chars,
+        )?;
+        tm.set_step_budget(self.tm.step_budget().saturating_mul(2));
+        Ok(tm)
+    }
+
+    /// The blank symbol, which directly follows the input alphabet
+    pub fn blank(&self) -> u16 {
+        self.tm.blank()
+    }
+
+    pub fn tape_chars(&self) -> u16 {
+        self.tm.tape_chars()
+    }
+
+    pub fn accept_state(&self) -> u16 {
+        self.tm.accept_state()
+    }
+
+    pub fn reject_state(&self) -> u16 {
+        self.tm.reject_state()
+    }
+
+    /// The transition table in the layout described by [`Tm::build`]
+    pub fn transition_table(&self) -> &[Option<(u16, TapeMovement)>] {
+        self.tm.transition_table()
+    }
+
+    pub fn step_budget(&self) -> usize {
+        self.tm.step_budget()
+    }
+
+    /// Sets the number of steps taken before [`StayTm::run`] gives up
+    pub fn set_step_budget(&mut self, step_budget: usize) {
+        self.tm.set_step_budget(step_budget);
+    }
+
+    /// The configuration with `input` on the tape and the head over its first symbol
+    pub fn initial_configuration(&self, input: &[u16]) -> TmConfiguration {
+        self.tm.initial_configuration(input)
+    }
+
+    /// Takes one step from `configuration`, returning the new configuration and the movement
+    /// the head made. Returns `None` if the machine has halted.
+    pub fn step(&self, configuration: &TmConfiguration) -> Option<(TmConfiguration, TapeMovement)> {
+        self.tm.step(configuration)
+    }
+
+    /// Runs the machine on `input` and reports if it accepts, rejects or had not halted by the
+    /// end of its step budget
+    pub fn run(&self, input: &[u16]) -> Result<Verdict, MachineError> {
+        self.tm.run(input)
+    }
+
+    /// Every configuration the machine passes through on `input`, until it halts or its step
+    /// budget runs out
+    pub fn trace(&self, input: &[u16]) -> Result<Vec<TmConfiguration>, MachineError> {
+        self.tm.trace(input)
+    }
+}
+
+impl TryFrom<&StayTm> for Tm {
+    type Error = MachineError;
+
+    fn try_from(value: &StayTm) -> Result<Self, Self::Error> {
+        value.to_tm()
+    }
+}
+
+impl StateMachine for StayTm {
+    /// See [`Tm`], running out of steps is reported as [`MachineError::BudgetExhausted`]
+    fn accepts(&self, input: &[u16]) -> Result<bool, MachineError> {
+        self.tm.accepts(input)
+    }
+
+    fn accepts_validated(&self, input: &[u16]) -> bool {
+        self.tm.accepts_validated(input)
+    }
+
+    fn trace_states_validated(&self, input: &[u16]) -> Vec<(u16, Vec<TapeMovement>)> {
+        self.tm.trace_states_validated(input)
+    }
+
+    fn states(&self) -> u16 {
+        self.tm.states()
+    }
+
+    fn chars(&self) -> u16 {
+        self.tm.chars()
+    }
+}
+
+impl Simulate for StayTm {
+    type Configuration = TmConfiguration;
+
+    fn start_configuration(&self, input: &[u16]) -> TmConfiguration {
+        self.tm.start_configuration(input)
+    }
+
+    fn next_configuration(
+        &self,
+        configuration: &TmConfiguration,
+        input: &[u16],
+    ) -> Option<TmConfiguration> {
+        self.tm.next_configuration(configuration, input)
+    }
+
+    fn is_accepting_configuration(&self, configuration: &TmConfiguration, input: &[u16]) -> bool {
+        self.tm.is_accepting_configuration(configuration, input)
+    }
+}
+
+#[cfg(test)]
+mod stay_tm_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    const BLANK: u16 = 2;
+
+    fn transition(start: u16, read: u16, end: u16, movement: TapeMovement) -> TmTransition {
+        TmTransition {
+            start,
+            end,
+            read,
+            movement,
+        }
+    }
+
+    // Flips every bit of the input, first marking the bit with a stay and then replacing the mark
+    fn complement() -> StayTm {
+        use TapeMovement::{Right, Stay};
+        StayTm::from_transitions(
+            [
+                transition(0, 0, 1, Stay(Some(3))),
+                transition(0, 1, 1, Stay(Some(4))),
+                transition(0, BLANK, 2, Stay(None)),
+                transition(1, 3, 0, Right(Some(1))),
+                transition(1, 4, 0, Right(Some(0))),
+            ],
+            2,
+            3,
+            4,
+            2,
+            5,
+        )
+        .unwrap()
+    }
+
+    // Accepts inputs with an even number of 1s, marking each 1 with a stay before moving on
+    fn even_ones() -> StayTm {
+        use TapeMovement::{Right, Stay};
+        const X: u16 = 3;
+        StayTm::from_transitions(
+            [
+                transition(0, 0, 0, Right(None)),
+                transition(0, 1, 2, Stay(Some(X))),
+                transition(0, BLANK, 4, Stay(None)),
+                transition(2, X, 1, Right(Some(1))),
+                transition(1, 0, 1, Right(None)),
+                transition(1, 1, 3, Stay(Some(X))),
+                transition(1, BLANK, 5, Stay(None)),
+                transition(3, X, 0, Right(Some(1))),
+            ],
+            4,
+            5,
+            6,
+            2,
+            4,
+        )
+        .unwrap()
+    }
+
+    fn assert_same(stay_tm: &StayTm, tm: &Tm, input: &[u16]) {
+        assert_eq!(
+            tm.run(input).unwrap(),
+            stay_tm.run(input).unwrap(),
+            "{input:?}"
+        );
+
+        let stay_last = stay_tm.trace(input).unwrap().pop().unwrap();
+        let last = tm.trace(input).unwrap().pop().unwrap();
+        assert_eq!(last.state, stay_last.state, "{input:?}");
+        assert_eq!(last.head, stay_last.head, "{input:?}");
+        assert_eq!(
+            last.tape_contents(tm.blank()),
+            stay_last.tape_contents(stay_tm.blank()),
+            "{input:?}"
+        );
+    }
+
+    #[test]
+    fn complement_tape() {
+        let stay_tm = complement();
+        let last = stay_tm.trace(&[0, 1, 1]).unwrap().pop().unwrap();
+
+        assert_eq!(stay_tm.run(&[0, 1, 1]).unwrap(), Verdict::Accept);
+        assert_eq!(last.tape_contents(stay_tm.blank()), &[1, 0, 0]);
+        assert_eq!(last.head, 3);
+    }
+
+    #[test]
+    fn stay_is_reported() {
+        assert_eq!(
+            complement().trace_states(&[1]).unwrap(),
+            vec![
+                (0, vec![TapeMovement::Stay(None)]),
+                (1, vec![TapeMovement::Stay(Some(4))]),
+                (0, vec![TapeMovement::Right(Some(0))]),
+                (2, vec![TapeMovement::Stay(None)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn converted_states() {
+        let tm = complement().to_tm().unwrap();
+
+        // Stays lead into states 1 and 2, so two states are added
+        assert_eq!(tm.states(), 6);
+        assert_eq!(
+            tm.transition_table()[0],
+            Some((4, TapeMovement::Right(Some(3))))
+        );
+        assert_eq!(
+            tm.transition_table()[2],
+            Some((5, TapeMovement::Right(None)))
+        );
+        assert!(tm.transition_table()[20..25]
+            .iter()
+            .all(|&transition| transition == Some((1, TapeMovement::Left(None)))));
+        assert!(tm.transition_table()[25..]
+            .iter()
+            .all(|&transition| transition == Some((2, TapeMovement::Left(None)))));
+    }
+
+    #[test]
+    fn converted_complement_matches() {
+        let stay_tm = complement();
+        let tm = Tm::try_from(&stay_tm).unwrap();
+        for input in all_inputs(2, 7) {
+            assert_same(&stay_tm, &tm, &input);
+        }
+    }
+
+    #[test]
+    fn converted_even_ones_matches() {
+        let stay_tm = even_ones();
+        let tm = stay_tm.to_tm().unwrap();
+        for input in all_inputs(2, 7) {
+            assert_eq!(
+                stay_tm.accepts(&input).unwrap(),
+                input.iter().filter(|&&c| c == 1).count() % 2 == 0,
+                "{input:?}"
+            );
+            assert_same(&stay_tm, &tm, &input);
+        }
+    }
+
+    #[test]
+    fn converted_budget_exhausted() {
+        // Stays forever
+        let mut stay_tm = StayTm::from_transitions(
+            [transition(0, 0, 0, TapeMovement::Stay(None))],
+            1,
+            2,
+            3,
+            2,
+            3,
+        )
+        .unwrap();
+        stay_tm.set_step_budget(20);
+        let tm = stay_tm.to_tm().unwrap();
+
+        assert_eq!(stay_tm.run(&[0]).unwrap(), Verdict::BudgetExhausted);
+        assert_eq!(stay_tm.accepts(&[0]), Err(MachineError::BudgetExhausted));
+        assert_eq!(tm.run(&[0]).unwrap(), Verdict::BudgetExhausted);
+        assert_eq!(tm.step_budget(), 40);
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            StayTm::from_transitions(
+                [transition(0, 0, 5, TapeMovement::Stay(None))],
+                1,
+                2,
+                3,
+                2,
+                3,
+            )
+            .unwrap_err(),
+            MachineError::InvalidTransitionTarget {
+                index: 0,
+                state: 5,
+                states: 3
+            }
+        );
+        assert_eq!(
+            StayTm::from_transitions([], 2, 2, 3, 2, 3).unwrap_err(),
+            MachineError::SameHaltingState { state: 2 }
+        );
+    }
+}
@@ -0,0 +1,664 @@
+use crate::{
+    machine_utils::{table_lookup, validate_input},
+    simulation::DEFAULT_STEP_BUDGET,
+    transitions::TmTransition,
+    MachineError, Simulate, StateMachine, TapeMovement, Verdict,
+};
+
+/// # Tm Configuration
+///
+/// A Turing machine in `state` with its head over `tape[head]` after taking `steps` steps. The
+/// tape is infinite to the right and every cell past the end of `tape` holds the blank symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TmConfiguration {
+    pub state: u16,
+    pub head: usize,
+    pub tape: Vec<u16>,
+    pub steps: usize,
+}
+
+impl TmConfiguration {
+    /// The configuration with `input` on the tape and the head over its first symbol, before
+    /// taking any steps. The tape always has at least one cell so the head is over a symbol.
+    pub fn new(input: &[u16], blank: u16) -> TmConfiguration {
+        TmConfiguration {
+            state: 0,
+            head: 0,
+            tape: if input.is_empty() {
+                vec![blank]
+            } else {
+                input.to_vec()
+            },
+            steps: 0,
+        }
+    }
+
+    /// The tape with the trailing blanks removed
+    pub fn tape_contents(&self, blank: u16) -> &[u16] {
+        let len = self
+            .tape
+            .iter()
+            .rposition(|&symbol| symbol != blank)
+            .map_or(0, |last| last + 1);
+        &self.tape[..len]
+    }
+
+    /// Writes the symbol in `movement`, if any, and then moves the head. Moving left from the
+    /// first cell leaves the head where it is. Returns the movement that actually happened.
+    pub(crate) fn apply(&mut self, movement: TapeMovement, blank: u16) -> TapeMovement {
+        apply_movement(&mut self.tape, &mut self.head, movement, blank)
+    }
+}
+
+/// Writes the symbol in `movement`, if any, under `head` and then moves it, growing the tape with
+/// blanks on the right as needed. Returns the movement that actually happened, which is a stay
+/// when moving left from the first cell.
+pub(crate) fn apply_movement(
+    tape: &mut Vec<u16>,
+    head: &mut usize,
+    movement: TapeMovement,
+    blank: u16,
+) -> TapeMovement {
+    let (TapeMovement::Left(write) | TapeMovement::Right(write) | TapeMovement::Stay(write)) =
+        movement;
+    if let Some(symbol) = write {
+        tape[*head] = symbol;
+    }
+    match movement {
+        TapeMovement::Right(_) => {
+            *head += 1;
+            if *head == tape.len() {
+                tape.push(blank);
+            }
+            movement
+        }
+        TapeMovement::Left(_) if *head > 0 => {
+            *head -= 1;
+            movement
+        }
+        TapeMovement::Left(write) | TapeMovement::Stay(write) => TapeMovement::Stay(write),
+    }
+}
+
+/// # Turing Machine
+///
+/// A deterministic single tape Turing machine. The tape alphabet has `tape_chars` symbols, the
+/// first `chars` of which make up the input alphabet and the next one is the blank symbol. The
+/// machine halts as soon as it enters its accept or reject state, and rejects when there is no
+/// transition for the symbol under the head.
+///
+/// Every transition moves the head left or right. A machine that may also leave the head in
+/// place is a [`StayTm`](crate::stay_tm::StayTm).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tm {
+    transition_table: Vec<Option<(u16, TapeMovement)>>,
+    accept_state: u16,
+    reject_state: u16,
+    states: u16,
+    chars: u16,
+    tape_chars: u16,
+    step_budget: usize,
+}
+
+impl Tm {
+    /// Builds a Turing machine. The transition for reading `symbol` in `state` is stored at
+    /// `state * tape_chars + symbol`, so the table has a length of `states * tape_chars`. Each
+    /// transition is the next state and the movement of the head, along with the symbol to write
+    /// before moving.
+    pub fn build(
+        transition_table: Vec<Option<(u16, TapeMovement)>>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+    ) -> Result<Tm, MachineError> {
+        Tm::build_with_stay(
+            transition_table,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+            false,
+        )
+    }
+
+    /// Builds a Turing machine whose head may also stay in place if `allow_stay` is set, which is
+    /// how a [`StayTm`](crate::stay_tm::StayTm) runs
+    pub(crate) fn build_with_stay(
+        transition_table: Vec<Option<(u16, TapeMovement)>>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+        allow_stay: bool,
+    ) -> Result<Tm, MachineError> {
+        validate_table(&transition_table, states, chars, tape_chars, allow_stay)?;
+        validate_halting_states(accept_state, reject_state, states)?;
+
+        Ok(Tm {
+            transition_table,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+            step_budget: DEFAULT_STEP_BUDGET,
+        })
+    }
+
+    /// Builds a Turing machine from a list of transitions instead of a full transition table.
+    /// Later transitions replace earlier ones for the same state and symbol.
+    pub fn from_transitions(
+        transitions: impl IntoIterator<Item = TmTransition>,
+        accept_state: u16,
+        reject_state: u16,
+        states: u16,
+        chars: u16,
+        tape_chars: u16,
+    ) -> Result<Tm, MachineError> {
+        Tm::build(
+            table_from_transitions(transitions, states, tape_chars)?,
+            accept_state,
+            reject_state,
+            states,
+            chars,
+            tape_chars,
+        )
+    }
+
+    /// The blank symbol, which directly follows the input alphabet
+    pub fn blank(&self) -> u16 {
+        self.chars
+    }
+
+    pub fn tape_chars(&self) -> u16 {
+        self.tape_chars
+    }
+
+    pub fn accept_state(&self) -> u16 {
+        self.accept_state
+    }
+
+    pub fn reject_state(&self) -> u16 {
+        self.reject_state
+    }
+
+    /// The transition table in the layout described by [`Tm::build`]
+    pub fn transition_table(&self) -> &[Option<(u16, TapeMovement)>] {
+        &self.transition_table
+    }
+
+    pub fn step_budget(&self) -> usize {
+        self.step_budget
+    }
+
+    /// Sets the number of steps taken before [`Tm::run`] gives up
+    pub fn set_step_budget(&mut self, step_budget: usize) {
+        self.step_budget = step_budget;
+    }
+
+    /// The configuration with `input` on the tape and the head over its first symbol, see
+    /// [`TmConfiguration::new`]
+    pub fn initial_configuration(&self, input: &[u16]) -> TmConfiguration {
+        TmConfiguration::new(input, self.blank())
+    }
+
+    /// Takes one step from `configuration`, returning the new configuration and the movement
+    /// the head made. Returns `None` if the machine has halted.
+    pub fn step(&self, configuration: &TmConfiguration) -> Option<(TmConfiguration, TapeMovement)> {
+        if configuration.state == self.accept_state || configuration.state == self.reject_state {
+            return None;
+        }
+        let (state, movement) = self.transition_table[table_lookup(
+            configuration.state as usize,
+            configuration.tape[configuration.head] as usize,
+            self.tape_chars as usize,
+        )]?;
+
+        let mut next = configuration.clone();
+        let movement = next.apply(movement, self.blank());
+        next.state = state;
+        next.steps += 1;
+        Some((next, movement))
+    }
+
+    /// Runs the machine on `input` and reports if it accepts, rejects or had not halted by the
+    /// end of its step budget
+    pub fn run(&self, input: &[u16]) -> Result<Verdict, MachineError> {
+        validate_input(input, self.chars)?;
+        let mut configuration = self.initial_configuration(input);
+        for _ in 0..self.step_budget {
+            match self.step(&configuration) {
+                Some((next, _)) => configuration = next,
+                None => break,
+            }
+        }
+        Ok(self.verdict(&configuration))
+    }
+
+    /// Every configuration the machine passes through on `input`, until it halts or its step
+    /// budget runs out
+    pub fn trace(&self, input: &[u16]) -> Result<Vec<TmConfiguration>, MachineError> {
+        validate_input(input, self.chars)?;
+        Ok(self
+            .trace_validated(input)
+            .into_iter()
+            .map(|(configuration, _)| configuration)
+            .collect())
+    }
+
+    fn trace_validated(&self, input: &[u16]) -> Vec<(TmConfiguration, TapeMovement)> {
+        let mut trace = vec![(self.initial_configuration(input), TapeMovement::Stay(None))];
+        for _ in 0..self.step_budget {
+            let Some(next) = self.step(&trace.last().expect("The trace is never empty").0) else {
+                break;
+            };
+            trace.push(next);
+        }
+        trace
+    }
+
+    fn verdict(&self, configuration: &TmConfiguration) -> Verdict {
+        if configuration.state == self.accept_state {
+            Verdict::Accept
+        } else if self.step(configuration).is_none() {
+            Verdict::Reject
+        } else {
+            Verdict::BudgetExhausted
+        }
+    }
+}
+
+/// Checks the dimensions, targets, written symbols and movements of a single tape transition
+/// table, along with the alphabets. Each cell may hold any number of transitions, so the same
+/// checks cover deterministic and nondeterministic machines. `allow_stay` decides if the head may
+/// stay in place.
+pub(crate) fn validate_table<C>(
+    transition_table: &[C],
+    states: u16,
+    chars: u16,
+    tape_chars: u16,
+    allow_stay: bool,
+) -> Result<(), MachineError>
+where
+    for<'a> &'a C: IntoIterator<Item = &'a (u16, TapeMovement)>,
+{
+    if states == 0 {
+        return Err(MachineError::NoStates);
+    }
+    if chars == 0 {
+        return Err(MachineError::NoChars);
+    }
+    // The tape alphabet must contain the input alphabet and the blank symbol after it
+    if tape_chars <= chars {
+        return Err(MachineError::TapeCharOutOfRange {
+            char: chars,
+            tape_chars,
+        });
+    }
+    if transition_table.len() != states as usize * tape_chars as usize {
+        return Err(MachineError::WrongTableLength {
+            expected: states as usize * tape_chars as usize,
+            actual: transition_table.len(),
+        });
+    }
+    for (index, &(state, movement)) in transition_table
+        .iter()
+        .enumerate()
+        .flat_map(|(index, cell)| cell.into_iter().map(move |transition| (index, transition)))
+    {
+        if state >= states {
+            return Err(MachineError::InvalidTransitionTarget {
+                index,
+                state,
+                states,
+            });
+        }
+        let (TapeMovement::Left(write) | TapeMovement::Right(write) | TapeMovement::Stay(write)) =
+            movement;
+        if let Some(char) = write.filter(|&char| char >= tape_chars) {
+            return Err(MachineError::TapeCharOutOfRange { char, tape_chars });
+        }
+        if !allow_stay && matches!(movement, TapeMovement::Stay(_)) {
+            return Err(MachineError::InvalidMovement { index });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the accept and reject states of a Turing machine exist and are different states
+pub(crate) fn validate_halting_states(
+    accept_state: u16,
+    reject_state: u16,
+    states: u16,
+) -> Result<(), MachineError> {
+    if accept_state >= states {
+        return Err(MachineError::InvalidAcceptState {
+            state: accept_state,
+            states,
+        });
+    }
+    if reject_state >= states {
+        return Err(MachineError::InvalidRejectState {
+            state: reject_state,
+            states,
+        });
+    }
+    if accept_state == reject_state {
+        return Err(MachineError::SameHaltingState {
+            state: accept_state,
+        });
+    }
+    Ok(())
+}
+
+/// Lays out a list of single tape transitions in the table format used by [`Tm::build`]
+pub(crate) fn table_from_transitions(
+    transitions: impl IntoIterator<Item = TmTransition>,
+    states: u16,
+    tape_chars: u16,
+) -> Result<Vec<Option<(u16, TapeMovement)>>, MachineError> {
+    let mut transition_table = vec![None; states as usize * tape_chars as usize];
+    for TmTransition {
+        start,
+        end,
+        read,
+        movement,
+    } in transitions
+    {
+        if start >= states {
+            return Err(MachineError::StateOutOfRange {
+                state: start,
+                states,
+            });
+        }
+        if read >= tape_chars {
+            return Err(MachineError::TapeCharOutOfRange {
+                char: read,
+                tape_chars,
+            });
+        }
+        transition_table[table_lookup(start as usize, read as usize, tape_chars as usize)] =
+            Some((end, movement));
+    }
+    Ok(transition_table)
+}
+
+impl StateMachine for Tm {
+    /// Checks if the machine halts in its accept state. Returns
+    /// [`MachineError::BudgetExhausted`] if it has not halted within its step budget, so a machine
+    /// that loops is not mistaken for one that rejects.
+    fn accepts(&self, input: &[u16]) -> Result<bool, MachineError> {
+        self.run(input)?.accepted()
+    }
+
+    /// Accepts when the machine halts in its accept state within the step budget. Running out of
+    /// steps counts as rejecting, use [`Tm::run`] or `accepts` to tell the two apart.
+    fn accepts_validated(&self, input: &[u16]) -> bool {
+        self.run(input) == Ok(Verdict::Accept)
+    }
+
+    /// The states the machine passes through along with the movement of the head, and the
+    /// symbol written, on the step into each state
+    fn trace_states_validated(&self, input: &[u16]) -> Vec<(u16, Vec<TapeMovement>)> {
+        self.trace_validated(input)
+            .into_iter()
+            .map(|(configuration, movement)| (configuration.state, vec![movement]))
+            .collect()
+    }
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn chars(&self) -> u16 {
+        self.chars
+    }
+}
+
+impl Simulate for Tm {
+    type Configuration = TmConfiguration;
+
+    fn start_configuration(&self, input: &[u16]) -> TmConfiguration {
+        self.initial_configuration(input)
+    }
+
+    fn next_configuration(
+        &self,
+        configuration: &TmConfiguration,
+        _input: &[u16],
+    ) -> Option<TmConfiguration> {
+        if configuration.steps >= self.step_budget {
+            return None;
+        }
+        self.step(configuration).map(|(next, _)| next)
+    }
+
+    fn is_accepting_configuration(&self, configuration: &TmConfiguration, _input: &[u16]) -> bool {
+        configuration.state == self.accept_state
+    }
+}
+
+#[cfg(test)]
+mod tm_tests {
+    use super::*;
+    use crate::{machine_utils::all_inputs, Simulation};
+
+    const BLANK: u16 = 2;
+    const X: u16 = 3;
+    const Y: u16 = 4;
+
+    fn transition(start: u16, read: u16, end: u16, movement: TapeMovement) -> TmTransition {
+        TmTransition {
+            start,
+            end,
+            read,
+            movement,
+        }
+    }
+
+    // Decides 0^n 1^n by crossing off a 0 with an X and the matching 1 with a Y
+    fn zeros_then_ones() -> Tm {
+        use TapeMovement::{Left, Right};
+        Tm::from_transitions(
+            [
+                transition(0, 0, 1, Right(Some(X))),
+                transition(0, Y, 3, Right(None)),
+                transition(0, BLANK, 4, Right(None)),
+                transition(1, 0, 1, Right(None)),
+                transition(1, Y, 1, Right(None)),
+                transition(1, 1, 2, Left(Some(Y))),
+                transition(2, 0, 2, Left(None)),
+                transition(2, Y, 2, Left(None)),
+                transition(2, X, 0, Right(None)),
+                transition(3, Y, 3, Right(None)),
+                transition(3, BLANK, 4, Right(None)),
+            ],
+            4,
+            5,
+            6,
+            2,
+            5,
+        )
+        .unwrap()
+    }
+
+    fn is_zeros_then_ones(input: &[u16]) -> bool {
+        let zeros = input.iter().take_while(|&&c| c == 0).count();
+        zeros * 2 == input.len() && input[zeros..].iter().all(|&c| c == 1)
+    }
+
+    #[test]
+    fn decides_zeros_then_ones() {
+        let tm = zeros_then_ones();
+        for input in all_inputs(2, 8) {
+            let expected = if is_zeros_then_ones(&input) {
+                Verdict::Accept
+            } else {
+                Verdict::Reject
+            };
+            assert_eq!(tm.run(&input).unwrap(), expected, "{input:?}");
+            assert_eq!(
+                tm.accepts(&input).unwrap(),
+                is_zeros_then_ones(&input),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn final_tape() {
+        let tm = zeros_then_ones();
+        let trace = tm.trace(&[0, 0, 1, 1]).unwrap();
+        let last = trace.last().unwrap();
+
+        assert_eq!(last.state, 4);
+        assert_eq!(last.tape_contents(tm.blank()), &[X, X, Y, Y]);
+        assert_eq!(last.steps, trace.len() - 1);
+    }
+
+    #[test]
+    fn trace_states_reports_writes() {
+        let tm = zeros_then_ones();
+
+        assert_eq!(
+            tm.trace_states(&[0, 1]).unwrap(),
+            vec![
+                (0, vec![TapeMovement::Stay(None)]),
+                (1, vec![TapeMovement::Right(Some(X))]),
+                (2, vec![TapeMovement::Left(Some(Y))]),
+                (0, vec![TapeMovement::Right(None)]),
+                (3, vec![TapeMovement::Right(None)]),
+                (4, vec![TapeMovement::Right(None)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn left_at_first_cell_stays() {
+        let tm = Tm::from_transitions(
+            [transition(0, 0, 1, TapeMovement::Left(Some(1)))],
+            1,
+            2,
+            3,
+            2,
+            3,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tm.trace_states(&[0]).unwrap(),
+            vec![
+                (0, vec![TapeMovement::Stay(None)]),
+                (1, vec![TapeMovement::Stay(Some(1))]),
+            ]
+        );
+        assert_eq!(tm.trace(&[0]).unwrap()[1].head, 0);
+    }
+
+    #[test]
+    fn budget_exhausted() {
+        // Moves right forever
+        let mut tm = Tm::from_transitions(
+            [
+                transition(0, 0, 0, TapeMovement::Right(None)),
+                transition(0, 1, 0, TapeMovement::Right(None)),
+                transition(0, BLANK, 0, TapeMovement::Right(None)),
+            ],
+            1,
+            2,
+            3,
+            2,
+            3,
+        )
+        .unwrap();
+        tm.set_step_budget(50);
+
+        assert_eq!(tm.run(&[0, 1]).unwrap(), Verdict::BudgetExhausted);
+        // A machine that loops is not reported as rejecting
+        assert_eq!(tm.accepts(&[0, 1]), Err(MachineError::BudgetExhausted));
+        assert!(!tm.accepts_validated(&[0, 1]));
+        assert_eq!(tm.trace(&[]).unwrap().len(), 51);
+    }
+
+    #[test]
+    fn simulation_matches_run() {
+        let tm = zeros_then_ones();
+        let mut simulation = Simulation::new(&tm, &[0, 0, 1, 1]).unwrap();
+
+        assert_eq!(simulation.step().unwrap().tape, vec![X, 0, 1, 1]);
+        let last = simulation.run_to_end().clone();
+        assert!(simulation.is_accepting());
+        assert_eq!(&last, tm.trace(&[0, 0, 1, 1]).unwrap().last().unwrap());
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            Tm::build(vec![None; 4], 0, 1, 2, 2, 2).unwrap_err(),
+            MachineError::TapeCharOutOfRange {
+                char: 2,
+                tape_chars: 2
+            }
+        );
+        assert_eq!(
+            Tm::build(vec![None; 4], 0, 1, 2, 1, 2).unwrap(),
+            Tm::from_transitions([], 0, 1, 2, 1, 2).unwrap()
+        );
+        assert_eq!(
+            Tm::from_transitions(
+                [transition(0, 0, 0, TapeMovement::Stay(None))],
+                0,
+                1,
+                2,
+                1,
+                2
+            )
+            .unwrap_err(),
+            MachineError::InvalidMovement { index: 0 }
+        );
+        assert_eq!(
+            Tm::from_transitions(
+                [transition(1, 1, 0, TapeMovement::Right(Some(2)))],
+                0,
+                1,
+                2,
+                1,
+                2
+            )
+            .unwrap_err(),
+            MachineError::TapeCharOutOfRange {
+                char: 2,
+                tape_chars: 2
+            }
+        );
+        assert_eq!(
+            Tm::build(vec![None; 4], 2, 1, 2, 1, 2).unwrap_err(),
+            MachineError::InvalidAcceptState {
+                state: 2,
+                states: 2
+            }
+        );
+        // Both constructors report a missing reject state the same way
+        for result in [
+            Tm::build(vec![None; 4], 0, 2, 2, 1, 2),
+            Tm::from_transitions([], 0, 2, 2, 1, 2),
+        ] {
+            assert_eq!(
+                result.unwrap_err(),
+                MachineError::InvalidRejectState {
+                    state: 2,
+                    states: 2
+                }
+            );
+        }
+        // Halting in the shared state would always accept, so it is refused
+        assert_eq!(
+            Tm::build(vec![Some((0, TapeMovement::Right(None))); 2], 0, 0, 1, 1, 2).unwrap_err(),
+            MachineError::SameHaltingState { state: 0 }
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Finite state transducers, which turn an input string into an output string instead of accepting
+//! or rejecting it
+
+use crate::{machine_utils::validate_input, MachineError};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, VecDeque};
+
+mod mealy;
+mod moore;
+
+pub use mealy::Mealy;
+pub use moore::Moore;
+
+/// # Transducer
+///
+/// A deterministic finite state machine that writes a symbol of its output alphabet for each step
+/// it takes. Output symbols are numbered from 0 like input characters, and the start state is
+/// always state 0.
+pub trait Transducer {
+    /// The output written while reading `input`
+    fn transduce(&self, input: &[u16]) -> Result<Vec<u16>, MachineError> {
+        validate_input(input, self.chars())?;
+        Ok(self.transduce_validated(input))
+    }
+    /// The output written while reading `input`
+    fn transduce_validated(&self, input: &[u16]) -> Vec<u16>;
+
+    /// The states the machine passes through while reading `input`, starting with state 0
+    fn trace_states(&self, input: &[u16]) -> Result<Vec<u16>, MachineError> {
+        validate_input(input, self.chars())?;
+        Ok(self.trace_states_validated(input))
+    }
+    /// The states the machine passes through while reading `input`, starting with state 0
+    fn trace_states_validated(&self, input: &[u16]) -> Vec<u16> {
+        let mut states = Vec::with_capacity(input.len() + 1);
+        states.push(0);
+        for &c in input {
+            states.push(self.next_state(states[states.len() - 1], c));
+        }
+        states
+    }
+
+    /// The state reached by reading `char` in `state`
+    fn next_state(&self, state: u16, char: u16) -> u16;
+
+    fn states(&self) -> u16;
+
+    fn chars(&self) -> u16;
+
+    /// The number of symbols in the output alphabet
+    fn outputs(&self) -> u16;
+}
+
+/// Validates the parts shared by every transducer and the targets of its transition table
+fn validate_transitions(
+    targets: impl Iterator<Item = u16>,
+    len: usize,
+    states: u16,
+    chars: u16,
+    outputs: u16,
+) -> Result<(), MachineError> {
+    if states == 0 {
+        return Err(MachineError::NoStates);
+    }
+    if chars == 0 {
+        return Err(MachineError::NoChars);
+    }
+    if outputs == 0 {
+        return Err(MachineError::NoOutputs);
+    }
+    if len != states as usize * chars as usize {
+        return Err(MachineError::WrongTableLength {
+            expected: states as usize * chars as usize,
+            actual: len,
+        });
+    }
+    if let Some((index, state)) = targets.enumerate().find(|&(_, state)| state >= states) {
+        return Err(MachineError::InvalidTransitionTarget {
+            index,
+            state,
+            states,
+        });
+    }
+    Ok(())
+}
+
+/// Finds an output symbol that is not part of the output alphabet
+fn validate_outputs(
+    mut output_symbols: impl Iterator<Item = u16>,
+    outputs: u16,
+) -> Result<(), MachineError> {
+    match output_symbols.find(|&output| output >= outputs) {
+        Some(output) => Err(MachineError::OutputOutOfRange { output, outputs }),
+        None => Ok(()),
+    }
+}
+
+/// Groups the states reachable from the start state into classes of states that write the same
+/// output for every input, where two states start out in the same class when they have the same
+/// `signature`. The classes are refined until every state in a class moves into the same class on
+/// each character.
+///
+/// The classes are numbered in breadth first order from the start state, following characters in
+/// increasing order, which makes them the states of the minimal machine. Returns the class of
+/// every state (`None` when unreachable) and the states in each class.
+fn minimal_partition<T: Transducer, K: Ord>(
+    machine: &T,
+    signature: impl Fn(u16) -> K,
+) -> (Vec<Option<u16>>, Vec<BTreeSet<u16>>) {
+    let mut reachable = vec![false; machine.states() as usize];
+    reachable[0] = true;
+    let mut q = VecDeque::from([0]);
+    while let Some(state) = q.pop_front() {
+        for c in 0..machine.chars() {
+            let next_state = machine.next_state(state, c);
+            if !reachable[next_state as usize] {
+                reachable[next_state as usize] = true;
+                q.push_back(next_state);
+            }
+        }
+    }
+    let reachable: Vec<u16> = (0..machine.states())
+        .filter(|&state| reachable[state as usize])
+        .collect();
+
+    let mut class_of = vec![0; machine.states() as usize];
+    let mut classes = number_by_key(&reachable, &mut class_of, signature);
+    loop {
+        let previous = class_of.clone();
+        let refined = number_by_key(&reachable, &mut class_of, |state| {
+            let next_classes: Vec<usize> = (0..machine.chars())
+                .map(|c| previous[machine.next_state(state, c) as usize])
+                .collect();
+            (previous[state as usize], next_classes)
+        });
+        // Refining never merges classes, so the same number of classes means nothing was split
+        if refined == classes {
+            break;
+        }
+        classes = refined;
+    }
+
+    // Renumber the classes in breadth first order from the class holding the start state
+    let mut new_state_of_class = HashMap::from([(class_of[0], 0)]);
+    let mut class_order = vec![class_of[0]];
+    let mut q = VecDeque::from([0]);
+    while let Some(state) = q.pop_front() {
+        for c in 0..machine.chars() {
+            let next_state = machine.next_state(state, c);
+            let class = class_of[next_state as usize];
+            if let Entry::Vacant(entry) = new_state_of_class.entry(class) {
+                entry.insert(class_order.len() as u16);
+                class_order.push(class);
+                q.push_back(next_state);
+            }
+        }
+    }
+
+    let mut renumbered = vec![None; machine.states() as usize];
+    let mut partition = vec![BTreeSet::new(); class_order.len()];
+    for &state in &reachable {
+        let new_state = new_state_of_class[&class_of[state as usize]];
+        renumbered[state as usize] = Some(new_state);
+        partition[new_state as usize].insert(state);
+    }
+    (renumbered, partition)
+}
+
+/// Numbers the distinct keys of `states` in sorted order, storing the number of each state in
+/// `class_of`. Returns how many numbers were used.
+fn number_by_key<K: Ord>(states: &[u16], class_of: &mut [usize], key: impl Fn(u16) -> K) -> usize {
+    let keys: Vec<K> = states.iter().map(|&state| key(state)).collect();
+    let distinct: BTreeSet<&K> = keys.iter().collect();
+    let distinct: Vec<&K> = distinct.into_iter().collect();
+    for (&state, key) in states.iter().zip(&keys) {
+        class_of[state as usize] = distinct
+            .binary_search(&key)
+            .expect("Every key is in the distinct keys");
+    }
+    distinct.len()
+}
@@ -0,0 +1,322 @@
+use super::{minimal_partition, validate_outputs, validate_transitions, Moore, Transducer};
+use crate::{machine_utils::table_lookup, MachineError};
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+/// # Mealy Machine
+///
+/// A transducer that writes one output symbol on every transition, so the output is as long as
+/// the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mealy {
+    transition_table: Vec<(u16, u16)>,
+    states: u16,
+    chars: u16,
+    outputs: u16,
+}
+
+impl Mealy {
+    /// Builds a Mealy machine whose transition table holds the next state and the output symbol
+    /// for every state and character, laid out like the transition table of a `Dfa`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{transducer::Mealy, Transducer};
+    ///
+    /// // Writes 1 whenever the input changes from 0 to 1
+    /// let rising_edge = Mealy::build(vec![(0, 0), (1, 1), (0, 0), (1, 0)], 2, 2, 2).unwrap();
+    ///
+    /// assert_eq!(rising_edge.transduce(&[1, 1, 0, 1]).unwrap(), vec![1, 0, 0, 1]);
+    /// assert_eq!(rising_edge.trace_states(&[1, 0]).unwrap(), vec![0, 1, 0]);
+    /// ```
+    pub fn build(
+        transition_table: Vec<(u16, u16)>,
+        states: u16,
+        chars: u16,
+        outputs: u16,
+    ) -> Result<Mealy, MachineError> {
+        validate_transitions(
+            transition_table.iter().map(|&(state, _)| state),
+            transition_table.len(),
+            states,
+            chars,
+            outputs,
+        )?;
+        validate_outputs(transition_table.iter().map(|&(_, output)| output), outputs)?;
+
+        Ok(Mealy {
+            transition_table,
+            states,
+            chars,
+            outputs,
+        })
+    }
+
+    /// Converts a `Moore` machine into a `Mealy` machine with the same states, where each
+    /// transition writes the output of the state it moves into. The output of the `Mealy` machine
+    /// is the output of the `Moore` machine without the output of the start state.
+    pub fn from_moore(moore: &Moore) -> Mealy {
+        let transition_table = moore
+            .transition_table()
+            .iter()
+            .map(|&state| (state, moore.output_table()[state as usize]))
+            .collect();
+        Mealy::build(
+            transition_table,
+            moore.states(),
+            moore.chars(),
+            moore.outputs(),
+        )
+        .expect("The Mealy machine shares the states and alphabets of a valid Moore machine")
+    }
+
+    /// Checks if both machines write the same output for every input. Returns `None` when they
+    /// do, and otherwise the shortest, and among those the lexicographically smallest, input they
+    /// write different outputs for. Both machines must have the same number of characters.
+    pub fn equivalent(&self, other: &Mealy) -> Result<Option<Vec<u16>>, MachineError> {
+        if self.chars != other.chars {
+            return Err(MachineError::AlphabetMismatch {
+                first: self.chars,
+                second: other.chars,
+            });
+        }
+
+        // Each explored pair remembers the pair it was reached from and the character read, so the
+        // counterexample can be rebuilt once a transition writes different outputs
+        let mut seen = HashSet::from([(0, 0)]);
+        let mut explored: Vec<(Option<usize>, u16)> = vec![(None, 0)];
+        let mut q = VecDeque::from([((0, 0), 0)]);
+        while let Some(((first, second), index)) = q.pop_front() {
+            for c in 0..self.chars {
+                let (first_next, first_output) = self.transition(first, c);
+                let (second_next, second_output) = other.transition(second, c);
+                if first_output != second_output {
+                    let mut input = vec![c];
+                    let mut cur = index;
+                    while let (Some(parent), c) = explored[cur] {
+                        input.push(c);
+                        cur = parent;
+                    }
+                    input.reverse();
+                    return Ok(Some(input));
+                }
+                if seen.insert((first_next, second_next)) {
+                    explored.push((Some(index), c));
+                    q.push_back(((first_next, second_next), explored.len() - 1));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Merges the states that write the same output for every input after removing every state
+    /// that cannot be reached from the start state.
+    ///
+    /// The states of the returned machine are numbered in breadth first order from the start
+    /// state, following characters in increasing order, so two equivalent machines minimize to
+    /// equal machines. Along with the minimal machine this returns the reachable original states
+    /// that each new state stands for, indexed by new state.
+    pub fn minimize(&self) -> (Mealy, Vec<BTreeSet<u16>>) {
+        let (renumbered, partition) = minimal_partition(self, |state| {
+            (0..self.chars)
+                .map(|c| self.transition(state, c).1)
+                .collect::<Vec<u16>>()
+        });
+
+        let transition_table = partition
+            .iter()
+            .flat_map(|states| {
+                let representative = *states.first().expect("Every class holds a state");
+                (0..self.chars).map(move |c| self.transition(representative, c))
+            })
+            .map(|(state, output)| {
+                let state = renumbered[state as usize].expect("Targets of reachable states");
+                (state, output)
+            })
+            .collect();
+        let mealy = Mealy::build(
+            transition_table,
+            partition.len() as u16,
+            self.chars,
+            self.outputs,
+        )
+        .expect("The minimized Mealy machine should always be valid");
+        (mealy, partition)
+    }
+
+    /// The transition table in the layout described by [`Mealy::build`]
+    pub fn transition_table(&self) -> &[(u16, u16)] {
+        &self.transition_table
+    }
+
+    /// The next state and the output symbol written when reading `char` in `state`
+    pub fn transition(&self, state: u16, char: u16) -> (u16, u16) {
+        self.transition_table[table_lookup(state as usize, char as usize, self.chars as usize)]
+    }
+}
+
+impl From<&Moore> for Mealy {
+    fn from(value: &Moore) -> Self {
+        Mealy::from_moore(value)
+    }
+}
+
+impl Transducer for Mealy {
+    fn transduce_validated(&self, input: &[u16]) -> Vec<u16> {
+        let mut state = 0;
+        input
+            .iter()
+            .map(|&c| {
+                let output;
+                (state, output) = self.transition(state, c);
+                output
+            })
+            .collect()
+    }
+
+    fn next_state(&self, state: u16, char: u16) -> u16 {
+        self.transition(state, char).0
+    }
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn chars(&self) -> u16 {
+        self.chars
+    }
+
+    fn outputs(&self) -> u16 {
+        self.outputs
+    }
+}
+
+#[cfg(test)]
+mod mealy_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    // Writes 1 whenever the input changes from 0 to 1
+    fn rising_edge() -> Mealy {
+        Mealy::build(vec![(0, 0), (1, 1), (0, 0), (1, 0)], 2, 2, 2).unwrap()
+    }
+
+    // Writes the parity of the 1s read so far
+    fn parity() -> Mealy {
+        Mealy::build(vec![(0, 0), (1, 1), (1, 1), (0, 0)], 2, 2, 2).unwrap()
+    }
+
+    #[test]
+    fn transduce() {
+        let parity = parity();
+
+        assert_eq!(parity.transduce(&[]).unwrap(), vec![]);
+        assert_eq!(
+            parity.transduce(&[1, 0, 1, 1, 0]).unwrap(),
+            vec![1, 1, 0, 1, 1]
+        );
+        assert_eq!(parity.trace_states(&[1, 0, 1]).unwrap(), vec![0, 1, 1, 0]);
+        assert_eq!(
+            parity.transduce(&[0, 2]).unwrap_err(),
+            MachineError::InvalidInputChar {
+                index: 1,
+                char: 2,
+                chars: 2
+            }
+        );
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            Mealy::build(vec![(0, 0)], 1, 1, 0).unwrap_err(),
+            MachineError::NoOutputs
+        );
+        assert_eq!(
+            Mealy::build(vec![(0, 0), (2, 0)], 1, 2, 1).unwrap_err(),
+            MachineError::InvalidTransitionTarget {
+                index: 1,
+                state: 2,
+                states: 1
+            }
+        );
+        assert_eq!(
+            Mealy::build(vec![(0, 0), (0, 3)], 1, 2, 2).unwrap_err(),
+            MachineError::OutputOutOfRange {
+                output: 3,
+                outputs: 2
+            }
+        );
+        assert_eq!(
+            Mealy::build(vec![(0, 0)], 1, 2, 2).unwrap_err(),
+            MachineError::WrongTableLength {
+                expected: 2,
+                actual: 1
+            }
+        );
+    }
+
+    #[test]
+    fn minimize() {
+        // Parity with each parity split over two states, and an unreachable state 4
+        let redundant = Mealy::build(
+            vec![
+                (2, 0),
+                (1, 1),
+                (3, 1),
+                (0, 0),
+                (0, 0),
+                (3, 1),
+                (1, 1),
+                (2, 0),
+                (4, 1),
+                (0, 1),
+            ],
+            5,
+            2,
+            2,
+        )
+        .unwrap();
+        let (minimal, partition) = redundant.minimize();
+
+        assert_eq!(minimal, parity());
+        assert_eq!(
+            partition,
+            vec![BTreeSet::from([0, 2]), BTreeSet::from([1, 3])]
+        );
+        assert_eq!(redundant.equivalent(&minimal), Ok(None));
+        assert_eq!(rising_edge().minimize().0, rising_edge());
+    }
+
+    #[test]
+    fn equivalence() {
+        assert_eq!(parity().equivalent(&parity()), Ok(None));
+        // Both write the input on the first character, but differ on 10
+        assert_eq!(rising_edge().equivalent(&parity()), Ok(Some(vec![1, 0])));
+        assert_eq!(
+            parity().equivalent(&Mealy::build(vec![(0, 0)], 1, 1, 1).unwrap()),
+            Err(MachineError::AlphabetMismatch {
+                first: 2,
+                second: 1
+            })
+        );
+    }
+
+    #[test]
+    fn moore_round_trip() {
+        for mealy in [rising_edge(), parity()] {
+            let (moore, pairs) = Moore::from_mealy(&mealy, 0).unwrap();
+            let back = Mealy::from_moore(&moore);
+
+            assert_eq!(pairs[0], (0, 0));
+            for input in all_inputs(2, 6) {
+                let output = mealy.transduce(&input).unwrap();
+                assert_eq!(moore.transduce(&input).unwrap()[1..], output);
+                assert_eq!(back.transduce(&input).unwrap(), output);
+            }
+            assert_eq!(back.equivalent(&mealy), Ok(None));
+            assert_eq!(back.minimize().0, mealy.minimize().0);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use super::{minimal_partition, validate_outputs, validate_transitions, Mealy, Transducer};
+use crate::{machine_utils::table_lookup, MachineError};
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// # Moore Machine
+///
+/// A transducer where every state has an output symbol, which is written whenever the machine
+/// enters that state. The output starts with the output of the start state, so it is one symbol
+/// longer than the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moore {
+    transition_table: Vec<u16>,
+    output_table: Vec<u16>,
+    states: u16,
+    chars: u16,
+    outputs: u16,
+}
+
+impl Moore {
+    /// Builds a Moore machine with the transition table layout of a `Dfa`. The output table holds
+    /// the output symbol of every state, indexed by state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use engine_room::{transducer::Moore, Transducer};
+    ///
+    /// // Writes the parity of the 1s read so far
+    /// let parity = Moore::build(vec![0, 1, 1, 0], vec![0, 1], 2, 2, 2).unwrap();
+    ///
+    /// assert_eq!(parity.transduce(&[1, 0, 1]).unwrap(), vec![0, 1, 1, 0]);
+    /// ```
+    pub fn build(
+        transition_table: Vec<u16>,
+        output_table: Vec<u16>,
+        states: u16,
+        chars: u16,
+        outputs: u16,
+    ) -> Result<Moore, MachineError> {
+        validate_transitions(
+            transition_table.iter().copied(),
+            transition_table.len(),
+            states,
+            chars,
+            outputs,
+        )?;
+        if output_table.len() != states as usize {
+            return Err(MachineError::WrongTableLength {
+                expected: states as usize,
+                actual: output_table.len(),
+            });
+        }
+        validate_outputs(output_table.iter().copied(), outputs)?;
+
+        Ok(Moore {
+            transition_table,
+            output_table,
+            states,
+            chars,
+            outputs,
+        })
+    }
+
+    /// Converts a `Mealy` machine into a `Moore` machine that writes `initial_output` followed by
+    /// the output of the `Mealy` machine. Each state of the `Moore` machine is a pair of a state
+    /// of the `Mealy` machine and the output written when entering it, so a `Mealy` state entered
+    /// with different outputs is split. Only the pairs reachable from `(0, initial_output)` are
+    /// kept, numbered in breadth first order. Along with the `Moore` machine this returns the pair
+    /// that each state stands for, indexed by state.
+    pub fn from_mealy(
+        mealy: &Mealy,
+        initial_output: u16,
+    ) -> Result<(Moore, Vec<(u16, u16)>), MachineError> {
+        validate_outputs([initial_output].into_iter(), mealy.outputs())?;
+
+        let mut state_of_pair = HashMap::from([((0, initial_output), 0)]);
+        let mut pairs = vec![(0, initial_output)];
+        let mut transition_table = vec![];
+        let mut q = VecDeque::from([0]);
+        while let Some(index) = q.pop_front() {
+            let (state, _) = pairs[index];
+            for c in 0..mealy.chars() {
+                let pair = mealy.transition(state, c);
+                let next_state = match state_of_pair.get(&pair) {
+                    Some(&next_state) => next_state,
+                    None => {
+                        if pairs.len() >= u16::MAX as usize {
+                            return Err(MachineError::TooManyStates);
+                        }
+                        let next_state = pairs.len() as u16;
+                        state_of_pair.insert(pair, next_state);
+                        pairs.push(pair);
+                        q.push_back(next_state as usize);
+                        next_state
+                    }
+                };
+                transition_table.push(next_state);
+            }
+        }
+
+        let output_table = pairs.iter().map(|&(_, output)| output).collect();
+        let moore = Moore::build(
+            transition_table,
+            output_table,
+            pairs.len() as u16,
+            mealy.chars(),
+            mealy.outputs(),
+        )
+        .expect("The Moore machine should always be valid");
+        Ok((moore, pairs))
+    }
+
+    /// Checks if both machines write the same output for every input. Returns `None` when they
+    /// do, and otherwise the shortest, and among those the lexicographically smallest, input they
+    /// write different outputs for. Both machines must have the same number of characters.
+    pub fn equivalent(&self, other: &Moore) -> Result<Option<Vec<u16>>, MachineError> {
+        if self.chars != other.chars {
+            return Err(MachineError::AlphabetMismatch {
+                first: self.chars,
+                second: other.chars,
+            });
+        }
+        if self.output_table[0] != other.output_table[0] {
+            return Ok(Some(vec![]));
+        }
+        // Without the output of the start states, the outputs are those of the Mealy machines
+        Mealy::from_moore(self).equivalent(&Mealy::from_moore(other))
+    }
+
+    /// Merges the states that write the same output for every input after removing every state
+    /// that cannot be reached from the start state. The states are numbered and returned in the
+    /// same way as [`Mealy::minimize`].
+    pub fn minimize(&self) -> (Moore, Vec<BTreeSet<u16>>) {
+        let (renumbered, partition) =
+            minimal_partition(self, |state| self.output_table[state as usize]);
+
+        let representatives: Vec<u16> = partition
+            .iter()
+            .map(|states| *states.first().expect("Every class holds a state"))
+            .collect();
+        let transition_table = representatives
+            .iter()
+            .flat_map(|&state| (0..self.chars).map(move |c| self.next_state(state, c)))
+            .map(|state| renumbered[state as usize].expect("Targets of reachable states"))
+            .collect();
+        let output_table = representatives
+            .iter()
+            .map(|&state| self.output_table[state as usize])
+            .collect();
+        let moore = Moore::build(
+            transition_table,
+            output_table,
+            partition.len() as u16,
+            self.chars,
+            self.outputs,
+        )
+        .expect("The minimized Moore machine should always be valid");
+        (moore, partition)
+    }
+
+    pub fn transition_table(&self) -> &[u16] {
+        &self.transition_table
+    }
+
+    /// The output symbol of every state, indexed by state
+    pub fn output_table(&self) -> &[u16] {
+        &self.output_table
+    }
+}
+
+impl TryFrom<&Mealy> for Moore {
+    type Error = MachineError;
+
+    /// Converts with an initial output of 0, see [`Moore::from_mealy`]. Fails when the `Moore`
+    /// machine would need more than `u16::MAX` states.
+    fn try_from(value: &Mealy) -> Result<Self, Self::Error> {
+        Ok(Moore::from_mealy(value, 0)?.0)
+    }
+}
+
+impl Transducer for Moore {
+    fn transduce_validated(&self, input: &[u16]) -> Vec<u16> {
+        self.trace_states_validated(input)
+            .into_iter()
+            .map(|state| self.output_table[state as usize])
+            .collect()
+    }
+
+    fn next_state(&self, state: u16, char: u16) -> u16 {
+        self.transition_table[table_lookup(state as usize, char as usize, self.chars as usize)]
+    }
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn chars(&self) -> u16 {
+        self.chars
+    }
+
+    fn outputs(&self) -> u16 {
+        self.outputs
+    }
+}
+
+#[cfg(test)]
+mod moore_tests {
+    use super::*;
+    use crate::machine_utils::all_inputs;
+
+    // Writes the parity of the 1s read so far
+    fn parity() -> Moore {
+        Moore::build(vec![0, 1, 1, 0], vec![0, 1], 2, 2, 2).unwrap()
+    }
+
+    // Writes the number of characters read so far modulo 3
+    fn count_mod_three() -> Moore {
+        Moore::build(vec![1, 2, 0], vec![0, 1, 2], 3, 1, 3).unwrap()
+    }
+
+    #[test]
+    fn transduce() {
+        assert_eq!(parity().transduce(&[]).unwrap(), vec![0]);
+        assert_eq!(
+            count_mod_three().transduce(&[0; 4]).unwrap(),
+            vec![0, 1, 2, 0, 1]
+        );
+        assert_eq!(
+            count_mod_three().trace_states(&[0, 0]).unwrap(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            Moore::build(vec![0, 0], vec![0, 0], 1, 2, 1).unwrap_err(),
+            MachineError::WrongTableLength {
+                expected: 1,
+                actual: 2
+            }
+        );
+        assert_eq!(
+            Moore::build(vec![0], vec![1], 1, 1, 1).unwrap_err(),
+            MachineError::OutputOutOfRange {
+                output: 1,
+                outputs: 1
+            }
+        );
+        assert_eq!(
+            Moore::build(vec![], vec![], 0, 1, 1).unwrap_err(),
+            MachineError::NoStates
+        );
+    }
+
+    #[test]
+    fn minimize() {
+        // Parity with the even states 0 and 3, the odd states 1 and 2 and an unreachable state 4
+        let redundant = Moore::build(
+            vec![3, 1, 2, 0, 1, 3, 0, 2, 4, 4],
+            vec![0, 1, 1, 0, 1],
+            5,
+            2,
+            2,
+        )
+        .unwrap();
+        let (minimal, partition) = redundant.minimize();
+
+        assert_eq!(minimal, parity());
+        assert_eq!(
+            partition,
+            vec![BTreeSet::from([0, 3]), BTreeSet::from([1, 2])]
+        );
+        assert_eq!(redundant.equivalent(&minimal), Ok(None));
+        assert_eq!(count_mod_three().minimize().0, count_mod_three());
+    }
+
+    #[test]
+    fn equivalence() {
+        let flipped = Moore::build(vec![0, 1, 1, 0], vec![1, 0], 2, 2, 2).unwrap();
+        // Writes 0 until the first 1 and then 1 forever
+        let seen_one = Moore::build(vec![0, 1, 1, 1], vec![0, 1], 2, 2, 2).unwrap();
+
+        assert_eq!(parity().equivalent(&flipped), Ok(Some(vec![])));
+        assert_eq!(parity().equivalent(&seen_one), Ok(Some(vec![1, 1])));
+        assert_eq!(
+            parity().equivalent(&count_mod_three()),
+            Err(MachineError::AlphabetMismatch {
+                first: 2,
+                second: 1
+            })
+        );
+    }
+
+    #[test]
+    fn too_many_pairs() {
+        // Reading c in state s moves to state c and writes s, so every pair is reachable
+        let transition_table = (0..256)
+            .flat_map(|state| (0..256).map(move |c| (c, state)))
+            .collect();
+        let mealy = Mealy::build(transition_table, 256, 256, 256).unwrap();
+
+        assert_eq!(
+            Moore::try_from(&mealy).unwrap_err(),
+            MachineError::TooManyStates
+        );
+    }
+
+    #[test]
+    fn mealy_round_trip() {
+        for moore in [parity(), count_mod_three()] {
+            let mealy = Mealy::from_moore(&moore);
+            let (back, _) = Moore::from_mealy(&mealy, moore.output_table()[0]).unwrap();
+
+            for input in all_inputs(moore.chars(), 6) {
+                let output = moore.transduce(&input).unwrap();
+                assert_eq!(mealy.transduce(&input).unwrap(), output[1..]);
+                assert_eq!(back.transduce(&input).unwrap(), output);
+            }
+            assert_eq!(back.minimize().0, moore.minimize().0);
+        }
+        assert_eq!(
+            Moore::try_from(&Mealy::from_moore(&parity()))
+                .unwrap()
+                .minimize()
+                .0,
+            parity()
+        );
+        assert_eq!(
+            Moore::from_mealy(&Mealy::from_moore(&parity()), 2).unwrap_err(),
+            MachineError::OutputOutOfRange {
+                output: 2,
+                outputs: 2
+            }
+        );
+    }
+}
@@ -1,5 +1,47 @@
+use crate::TapeMovement;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SingleChar {
     pub start: u16,
     pub end: u16,
     pub char: u16,
 }
+
+/// A transition that either reads `char` or, when `char` is `None`, is an epsilon transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SingleCharOrEpsilon {
+    pub start: u16,
+    pub end: u16,
+    pub char: Option<u16>,
+}
+
+/// A pushdown automaton transition. `char` and `pop` are `None` when the transition does not read
+/// the input or does not pop the stack. The first symbol of `push` ends up on top of the stack.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PdaTransition {
+    pub start: u16,
+    pub end: u16,
+    pub char: Option<u16>,
+    pub pop: Option<u16>,
+    pub push: Vec<u16>,
+}
+
+/// A Turing machine transition taken when the head reads `read` in `start`. The symbol in
+/// `movement`, if any, is written before the head moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TmTransition {
+    pub start: u16,
+    pub end: u16,
+    pub read: u16,
+    pub movement: TapeMovement,
+}
+
+/// A multi-tape Turing machine transition taken when the heads read `read` in `start`, with one
+/// symbol and one movement for each tape
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MultiTmTransition {
+    pub start: u16,
+    pub end: u16,
+    pub read: Vec<u16>,
+    pub movements: Vec<TapeMovement>,
+}